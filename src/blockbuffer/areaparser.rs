@@ -0,0 +1,581 @@
+use blockbuffer::runtime::{Level, ROWS};
+use rom::{AreaType, Rom};
+
+// Areas have at most 32 pages, AreaObjectPageLoc is 5 bits
+const MAX_PAGES: u8 = 0x20;
+const END_OF_AREA: u8 = 0xfd;
+const NUM_AREA_OBJECTS: usize = 3;
+const NO_LENGTH: u8 = 0xff;
+
+// Metatile tables of the area parser
+const BLOCK_BUFF_LOW_BOUNDS: [u8; 4] = [0x10, 0x51, 0x88, 0xc0];
+const FSCENE_DATA_OFFSETS: [usize; 3] = [0x00, 0x0d, 0x1a];
+const FORE_SCENERY_DATA: [u8; 39] = [
+    0x86, 0x87, 0x87, 0x87, 0x87, 0x87, 0x87, 0x87, 0x87, 0x87, 0x87, 0x69, 0x69, // in water
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x45, 0x47, 0x47, 0x47, 0x47, 0x47, 0x00, 0x00, // wall
+    0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x86, 0x87]; // over water
+const TERRAIN_METATILES: [u8; 4] = [0x69, 0x54, 0x52, 0x62];
+const TERRAIN_RENDER_BITS: [u8; 32] = [
+    0b00000000, 0b00000000, // no ceiling or floor
+    0b00000000, 0b00011000, // no ceiling, floor 2
+    0b00000001, 0b00011000, // ceiling 1, floor 2
+    0b00000111, 0b00011000, // ceiling 3, floor 2
+    0b00001111, 0b00011000, // ceiling 4, floor 2
+    0b11111111, 0b00011000, // ceiling 8, floor 2
+    0b00000001, 0b00011111, // ceiling 1, floor 5
+    0b00000111, 0b00011111, // ceiling 3, floor 5
+    0b00001111, 0b00011111, // ceiling 4, floor 5
+    0b10000001, 0b00011111, // ceiling 1, floor 6
+    0b00000001, 0b00000000, // ceiling 1, no floor
+    0b10001111, 0b00011111, // ceiling 4, floor 6
+    0b11110001, 0b00011111, // ceiling 1, floor 9
+    0b11111001, 0b00011000, // ceiling 1, middle 5, floor 2
+    0b11110001, 0b00011000, // ceiling 1, middle 4, floor 2
+    0b11111111, 0b00011111]; // completely solid top to bottom
+const CLOUD_TERRAIN_METATILE: u8 = 0x88;
+const WORLD8_WATER_TERRAIN_METATILE: u8 = 0x62;
+const SOLID_BLOCK_METATILES: [u8; 4] = [0x69, 0x61, 0x61, 0x62];
+const BRICK_METATILES: [u8; 5] = [0x22, 0x51, 0x52, 0x52, 0x88]; // the last one only for rows of bricks in cloud areas
+const COIN_METATILE_DATA: [u8; 4] = [0xc3, 0xc2, 0xc2, 0xc2];
+const HOLE_METATILES: [u8; 4] = [0x87, 0x00, 0x00, 0x00];
+const BRICK_QBLOCK_METATILES: [u8; 14] = [
+    0xc1, 0xc0, 0x5f, 0x60, // question blocks
+    0x55, 0x56, 0x57, 0x58, 0x59, // bricks in ground areas
+    0x5a, 0x5b, 0x5c, 0x5d, 0x5e]; // bricks in other areas
+const VERTICAL_PIPE_DATA: [u8; 8] = [0x11, 0x10, 0x15, 0x14, 0x13, 0x12, 0x15, 0x14];
+const SIDE_PIPE_SHAFT_DATA: [u8; 4] = [0x15, 0x14, 0x00, 0x00];
+const SIDE_PIPE_TOP_PART: [u8; 4] = [0x15, 0x1e, 0x1d, 0x1c];
+const SIDE_PIPE_BOTTOM_PART: [u8; 4] = [0x15, 0x21, 0x20, 0x1f];
+const CASTLE_METATILES: [u8; 55] = [
+    0x00, 0x45, 0x45, 0x45, 0x00,
+    0x00, 0x48, 0x47, 0x46, 0x00,
+    0x45, 0x49, 0x49, 0x49, 0x45,
+    0x47, 0x47, 0x4a, 0x47, 0x47,
+    0x47, 0x47, 0x4b, 0x47, 0x47,
+    0x49, 0x49, 0x49, 0x49, 0x49,
+    0x47, 0x4a, 0x47, 0x4a, 0x47,
+    0x47, 0x4b, 0x47, 0x4b, 0x47,
+    0x47, 0x47, 0x47, 0x47, 0x47,
+    0x4a, 0x47, 0x4a, 0x47, 0x4a,
+    0x4b, 0x47, 0x4b, 0x47, 0x4b];
+const STAIRCASE_HEIGHT_DATA: [u8; 9] = [0x07, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01, 0x00];
+const STAIRCASE_ROW_DATA: [usize; 9] = [0x03, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a];
+const PULLEY_ROPE_METATILES: [u8; 3] = [0x42, 0x41, 0x43];
+const C_OBJECT_ROW: [usize; 3] = [0x06, 0x07, 0x08]; // axe, chain, castle bridge
+const C_OBJECT_METATILE: [u8; 3] = [0xc5, 0x0c, 0x89];
+
+/// Renders the block buffer of a whole area from its area object data, porting AreaParserCore column by column
+/// starting at page 0. Background scenery is not rendered, its metatiles are all below BlockBuffLowBounds and never
+/// reach the block buffer. Enemies, frenzies, scroll locks and loop commands don't affect the blocks and are skipped,
+/// and the hidden 1-up block is rendered as if Hidden1UpFlag was set.
+struct AreaParser<'a> {
+  area_data: &'a [u8], // after the header
+  world_number: usize,
+  area_type: AreaType,
+  // header
+  foreground_scenery: u8,
+  terrain_control: u8,
+  area_style: u8,
+  cloud_type_override: bool,
+  // parser state
+  area_data_offset: usize,
+  area_object_page_loc: u8,
+  area_object_page_sel: bool,
+  area_object_length: [u8; NUM_AREA_OBJECTS],
+  area_obj_offset_buffer: [usize; NUM_AREA_OBJECTS],
+  mushroom_ledge_half_len: [u8; NUM_AREA_OBJECTS],
+  behind_area_parser_flag: bool,
+  staircase_control: u8,
+  hidden_1up_flag: bool,
+  current_page_loc: u8,
+  current_column_pos: u8,
+  metatile_buffer: [u8; ROWS],
+  warnings: Vec<String>,
+}
+impl<'a> AreaParser<'a> {
+  fn new(area_data: &'a [u8], world_number: usize, area_type: AreaType) -> AreaParser<'a> {
+    let (header0, header1) = (area_data[0], area_data[1]);
+    let style = header1 >> 6;
+    AreaParser {
+      area_data: &area_data[2..],
+      world_number,
+      area_type,
+      foreground_scenery: if header0 & 0x7 < 4 { header0 & 0x7 } else { 0 }, // otherwise background color control
+      terrain_control: header1 & 0xf,
+      area_style: if style == 3 { 0 } else { style },
+      cloud_type_override: style == 3,
+      area_data_offset: 0,
+      area_object_page_loc: 0,
+      area_object_page_sel: false,
+      area_object_length: [NO_LENGTH; NUM_AREA_OBJECTS],
+      area_obj_offset_buffer: [0; NUM_AREA_OBJECTS],
+      mushroom_ledge_half_len: [0; NUM_AREA_OBJECTS],
+      behind_area_parser_flag: false,
+      staircase_control: 0,
+      hidden_1up_flag: true,
+      current_page_loc: 0,
+      current_column_pos: 0,
+      metatile_buffer: [0; ROWS],
+      warnings: vec![],
+    }
+  }
+
+  fn data(&self, offset: usize) -> u8 {
+    self.area_data[offset]
+  }
+  fn is_done(&self) -> bool {
+    self.data(self.area_data_offset) == END_OF_AREA && self.area_object_length.iter().all(|&l| l == NO_LENGTH)
+  }
+
+  fn render(mut self) -> (Level, Vec<String>) {
+    let mut columns = vec![];
+    loop {
+      columns.push(self.render_column());
+      self.current_column_pos = (self.current_column_pos + 1) & 0xf;
+      if self.current_column_pos == 0 {
+        self.current_page_loc += 1;
+        if self.is_done() || self.current_page_loc >= MAX_PAGES { break; }
+      }
+    }
+    (Level { area_type: self.area_type, columns }, self.warnings)
+  }
+
+  /// AreaParserCore: renders foreground scenery, terrain and area objects of the current column and stores them into
+  /// the block buffer.
+  fn render_column(&mut self) -> [u8; ROWS] {
+    self.metatile_buffer = [0; ROWS];
+    if self.foreground_scenery > 0 {
+      let offset = FSCENE_DATA_OFFSETS[self.foreground_scenery as usize - 1];
+      for (i, &metatile) in FORE_SCENERY_DATA[offset..offset + ROWS].iter().enumerate() {
+        if metatile != 0 { self.metatile_buffer[i] = metatile; }
+      }
+    }
+    self.render_terrain();
+    self.process_area_data();
+    let mut column = [0; ROWS];
+    for (block, &metatile) in column.iter_mut().zip(self.metatile_buffer.iter()) {
+      if metatile >= BLOCK_BUFF_LOW_BOUNDS[(metatile >> 6) as usize] { *block = metatile; }
+    }
+    column
+  }
+  fn render_terrain(&mut self) {
+    let mut metatile = if self.area_type == AreaType::Water && self.world_number == 7 { WORLD8_WATER_TERRAIN_METATILE }
+        else if self.cloud_type_override { CLOUD_TERRAIN_METATILE }
+        else { TERRAIN_METATILES[self.area_type.index()] };
+    let mut row = 0;
+    for &render_bits in TERRAIN_RENDER_BITS[self.terrain_control as usize * 2..self.terrain_control as usize * 2 + 2].iter() {
+      let render_bits = if self.cloud_type_override && row != 0 { render_bits & 0x08 } else { render_bits };
+      for bit in 0..8 {
+        if render_bits & (1 << bit) != 0 { self.metatile_buffer[row] = metatile; }
+        row += 1;
+        if row == ROWS { return; }
+        if self.area_type == AreaType::Underground && row == 0x0b { metatile = TERRAIN_METATILES[AreaType::Ground.index()]; }
+      }
+    }
+  }
+
+  fn process_area_data(&mut self) {
+    loop {
+      for x in (0..NUM_AREA_OBJECTS).rev() {
+        self.behind_area_parser_flag = false;
+        let offset = self.area_data_offset;
+        if self.data(offset) != END_OF_AREA && self.area_object_length[x] == NO_LENGTH {
+          if self.data(offset + 1) & 0x80 != 0 && !self.area_object_page_sel {
+            self.area_object_page_sel = true;
+            self.area_object_page_loc += 1;
+          }
+          if self.data(offset) & 0xf == 0x0d && self.data(offset + 1) & 0x40 == 0 && !self.area_object_page_sel {
+            // page control object
+            self.area_object_page_loc = self.data(offset + 1) & 0x1f;
+            self.inc_area_obj_offset();
+          } else if self.area_object_page_loc < self.current_page_loc {
+            self.behind_area_parser_flag = true;
+            self.inc_area_obj_offset();
+          } else {
+            self.decode_area_data(x);
+          }
+        } else {
+          self.decode_area_data(x);
+        }
+        if self.area_object_length[x] != NO_LENGTH { self.area_object_length[x] = self.area_object_length[x].wrapping_sub(1); }
+      }
+      if !self.behind_area_parser_flag { return; }
+    }
+  }
+  fn inc_area_obj_offset(&mut self) {
+    self.area_data_offset += 2;
+    self.area_object_page_sel = false;
+  }
+
+  fn decode_area_data(&mut self, x: usize) {
+    let offset = if self.area_object_length[x] != NO_LENGTH { self.area_obj_offset_buffer[x] } else { self.area_data_offset };
+    let byte0 = self.data(offset);
+    if byte0 == END_OF_AREA { return; }
+    let byte1 = self.data(offset + 1);
+    let row = byte0 & 0xf;
+    let (object_id, jump_offset) = match row {
+      0x0e => (0x00, 0x2e),
+      0x0d => {
+        if byte1 & 0x40 == 0 { return; } // page control, handled by process_area_data
+        (byte1 & 0x3f, 0x22)
+      },
+      0x0c => ((byte1 & 0x70) >> 4, 0x08),
+      0x0f => ((byte1 & 0x70) >> 4, 0x10),
+      _ if byte1 & 0x70 == 0 => (byte1 & 0xf, 0x16),
+      _ if byte1 & 0x70 == 0x70 && byte1 & 0x08 != 0 => (0x00, 0x00), // warp pipe
+      _ => ((byte1 & 0x70) >> 4, 0x00),
+    };
+    if self.area_object_length[x] == NO_LENGTH {
+      if self.area_object_page_loc != self.current_page_loc { return; }
+      if self.data(self.area_data_offset) >> 4 != self.current_column_pos { return; }
+      self.area_obj_offset_buffer[x] = self.area_data_offset;
+      self.inc_area_obj_offset();
+    }
+    self.run_area_object(x, object_id, object_id + jump_offset);
+  }
+  fn run_area_object(&mut self, x: usize, object_id: u8, jump_index: u8) {
+    match jump_index {
+      // large objects
+      0x00 | 0x07 => self.vertical_pipe(x, object_id),
+      0x01 => self.area_style_object(x),
+      0x02 => {
+        let metatile = BRICK_METATILES[if self.cloud_type_override { 4 } else { self.area_type.index() }];
+        self.row_object(x, metatile);
+      },
+      0x03 => { let metatile = SOLID_BLOCK_METATILES[self.area_type.index()]; self.row_object(x, metatile); },
+      0x04 => { let metatile = COIN_METATILE_DATA[self.area_type.index()]; self.row_object(x, metatile); },
+      0x05 => { let metatile = BRICK_METATILES[self.area_type.index()]; self.column_object(x, metatile); },
+      0x06 => { let metatile = SOLID_BLOCK_METATILES[self.area_type.index()]; self.column_object(x, metatile); },
+      // row 12
+      0x08 => self.hole_empty(x),
+      0x09 => self.pulley_rope_object(x),
+      0x0a => self.bridge(x, 0x06),
+      0x0b => self.bridge(x, 0x07),
+      0x0c => self.bridge(x, 0x09),
+      0x0d => self.hole_water(x),
+      0x0e => self.question_block_row(x, 0x03),
+      0x0f => self.question_block_row(x, 0x07),
+      // row 15
+      0x10 => { self.render_under_part(0x00, 0x0f, 0x40); }, // EndlessRope
+      0x11 => self.balance_plat_rope(x),
+      0x12 => self.castle_object(x),
+      0x13 => self.staircase_object(x),
+      0x14 => self.exit_pipe(x),
+      0x15 => { let (_, length) = self.get_lrg_obj_attrib(x); self.render_under_part(0x02, length, 0x6d); }, // FlagBalls_Residual
+      // small objects
+      0x16 ..= 0x18 => self.question_block(x, object_id),
+      0x19 => if self.hidden_1up_flag {
+        self.hidden_1up_flag = false;
+        self.brick_with_item(x, object_id);
+      },
+      0x1a ..= 0x1e => self.brick_with_item(x, object_id),
+      0x1f => self.two_part_object(x, 0x6b, 0x6c), // WaterPipe
+      0x20 => { let (row, _) = self.get_lrg_obj_attrib(x); self.render_under_part(row, 0x00, 0xc4); }, // EmptyBlock
+      0x21 => self.two_part_object(x, 0x67, 0x68), // Jumpspring
+      // row 13
+      0x22 => self.intro_pipe(x),
+      0x23 => self.flagpole_object(),
+      0x24 | 0x25 => self.chain_obj(object_id),
+      0x26 => { self.chk_lrg_obj_fixed_length(x, 0x0c); self.chain_obj(object_id); }, // CastleBridgeObj
+      0x27 ..= 0x2d => {}, // scroll locks, area frenzies and loop command
+      // row 14
+      0x2e => self.alter_area_attributes(x),
+      _ => self.warnings.push(format!("unknown area object {:#04x} at offset {}", jump_index, self.area_obj_offset_buffer[x])),
+    }
+  }
+
+  fn set_metatile(&mut self, row: usize, metatile: u8) {
+    if row < ROWS { self.metatile_buffer[row] = metatile; }
+  }
+  /// RenderUnderPart: renders the metatile from the given row downwards, keeping some metatiles already in place.
+  /// Returns the row after the last one rendered.
+  fn render_under_part(&mut self, mut row: usize, mut height: u8, metatile: u8) -> usize {
+    while row < ROWS {
+      let draw = match self.metatile_buffer[row] {
+        0x17 | 0x1a => false, // middle of tree and mushroom ledges
+        0xc0 => true,
+        cur if cur > 0xc0 => false,
+        0x54 => metatile != 0x50, // mushroom stems don't replace ground
+        _ => true,
+      };
+      if draw { self.metatile_buffer[row] = metatile; }
+      row += 1;
+      height = height.wrapping_sub(1);
+      if (height as i8) < 0 { break; }
+    }
+    row
+  }
+  /// GetLrgObjAttrib: row and length or height of the object.
+  fn get_lrg_obj_attrib(&self, x: usize) -> (usize, u8) {
+    let offset = self.area_obj_offset_buffer[x];
+    ((self.data(offset) & 0xf) as usize, self.data(offset + 1) & 0xf)
+  }
+  /// ChkLrgObjFixedLength: sets the length of a new object, returns whether it's just starting.
+  fn chk_lrg_obj_fixed_length(&mut self, x: usize, length: u8) -> bool {
+    if self.area_object_length[x] != NO_LENGTH { return false; }
+    self.area_object_length[x] = length;
+    true
+  }
+  fn chk_lrg_obj_length(&mut self, x: usize) -> (usize, u8, bool) {
+    let (row, length) = self.get_lrg_obj_attrib(x);
+    (row, length, self.chk_lrg_obj_fixed_length(x, length))
+  }
+
+  fn area_style_object(&mut self, x: usize) {
+    match self.area_style {
+      0 => self.tree_ledge(x),
+      1 => self.mushroom_ledge(x),
+      _ => self.bullet_bill_cannon(x),
+    }
+  }
+  fn tree_ledge(&mut self, x: usize) {
+    let (row, length) = self.get_lrg_obj_attrib(x);
+    if self.area_object_length[x] == 0 {
+      self.render_under_part(row, 0x00, 0x18);
+    } else if self.area_object_length[x] == NO_LENGTH && (self.current_page_loc != 0 || self.current_column_pos != 0) {
+      self.area_object_length[x] = length;
+      self.render_under_part(row, 0x00, 0x16);
+    } else {
+      if self.area_object_length[x] == NO_LENGTH { self.area_object_length[x] = length; }
+      self.set_metatile(row, 0x17);
+      self.render_under_part(row + 1, 0x0f, 0x4c); // trunk
+    }
+  }
+  fn mushroom_ledge(&mut self, x: usize) {
+    let (row, _, started) = self.chk_lrg_obj_length(x);
+    if started {
+      self.mushroom_ledge_half_len[x] = self.area_object_length[x] >> 1;
+      self.render_under_part(row, 0x00, 0x19);
+    } else if self.area_object_length[x] == 0 {
+      self.render_under_part(row, 0x00, 0x1b);
+    } else {
+      self.set_metatile(row, 0x1a);
+      if self.area_object_length[x] == self.mushroom_ledge_half_len[x] {
+        self.set_metatile(row + 1, 0x4f);
+        self.render_under_part(row + 2, 0x0f, 0x50); // stem
+      }
+    }
+  }
+  fn bullet_bill_cannon(&mut self, x: usize) {
+    let (row, length) = self.get_lrg_obj_attrib(x);
+    self.set_metatile(row, 0x64);
+    if length == 0 { return; }
+    self.set_metatile(row + 1, 0x65);
+    if length == 1 { return; }
+    self.render_under_part(row + 2, length - 2, 0x66);
+  }
+  /// RowOfBricks, RowOfSolidBlocks and RowOfCoins.
+  fn row_object(&mut self, x: usize, metatile: u8) {
+    let (row, _, _) = self.chk_lrg_obj_length(x);
+    self.render_under_part(row, 0x00, metatile);
+  }
+  /// ColumnOfBricks and ColumnOfSolidBlocks.
+  fn column_object(&mut self, x: usize, metatile: u8) {
+    let (row, height) = self.get_lrg_obj_attrib(x);
+    self.render_under_part(row, height, metatile);
+  }
+  fn vertical_pipe(&mut self, x: usize, object_id: u8) {
+    self.chk_lrg_obj_fixed_length(x, 0x01);
+    let (row, height) = self.get_lrg_obj_attrib(x);
+    let index = self.area_object_length[x] as usize + if object_id != 0 { 4 } else { 0 }; // decoration pipes
+    self.set_metatile(row, VERTICAL_PIPE_DATA[index]);
+    self.render_under_part(row + 1, (height & 0x7).wrapping_sub(1), VERTICAL_PIPE_DATA[index + 2]);
+  }
+  fn two_part_object(&mut self, x: usize, top: u8, bottom: u8) {
+    let (row, _) = self.get_lrg_obj_attrib(x);
+    self.set_metatile(row, top);
+    self.set_metatile(row + 1, bottom);
+  }
+
+  fn hole_empty(&mut self, x: usize) {
+    self.chk_lrg_obj_length(x);
+    self.render_under_part(0x08, 0x0f, HOLE_METATILES[self.area_type.index()]);
+  }
+  fn hole_water(&mut self, x: usize) {
+    self.chk_lrg_obj_length(x);
+    self.set_metatile(0x0a, 0x86); // waves
+    self.render_under_part(0x0b, 0x01, 0x87);
+  }
+  fn pulley_rope_object(&mut self, x: usize) {
+    let (_, _, started) = self.chk_lrg_obj_length(x);
+    let index = if started { 0 } else if self.area_object_length[x] != 0 { 1 } else { 2 };
+    self.set_metatile(0, PULLEY_ROPE_METATILES[index]);
+  }
+  fn bridge(&mut self, x: usize, row: usize) {
+    self.chk_lrg_obj_length(x);
+    self.set_metatile(row, 0x0b); // railing
+    self.render_under_part(row + 1, 0x00, 0x63);
+  }
+  fn question_block_row(&mut self, x: usize, row: usize) {
+    self.chk_lrg_obj_length(x);
+    self.set_metatile(row, 0xc0);
+  }
+  fn balance_plat_rope(&mut self, x: usize) {
+    self.render_under_part(0x01, 0x0f, 0x44);
+    let (_, height) = self.get_lrg_obj_attrib(x);
+    self.render_under_part(0x01, height, 0x40);
+  }
+  fn castle_object(&mut self, x: usize) {
+    let (_, start_row) = self.get_lrg_obj_attrib(x);
+    let start_row = start_row as usize;
+    self.chk_lrg_obj_fixed_length(x, 0x04);
+    let mut index = self.area_object_length[x] as usize;
+    let mut row = start_row;
+    let mut rows_left = 0x0b;
+    loop {
+      self.set_metatile(row, CASTLE_METATILES[index]);
+      row += 1;
+      if rows_left > 0 {
+        index += 5;
+        rows_left -= 1;
+      }
+      if row >= 0x0b { break; }
+    }
+    if self.current_page_loc == 0 { return; }
+    let length = self.area_object_length[x];
+    if length == 1 || (start_row == 0 && length == 3) {
+      self.set_metatile(0x0a, 0x52); // PlayerStop: brick at the floor to stop the player at the end of the level
+    }
+  }
+  fn staircase_object(&mut self, x: usize) {
+    let (_, _, started) = self.chk_lrg_obj_length(x);
+    if started { self.staircase_control = 0x09; }
+    self.staircase_control = self.staircase_control.wrapping_sub(1);
+    let step = self.staircase_control as usize;
+    if step < STAIRCASE_ROW_DATA.len() {
+      self.render_under_part(STAIRCASE_ROW_DATA[step], STAIRCASE_HEIGHT_DATA[step], 0x61);
+    }
+  }
+  fn exit_pipe(&mut self, x: usize) {
+    self.chk_lrg_obj_fixed_length(x, 0x03);
+    let (_, height) = self.get_lrg_obj_attrib(x);
+    self.render_sideways_pipe(x, height);
+  }
+  /// RenderSidewaysPipe, returns whether the vertical shaft was not drawn.
+  fn render_sideways_pipe(&mut self, x: usize, height: u8) -> bool {
+    let shaft_height = height.wrapping_sub(2);
+    let part = self.area_object_length[x] as usize;
+    let shaft = SIDE_PIPE_SHAFT_DATA[part];
+    let row = if shaft == 0 { shaft_height.wrapping_add(1) as usize } else { self.render_under_part(0x00, shaft_height, shaft) };
+    self.set_metatile(row, SIDE_PIPE_TOP_PART[part]);
+    self.set_metatile(row + 1, SIDE_PIPE_BOTTOM_PART[part]);
+    shaft == 0
+  }
+  fn intro_pipe(&mut self, x: usize) {
+    self.chk_lrg_obj_fixed_length(x, 0x03);
+    if !self.render_sideways_pipe(x, 0x0a) {
+      for row in 0..=6 { self.metatile_buffer[row] = 0; }
+      self.metatile_buffer[7] = VERTICAL_PIPE_DATA[self.area_object_length[x] as usize];
+    }
+  }
+  fn flagpole_object(&mut self) {
+    self.set_metatile(0x00, 0x24); // ball
+    self.render_under_part(0x01, 0x08, 0x25);
+    self.set_metatile(0x0a, 0x61);
+  }
+  /// AxeObj, ChainObj and the bridge of CastleBridgeObj.
+  fn chain_obj(&mut self, object_id: u8) {
+    let index = object_id as usize - 2;
+    self.render_under_part(C_OBJECT_ROW[index], 0x00, C_OBJECT_METATILE[index]);
+  }
+
+  fn question_block(&mut self, x: usize, object_id: u8) {
+    let (row, _) = self.get_lrg_obj_attrib(x);
+    self.render_under_part(row, 0x00, BRICK_QBLOCK_METATILES[object_id as usize]);
+  }
+  /// BrickWithItem, also used for bricks with coins and hidden 1-up blocks.
+  fn brick_with_item(&mut self, x: usize, object_id: u8) {
+    self.question_block(x, object_id + if self.area_type == AreaType::Ground { 0 } else { 5 });
+  }
+
+  fn alter_area_attributes(&mut self, x: usize) {
+    let attributes = self.data(self.area_obj_offset_buffer[x] + 1);
+    if attributes & 0x40 == 0 {
+      self.terrain_control = attributes & 0xf; // background scenery is not rendered
+    } else {
+      self.foreground_scenery = if attributes & 0x7 < 4 { attributes & 0x7 } else { 0 };
+    }
+  }
+}
+
+/// Block buffer of the area with the given area pointer, decoded from the area object data in the ROM, with warnings
+/// for area objects that are not rendered. The world number (0-based) selects the terrain of the water area in world 8.
+pub fn decode_area(rom: &Rom, world_number: usize, area_pointer: u8) -> (Level, Vec<String>) {
+  let area_type = AreaType::from_area_pointer(area_pointer);
+  decode_area_data(rom.bytes_from(rom.area_data_addr(area_pointer)), world_number, area_type)
+}
+/// Block buffer of an area from its area object data, starting with the 2 byte header and ending with $fd.
+fn decode_area_data(area_data: &[u8], world_number: usize, area_type: AreaType) -> (Level, Vec<String>) {
+  AreaParser::new(area_data, world_number, area_type).render()
+}
+
+/// Decodes an area of a world (both 0-based) from the ROM file, e.g. for a runtime::LevelSource.
+pub fn decode_rom_area(rom_path: &str, world_number: usize, area_number: usize) -> (Level, Vec<String>) {
+  let rom = Rom::load(rom_path);
+  let area_pointer = rom.area_pointer(world_number, area_number);
+  decode_area(&rom, world_number, area_pointer)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use blockbuffer::BlockBuffer;
+  use blockbuffer::world1::{BB11, BB12};
+
+  // Area object data of 1-1 (L_GroundArea6) and 1-2 (L_UndergroundArea1)
+  const W11_AREA_DATA: [u8; 101] = [
+      0x50, 0x21,
+      0x07, 0x81, 0x47, 0x24, 0x57, 0x00, 0x63, 0x01, 0x77, 0x01,
+      0xc9, 0x71, 0x68, 0xf2, 0xe7, 0x73, 0x97, 0xfb, 0x06, 0x83,
+      0x5c, 0x01, 0xd7, 0x22, 0xe7, 0x00, 0x03, 0xa7, 0x6c, 0x02,
+      0xb3, 0x22, 0xe3, 0x01, 0xe7, 0x07, 0x47, 0xa0, 0x57, 0x06,
+      0xa7, 0x01, 0xd3, 0x00, 0xd7, 0x01, 0x07, 0x81, 0x67, 0x20,
+      0x93, 0x22, 0x03, 0xa3, 0x1c, 0x61, 0x17, 0x21, 0x6f, 0x33,
+      0xc7, 0x63, 0xd8, 0x62, 0xe9, 0x61, 0xfa, 0x60, 0x4f, 0xb3,
+      0x87, 0x63, 0x9c, 0x01, 0xb7, 0x63, 0xc8, 0x62, 0xd9, 0x61,
+      0xea, 0x60, 0x39, 0xf1, 0x87, 0x21, 0xa7, 0x01, 0xb7, 0x20,
+      0x39, 0xf1, 0x5f, 0x38, 0x6d, 0xc1, 0xaf, 0x26,
+      0xfd];
+  const W12_AREA_DATA: [u8; 163] = [
+      0x48, 0x0f,
+      0x0e, 0x01, 0x5e, 0x02, 0xa7, 0x00, 0xbc, 0x73, 0x1a, 0xe0,
+      0x39, 0x61, 0x58, 0x62, 0x77, 0x63, 0x97, 0x63, 0xb8, 0x62,
+      0xd6, 0x07, 0xf8, 0x62, 0x19, 0xe1, 0x75, 0x52, 0x86, 0x40,
+      0x87, 0x50, 0x95, 0x52, 0x93, 0x43, 0xa5, 0x21, 0xc5, 0x52,
+      0xd6, 0x40, 0xd7, 0x20, 0xe5, 0x06, 0xe6, 0x51, 0x3e, 0x8d,
+      0x5e, 0x03, 0x67, 0x52, 0x77, 0x52, 0x7e, 0x02, 0x9e, 0x03,
+      0xa6, 0x43, 0xa7, 0x23, 0xde, 0x05, 0xfe, 0x02, 0x1e, 0x83,
+      0x33, 0x54, 0x46, 0x40, 0x47, 0x21, 0x56, 0x04, 0x5e, 0x02,
+      0x83, 0x54, 0x93, 0x52, 0x96, 0x07, 0x97, 0x50, 0xbe, 0x03,
+      0xc7, 0x23, 0xfe, 0x02, 0x0c, 0x82, 0x43, 0x45, 0x45, 0x24,
+      0x46, 0x24, 0x90, 0x08, 0x95, 0x51, 0x78, 0xfa, 0xd7, 0x73,
+      0x39, 0xf1, 0x8c, 0x01, 0xa8, 0x52, 0xb8, 0x52, 0xcc, 0x01,
+      0x5f, 0xb3, 0x97, 0x63, 0x9e, 0x00, 0x0e, 0x81, 0x16, 0x24,
+      0x66, 0x04, 0x8e, 0x00, 0xfe, 0x01, 0x08, 0xd2, 0x0e, 0x06,
+      0x6f, 0x47, 0x9e, 0x0f, 0x0e, 0x82, 0x2d, 0x47, 0x28, 0x7a,
+      0x68, 0x7a, 0xa8, 0x7a, 0xae, 0x01, 0xde, 0x0f, 0x6d, 0xc5,
+      0xfd];
+
+  fn assert_level_matches<B: BlockBuffer>(level: &Level, width: usize) {
+    assert!(level.width() >= width, "decoded {} columns, expected at least {}", level.width(), width);
+    let block_buffer = B::new();
+    for x in 0..width {
+      let expected: Vec<u8> = (0..ROWS).map(|y| block_buffer.get_block_at(x, y)).collect();
+      assert_eq!(level.columns[x].to_vec(), expected, "column {:#x}", x);
+    }
+  }
+
+  #[test]
+  fn decodes_w11() {
+    let (level, warnings) = decode_area_data(&W11_AREA_DATA, 0, AreaType::Ground);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_level_matches::<BB11>(&level, 13*16);
+  }
+
+  #[test]
+  fn decodes_w12() {
+    let (level, warnings) = decode_area_data(&W12_AREA_DATA, 0, AreaType::Underground);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_level_matches::<BB12>(&level, 12*16);
+  }
+}
@@ -0,0 +1,224 @@
+use blockbuffer::runtime::{Level, LevelFileError, ROWS, read_level, save_level};
+use rom::AreaType;
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+
+const RAM_SIZE: usize = 0x800;
+const BLOCK_BUFFER: usize = 0x500; // Block_Buffer_1 at 0x500-0x5cf and Block_Buffer_2 at 0x5d0-0x69f
+const BLOCK_BUFFER_SIZE: usize = 2 * PAGE_SIZE;
+const PAGE_SIZE: usize = ROWS * COLUMNS_PER_PAGE;
+const COLUMNS_PER_PAGE: usize = 16;
+const BLOCK_BUFFER_COLUMNS: usize = 2 * COLUMNS_PER_PAGE;
+// RAM addresses of the area parser's position
+const BLOCK_BUFFER_COLUMN_POS: usize = 0x6a0;
+const CURRENT_PAGE_LOC: usize = 0x725;
+const CURRENT_COLUMN_POS: usize = 0x726;
+const AREA_TYPE: usize = 0x74e;
+
+/// Columns of the level contained in a block buffer dump, starting at first_column.
+pub struct BlockBufferDump {
+  pub first_column: usize,
+  pub columns: Vec<[u8; ROWS]>,
+  pub area_type: Option<AreaType>,
+}
+impl BlockBufferDump {
+  /// Both pages of a block buffer (0x500-0x69f), with first_page being the page of the level in the lower one of
+  /// the two pages the dump covers. Even pages are at 0x500, odd ones at 0x5d0.
+  pub fn from_block_buffer(block_buffer: &[u8], first_page: usize) -> BlockBufferDump {
+    assert!(block_buffer.len() == BLOCK_BUFFER_SIZE, "block buffer has {} bytes instead of {}", block_buffer.len(), BLOCK_BUFFER_SIZE);
+    let first_column = first_page * COLUMNS_PER_PAGE;
+    let columns = (first_column..first_column + BLOCK_BUFFER_COLUMNS).map(|column| block_buffer_column(block_buffer, column)).collect();
+    BlockBufferDump { first_column, columns, area_type: None }
+  }
+  /// Block buffer of a 2KB NES RAM dump. The columns are located using the area parser's position, the oldest column
+  /// is left out as it may already be overwritten by the column currently being rendered.
+  pub fn from_ram(ram: &[u8]) -> BlockBufferDump {
+    assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+    let next_column = ram[CURRENT_PAGE_LOC] as usize * COLUMNS_PER_PAGE + ram[CURRENT_COLUMN_POS] as usize;
+    let first_column = next_column.saturating_sub(BLOCK_BUFFER_COLUMNS - 1);
+    let block_buffer = &ram[BLOCK_BUFFER..BLOCK_BUFFER + BLOCK_BUFFER_SIZE];
+    let columns = (first_column..next_column).map(|column| {
+      let buffer_column = (ram[BLOCK_BUFFER_COLUMN_POS] as usize + BLOCK_BUFFER_COLUMNS + column - next_column) % BLOCK_BUFFER_COLUMNS;
+      block_buffer_column(block_buffer, buffer_column)
+    }).collect();
+    BlockBufferDump { first_column, columns, area_type: Some(AreaType::from_index(ram[AREA_TYPE])) }
+  }
+}
+
+/// GetBlockBufferAddr: column of the block buffer, using the lower 5 bits of the column.
+fn block_buffer_column(block_buffer: &[u8], column: usize) -> [u8; ROWS] {
+  let column = column % BLOCK_BUFFER_COLUMNS;
+  let base = (column / COLUMNS_PER_PAGE) * PAGE_SIZE + column % COLUMNS_PER_PAGE;
+  let mut result = [0; ROWS];
+  for (row, block) in result.iter_mut().enumerate() {
+    *block = block_buffer[base + row * COLUMNS_PER_PAGE];
+  }
+  result
+}
+
+/// Bytes of a hex dump, ignoring whitespace, e.g. copied from an emulator's memory viewer.
+pub fn parse_hex(hex: &str) -> Vec<u8> {
+  let digits: Vec<u8> = hex.chars().filter(|c| !c.is_whitespace()).map(|c| c.to_digit(16).unwrap_or_else(|| panic!("invalid hex digit {:?}", c)) as u8).collect();
+  assert!(digits.len() % 2 == 0, "odd number of hex digits");
+  digits.chunks(2).map(|d| d[0] << 4 | d[1]).collect()
+}
+
+/// Block that differs between two dumps covering the same column.
+#[derive(Debug,Eq,PartialEq)]
+pub struct ColumnMismatch {
+  pub column: usize,
+  pub row: usize,
+  pub block: u8,
+  pub other_block: u8,
+}
+impl fmt::Display for ColumnMismatch {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "dumps differ in column {:#x} row {}: {:#04x} vs {:#04x}", self.column, self.row, self.block, self.other_block)
+  }
+}
+
+/// Stitches dumps into the columns of the whole level, starting at column 0, with warnings. Overlapping columns must
+/// match, columns not covered by any dump are left empty.
+pub fn stitch(dumps: &[BlockBufferDump]) -> Result<(Vec<[u8; ROWS]>, Vec<String>), ColumnMismatch> {
+  let width = dumps.iter().map(|d| d.first_column + d.columns.len()).max().unwrap_or(0);
+  let mut columns: Vec<Option<[u8; ROWS]>> = vec![None; width];
+  for dump in dumps.iter() {
+    for (i, column) in dump.columns.iter().enumerate() {
+      let x = dump.first_column + i;
+      if let Some(existing) = columns[x] {
+        if let Some(row) = (0..ROWS).find(|&row| existing[row] != column[row]) {
+          return Err(ColumnMismatch { column: x, row, block: existing[row], other_block: column[row] });
+        }
+      }
+      columns[x] = Some(*column);
+    }
+  }
+  let mut warnings = vec![];
+  let missing = columns.iter().filter(|c| c.is_none()).count();
+  if missing > 0 { warnings.push(format!("{} of {} columns are not covered by any dump", missing, width)); }
+  Ok((columns.into_iter().map(|c| c.unwrap_or([0; ROWS])).collect(), warnings))
+}
+
+/// blockbuf! macro invocation defining the block buffer, in the format of the blockbuffer::world* modules.
+pub fn blockbuf_source(name: &str, columns: &[[u8; ROWS]]) -> String {
+  let width = if columns.len() % COLUMNS_PER_PAGE == 0 { format!("{}*{}", columns.len() / COLUMNS_PER_PAGE, COLUMNS_PER_PAGE) } else { columns.len().to_string() };
+  let prefix = format!("blockbuf!({}, {}, [", name, width);
+  let rows: Vec<String> = (0..ROWS).map(|row| {
+    let blocks: Vec<String> = columns.iter().map(|c| if c[row] == 0 { "  0 ".to_string() } else { format!("0x{:02X}", c[row]) }).collect();
+    format!("[{}]", blocks.join(","))
+  }).collect();
+  format!("{}{}]);\n", prefix, rows.join(&format!(",\n{}", " ".repeat(prefix.len()))))
+}
+
+fn load_dump(arg: &str) -> BlockBufferDump {
+  let (path, first_page) = match arg.find('@') {
+    Some(i) => (&arg[..i], Some(arg[i + 1..].parse::<usize>().unwrap_or_else(|_| panic!("invalid page in {}", arg)))),
+    None => (arg, None),
+  };
+  let mut data = vec![];
+  File::open(path).and_then(|mut f| f.read_to_end(&mut data)).unwrap_or_else(|e| panic!("can't read {}: {}", path, e));
+  match read_level(&data) {
+    Ok(level) => return BlockBufferDump { first_column: 0, columns: level.columns, area_type: Some(level.area_type) },
+    Err(LevelFileError::BadMagic) => {},
+    Err(e) => panic!("can't load {}: {}", path, e),
+  }
+  if data.len() == RAM_SIZE {
+    BlockBufferDump::from_ram(&data)
+  } else {
+    let hex = String::from_utf8(data).unwrap_or_else(|_| panic!("{} is neither a RAM dump nor a hex dump", path));
+    BlockBufferDump::from_block_buffer(&parse_hex(&hex), first_page.unwrap_or_else(|| panic!("hex dump {} needs its first page, as {}@<page>", path, path)))
+  }
+}
+
+const USAGE: &str = "usage: bbconvert [--name <name>] [--area-type <0-3>] [--out <level file>] <dump>...
+  Each dump is either a level file, a raw 2KB RAM dump or a hex dump of the block buffer (0x500-0x69f) given as
+  <file>@<page>, with <page> the lower one of the two level pages it covers. Prints a blockbuf! snippet unless --out
+  is given.";
+
+/// bbconvert subcommand: stitches block buffer dumps into a blockbuf! snippet or a level file.
+pub fn run(args: &[String]) {
+  let mut name = "BB".to_string();
+  let mut area_type = None;
+  let mut out = None;
+  let mut dumps = vec![];
+  let mut args = args.iter();
+  while let Some(arg) = args.next() {
+    match arg.as_str() {
+      "--name" => name = args.next().expect(USAGE).clone(),
+      "--area-type" => area_type = Some(AreaType::from_index(args.next().and_then(|a| a.parse().ok()).expect(USAGE))),
+      "--out" => out = Some(args.next().expect(USAGE).clone()),
+      _ => dumps.push(load_dump(arg)),
+    }
+  }
+  if dumps.is_empty() { panic!("{}", USAGE); }
+  let (columns, warnings) = stitch(&dumps).unwrap_or_else(|e| panic!("can't stitch dumps: {}", e));
+  // stdout may be the snippet
+  for warning in warnings { eprintln!("WARNING: {}", warning); }
+  match out {
+    Some(path) => {
+      let area_type = area_type.or_else(|| dumps.iter().filter_map(|d| d.area_type).next()).unwrap_or_else(|| panic!("the area type of hex dumps needs --area-type"));
+      save_level(&path, &Level { area_type, columns }).unwrap_or_else(|e| panic!("can't write {}: {}", path, e));
+    },
+    None => print!("{}", blockbuf_source(&name, &columns)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn dump(first_column: usize, blocks: &[u8]) -> BlockBufferDump {
+    let columns = blocks.iter().map(|&block| { let mut column = [0; ROWS]; column[ROWS - 1] = block; column }).collect();
+    BlockBufferDump { first_column, columns, area_type: None }
+  }
+
+  #[test]
+  fn block_buffer_pages_alternate() {
+    let mut block_buffer = vec![0; BLOCK_BUFFER_SIZE];
+    block_buffer[3 + 12 * COLUMNS_PER_PAGE] = 0x54; // page at 0x500, column 3, row 12
+    block_buffer[PAGE_SIZE + 1] = 0x61; // page at 0x5d0, column 1, row 0
+    let odd = BlockBufferDump::from_block_buffer(&block_buffer, 3);
+    assert_eq!(odd.first_column, 0x30);
+    assert_eq!(odd.columns[0x13][12], 0x54);
+    assert_eq!(odd.columns[0x01][0], 0x61);
+    let even = BlockBufferDump::from_block_buffer(&block_buffer, 2);
+    assert_eq!(even.columns[0x03][12], 0x54);
+    assert_eq!(even.columns[0x11][0], 0x61);
+  }
+
+  #[test]
+  fn ram_dump_skips_column_being_rendered() {
+    let mut ram = vec![0; RAM_SIZE];
+    ram[CURRENT_PAGE_LOC] = 2;
+    ram[CURRENT_COLUMN_POS] = 5;
+    ram[BLOCK_BUFFER_COLUMN_POS] = 5;
+    ram[AREA_TYPE] = 1;
+    ram[BLOCK_BUFFER + 4 + 10 * COLUMNS_PER_PAGE] = 0x61; // column 0x24
+    ram[BLOCK_BUFFER + 5] = 0x52; // column 0x25, being rendered
+    let dump = BlockBufferDump::from_ram(&ram);
+    assert_eq!(dump.first_column, 0x06);
+    assert_eq!(dump.columns.len(), BLOCK_BUFFER_COLUMNS - 1);
+    assert_eq!(dump.columns[0x24 - 0x06][10], 0x61);
+    assert!(dump.columns.iter().all(|column| column[0] == 0));
+    assert_eq!(dump.area_type, Some(AreaType::Ground));
+  }
+
+  #[test]
+  fn stitch_checks_overlapping_columns() {
+    let (columns, warnings) = stitch(&[dump(0, &[1, 2, 3]), dump(2, &[3, 4]), dump(6, &[7])]).unwrap();
+    assert_eq!(columns.iter().map(|column| column[ROWS - 1]).collect::<Vec<_>>(), vec![1, 2, 3, 4, 0, 0, 7]);
+    assert_eq!(warnings, vec!["2 of 7 columns are not covered by any dump".to_string()]);
+    assert_eq!(stitch(&[dump(0, &[1, 2]), dump(2, &[3])]).unwrap().1, Vec::<String>::new());
+    assert_eq!(stitch(&[dump(0, &[1, 2, 3]), dump(1, &[2, 5])]).err(), Some(ColumnMismatch { column: 2, row: ROWS - 1, block: 3, other_block: 5 }));
+  }
+
+  #[test]
+  fn blockbuf_source_matches_world_modules() {
+    let source = blockbuf_source("BB11", &dump(0, &[0x54; COLUMNS_PER_PAGE]).columns);
+    let lines: Vec<&str> = source.lines().collect();
+    assert_eq!(lines.len(), ROWS);
+    assert!(lines[0].starts_with("blockbuf!(BB11, 1*16, [[  0 ,  0 ,"));
+    assert_eq!(lines[ROWS - 1], format!("{}[{}]]);", " ".repeat("blockbuf!(BB11, 1*16, [".len()), vec!["0x54"; COLUMNS_PER_PAGE].join(",")));
+  }
+}
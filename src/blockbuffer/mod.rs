@@ -3,9 +3,10 @@ macro_rules! blockbuf {
     $name:ident, $width:expr, $buf:expr
   ) => {
     #[allow(dead_code)]
-    pub enum $name {}
+    pub struct $name;
     impl ::blockbuffer::BlockBuffer for $name {
-      fn get_block_at(block_x: usize, block_y: usize) -> u8 {
+      fn new() -> Self { $name }
+      fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 {
         const BUF: [[u8; $width]; 13] = $buf;
 
         if block_x < $width && block_y < 13 { BUF[block_y][block_x] } else { 0 }
@@ -14,6 +15,9 @@ macro_rules! blockbuf {
   }
 }
 
+pub mod areaparser;
+pub mod import;
+pub mod runtime;
 pub mod world1;
 pub mod world2;
 pub mod world3;
@@ -47,13 +51,15 @@ pub mod util {
 }
 
 pub trait BlockBuffer {
-  fn get_block_at(block_x: usize, block_y: usize) -> u8;
+  fn new() -> Self;
+  fn get_block_at(&self, block_x: usize, block_y: usize) -> u8;
 }
 
 #[allow(dead_code)]
-pub enum NoCollisions {}
+pub struct NoCollisions;
 impl BlockBuffer for NoCollisions {
-  fn get_block_at(_: usize, _: usize) -> u8 { 0 }
+  fn new() -> Self { NoCollisions }
+  fn get_block_at(&self, _: usize, _: usize) -> u8 { 0 }
 }
 
 blockbuf!(Test, 1, [[0],[0],[0],[0],[0],[0],[0],[0],[0],[0],[0],[0],[0],]);
@@ -0,0 +1,146 @@
+use blockbuffer::BlockBuffer;
+use rom::AreaType;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+
+pub const ROWS: usize = 13;
+// Level files: magic, version, AreaType, width in columns (little endian), then the blocks row by row
+const LEVEL_FILE_MAGIC: &[u8; 4] = b"SMBL";
+const LEVEL_FILE_VERSION: u8 = 1;
+const LEVEL_FILE_HEADER_SIZE: usize = 8;
+
+/// Block buffer contents of a whole area, one 13 block column per 16 pixels.
+#[derive(Clone,Debug,Eq,PartialEq)]
+pub struct Level {
+  pub area_type: AreaType,
+  pub columns: Vec<[u8; ROWS]>,
+}
+impl Level {
+  pub fn width(&self) -> usize {
+    self.columns.len()
+  }
+}
+
+/// Writes the level in the level file format.
+pub fn write_level<W: Write>(w: &mut W, level: &Level) -> io::Result<()> {
+  w.write_all(LEVEL_FILE_MAGIC)?;
+  w.write_all(&[LEVEL_FILE_VERSION, level.area_type.index() as u8])?;
+  w.write_all(&[level.width() as u8, (level.width() >> 8) as u8])?;
+  for row in 0..ROWS {
+    w.write_all(&level.columns.iter().map(|column| column[row]).collect::<Vec<_>>())?;
+  }
+  Ok(())
+}
+pub fn save_level(path: &str, level: &Level) -> io::Result<()> {
+  write_level(&mut File::create(path)?, level)
+}
+
+/// Error reading a level file.
+#[derive(Debug,Eq,PartialEq)]
+pub enum LevelFileError {
+  BadMagic,
+  UnsupportedVersion(u8),
+  Truncated { expected: usize, actual: usize },
+}
+impl fmt::Display for LevelFileError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match *self {
+      LevelFileError::BadMagic => write!(f, "not a level file"),
+      LevelFileError::UnsupportedVersion(version) => write!(f, "unsupported level file version {}", version),
+      LevelFileError::Truncated { expected, actual } => write!(f, "level file has {} bytes instead of {}", actual, expected),
+    }
+  }
+}
+
+/// Reads a level in the level file format.
+pub fn read_level(data: &[u8]) -> Result<Level, LevelFileError> {
+  if data.len() < LEVEL_FILE_HEADER_SIZE || &data[0..4] != LEVEL_FILE_MAGIC { return Err(LevelFileError::BadMagic); }
+  if data[4] != LEVEL_FILE_VERSION { return Err(LevelFileError::UnsupportedVersion(data[4])); }
+  let area_type = AreaType::from_index(data[5]);
+  let width = data[6] as usize | (data[7] as usize) << 8;
+  let expected = LEVEL_FILE_HEADER_SIZE + ROWS * width;
+  if data.len() != expected { return Err(LevelFileError::Truncated { expected, actual: data.len() }); }
+  let blocks = &data[LEVEL_FILE_HEADER_SIZE..];
+  let columns = (0..width).map(|x| {
+    let mut column = [0; ROWS];
+    for (y, block) in column.iter_mut().enumerate() { *block = blocks[y * width + x]; }
+    column
+  }).collect();
+  Ok(Level { area_type, columns })
+}
+
+/// Source of the level served by RuntimeBlockBuffer, e.g. an area decoded from the ROM or a level file.
+pub trait LevelSource {
+  fn level() -> Level;
+}
+
+/// Block buffer of a level loaded at runtime from L, stored row-major.
+pub struct RuntimeBlockBuffer<L: LevelSource> {
+  blocks: Vec<u8>,
+  width: usize,
+  _source: PhantomData<L>,
+}
+impl<L: LevelSource> RuntimeBlockBuffer<L> {
+  pub fn from_level(level: &Level) -> Self {
+    let blocks = (0..ROWS).flat_map(|y| level.columns.iter().map(move |column| column[y])).collect();
+    RuntimeBlockBuffer { blocks, width: level.width(), _source: PhantomData }
+  }
+}
+impl<L: LevelSource> BlockBuffer for RuntimeBlockBuffer<L> {
+  fn new() -> Self {
+    Self::from_level(&L::level())
+  }
+  #[inline]
+  fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 {
+    if block_x < self.width && block_y < ROWS { self.blocks[block_y * self.width + block_x] } else { 0 }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn level() -> Level {
+    let mut columns = vec![[0; ROWS]; 0x20];
+    columns[0x1f][ROWS - 1] = 0x54;
+    columns[0x03][0] = 0xc0;
+    Level { area_type: AreaType::Underground, columns }
+  }
+
+  #[test]
+  fn level_file_round_trip() {
+    let mut data = vec![];
+    write_level(&mut data, &level()).unwrap();
+    assert_eq!(data.len(), LEVEL_FILE_HEADER_SIZE + ROWS * 0x20);
+    assert_eq!(read_level(&data), Ok(level()));
+  }
+
+  #[test]
+  fn read_level_rejects_bad_files() {
+    let mut data = vec![];
+    write_level(&mut data, &level()).unwrap();
+    assert_eq!(read_level(&data[1..]), Err(LevelFileError::BadMagic));
+    assert_eq!(read_level(&data[..data.len() - 1]), Err(LevelFileError::Truncated { expected: data.len(), actual: data.len() - 1 }));
+    data[4] = LEVEL_FILE_VERSION + 1;
+    assert_eq!(read_level(&data), Err(LevelFileError::UnsupportedVersion(LEVEL_FILE_VERSION + 1)));
+  }
+
+  enum TestLevel {}
+  impl LevelSource for TestLevel {
+    fn level() -> Level { level() }
+  }
+
+  #[test]
+  fn runtime_block_buffer_serves_its_level() {
+    let bb = RuntimeBlockBuffer::<TestLevel>::new();
+    assert_eq!(bb.get_block_at(0x1f, ROWS - 1), 0x54);
+    assert_eq!(bb.get_block_at(0x03, 0), 0xc0);
+    assert_eq!(bb.get_block_at(0x20, 0), 0);
+    assert_eq!(bb.get_block_at(0x03, ROWS), 0);
+    let other = RuntimeBlockBuffer::<TestLevel>::from_level(&Level { area_type: AreaType::Ground, columns: vec![[0x61; ROWS]] });
+    assert_eq!(other.get_block_at(0, 0), 0x61);
+    assert_eq!(bb.get_block_at(0, 0), 0);
+  }
+}
@@ -0,0 +1,311 @@
+use hitbox::Hitbox;
+use lift::impose_gravity;
+use options::Options;
+use state::State;
+
+// InitBowser
+#[cfg(test)]
+const BOWSER_FRAME_TIMER: u8 = 0x20;
+#[cfg(test)]
+const BOWSER_FIRE_BREATH_TIMER: u8 = 0xdf;
+const BOWSER_MOVEMENT_SPEED: u8 = 0x02;
+// BowserControl
+const PRANDOM_RANGE: [u8; 4] = [0x21, 0x41, 0x11, 0x31];
+const BOWSER_GROUND_Y_POS: i32 = 0x80;
+const BOWSER_TURN_X_POS: i32 = 0xc8; // B_FaceP
+const BOWSER_JUMP_Y_SPD: i16 = -0x200;
+const MOUTH_OPEN_TIMER: u8 = 0x20;
+const FLAME_TIMER_DATA: [u8; 8] = [0xbf, 0x40, 0xbf, 0xbf, 0xbf, 0x40, 0x40, 0xbf];
+// MoveEnemySlowVert
+const BOWSER_DOWN_FORCE: i16 = 0x0f;
+const BOWSER_MAX_Y_SPD: i16 = 0x02;
+// InitBowserFlame and ProcBowserFlame
+const FLAME_Y_POS_DATA: [i32; 4] = [0x90, 0x80, 0x70, 0x90];
+const FLAME_X_SPD: i32 = 0x140;
+const FLAME_X_SPD_HARD: i32 = 0x160; // secondary hard mode
+const FLAME_RANGE: i32 = 0x180; // flames further left than this are assumed to be offscreen
+// BoundBoxCtrlData, Bowser keeps the default entry 0 for both halves
+const BOWSER_BOUNDING_BOX: (i32, i32, i32, i32) = (0x02, 0x08, 0x0e, 0x20);
+const BOWSER_REAR_X_OFFSET: i32 = 0x10;
+const FLAME_BOUNDING_BOX: (i32, i32, i32, i32) = (0x06, 0x04, 0x0a, 0x08);
+
+/// PseudoRandomBitReg, a 56 bit LFSR advanced once in every frame.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct PseudoRandom(pub [u8; 7]);
+impl PseudoRandom {
+  pub fn next_frame(&mut self) -> () {
+    let mut carry = (self.0[0] ^ self.0[1]) & 2 != 0;
+    for b in self.0.iter_mut() {
+      let next_carry = *b & 1 != 0;
+      *b = (*b >> 1) | if carry { 0x80 } else { 0 };
+      carry = next_carry;
+    }
+  }
+  /// Random value used by the enemy in the given slot.
+  pub fn get(&self, slot: usize) -> u8 { self.0[slot] }
+}
+
+/// Bowser at the end of a castle (BowserControl), with x_pos his pixel position including the page and y_pos
+/// positioned like the player without the page. Only his movement and fire breath are modeled; he never throws hammers
+/// (worlds 6 to 8) and is never defeated by fireballs.
+#[derive(Clone,Debug)]
+pub struct Bowser {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub y_spd: i16,
+  pub orig_x_pos: u8, // BowserOrigXPos
+  pub moving_dir: u8,
+  pub movement_speed: u8,
+  pub max_range: u8, // MaxRangeFromOrigin
+  pub frame_timer: u8, // EnemyFrameTimer
+  pub fire_breath_timer: u8,
+  pub mouth_open: bool,
+  pub flame_timer_ctrl: u8,
+  pub breathes_fire: bool, // worlds 1 to 5 and 8
+  pub secondary_hard_mode: bool,
+  pub slot: usize, // enemy slot of Bowser, selects his PseudoRandomBitReg byte
+  pub flame_slot: usize, // enemy slot the flames spawn in
+}
+impl Bowser {
+  /// Bowser as initialized by InitBowser at pixel position (x_pos, y_pos), for tests; the search cases load Bowser from
+  /// RAM dumps.
+  #[cfg(test)]
+  pub fn new(x_pos: i32, y_pos: i32, slot: usize, flame_slot: usize) -> Self {
+    Bowser {
+      x_pos,
+      y_pos: y_pos << 8,
+      y_spd: 0,
+      orig_x_pos: x_pos as u8,
+      moving_dir: BOWSER_FIRE_BREATH_TIMER, // InitBowser stores the same value
+      movement_speed: BOWSER_MOVEMENT_SPEED,
+      max_range: 0,
+      frame_timer: BOWSER_FRAME_TIMER,
+      fire_breath_timer: BOWSER_FIRE_BREATH_TIMER,
+      mouth_open: false,
+      flame_timer_ctrl: 0,
+      breathes_fire: true,
+      secondary_hard_mode: false,
+      slot,
+      flame_slot,
+    }
+  }
+
+  /// Positions of Bowser and his flames in every frame, starting with the start states, assuming the player stays left
+  /// of him. prng and frame_counter are the PseudoRandomBitReg and FrameCounter values in the start states.
+  pub fn path(mut self, mut prng: PseudoRandom, frame_counter: u8, frames: usize) -> BowserPath {
+    let mut path = vec![BowserFrame { x_pos: self.x_pos, y_pos: self.y_pos >> 8, faces_player: false, flames: vec![] }];
+    let mut flames: Vec<Flame> = vec![];
+    let mut spawn_flame = None;
+    for frame in 1..frames + 1 {
+      let frame_counter = frame_counter.wrapping_add(frame as u8);
+      prng.next_frame();
+      if let Some(spawn_frame) = spawn_flame.take() { flames.push(self.spawn_flame(&prng, spawn_frame)); } // EnemyFrenzyBuffer
+      let (faces_player, breathes_flame) = self.next_frame(&prng, frame_counter);
+      if breathes_flame { spawn_flame = Some(frame); }
+      let x_spd = if self.secondary_hard_mode { FLAME_X_SPD_HARD } else { FLAME_X_SPD };
+      for flame in flames.iter_mut() { flame.next_frame(x_spd); }
+      flames.retain(|flame| (flame.x_pos >> 8) >= self.x_pos - FLAME_RANGE);
+      path.push(BowserFrame {
+        x_pos: self.x_pos,
+        y_pos: self.y_pos >> 8,
+        faces_player,
+        flames: flames.iter().map(|flame| (flame.spawn_frame, flame.x_pos >> 8, flame.y_pos)).collect(),
+      });
+    }
+    BowserPath { frames: path, frame_counter }
+  }
+
+  /// BowserControl for a single frame. Returns whether B_FaceP checks the player's position in this frame, and whether
+  /// a flame is spawned.
+  fn next_frame(&mut self, prng: &PseudoRandom, frame_counter: u8) -> (bool, bool) {
+    // DecTimers
+    if self.frame_timer > 0 { self.frame_timer -= 1; }
+    if self.fire_breath_timer > 0 { self.fire_breath_timer -= 1; }
+
+    let faces_player = !self.mouth_open && self.frame_timer != 0;
+    if !self.mouth_open { // ChkMouth
+      if frame_counter & 0x0f == 0 { self.moving_dir = 2; } // ResetMDr
+      if frame_counter & 3 == 0 { // GetPRCmp
+        if self.x_pos as u8 == self.orig_x_pos { self.max_range = PRANDOM_RANGE[prng.get(self.slot) as usize & 3]; }
+        self.x_pos = (self.x_pos & !0xff) | ((self.x_pos + self.movement_speed as i8 as i32) & 0xff); // no page carry
+        if self.moving_dir != 1 {
+          let diff = (self.x_pos as u8).wrapping_sub(self.orig_x_pos);
+          let (dist, movement_speed) = if (diff as i8) >= 0 { (diff, 0xff) } else { (diff.wrapping_neg(), 1) };
+          if dist >= self.max_range { self.movement_speed = movement_speed; }
+        }
+      }
+    }
+
+    // HammerChk
+    if self.frame_timer == 0 {
+      impose_gravity(&mut self.y_pos, &mut self.y_spd, BOWSER_DOWN_FORCE, 0, BOWSER_MAX_Y_SPD, false);
+      if (self.y_pos >> 8) >= BOWSER_GROUND_Y_POS { self.frame_timer = PRANDOM_RANGE[prng.get(self.slot) as usize & 3]; } // SetHmrTmr
+    } else if self.frame_timer == 1 { // MakeBJump
+      self.y_pos -= 0x100;
+      self.y_spd = BOWSER_JUMP_Y_SPD;
+    }
+
+    // ChkFireB
+    if !self.breathes_fire || self.fire_breath_timer != 0 { return (faces_player, false); }
+    self.fire_breath_timer = MOUTH_OPEN_TIMER;
+    self.mouth_open = !self.mouth_open;
+    if self.mouth_open { return (faces_player, false); }
+    let flame_timer = FLAME_TIMER_DATA[self.flame_timer_ctrl as usize]; // SetFlameTimer
+    self.flame_timer_ctrl = (self.flame_timer_ctrl + 1) & 7;
+    self.fire_breath_timer = if self.secondary_hard_mode { flame_timer - 0x10 } else { flame_timer };
+    (faces_player, true)
+  }
+
+  /// InitBowserFlame, spawning from Bowser's mouth (SpawnFromMouth).
+  fn spawn_flame(&self, prng: &PseudoRandom, spawn_frame: usize) -> Flame {
+    let x_pos = (self.x_pos & !0xff) | ((self.x_pos - 0x0e) & 0xff); // no page borrow
+    let y_pos = (self.y_pos >> 8) + 8;
+    let target_y_pos = FLAME_Y_POS_DATA[prng.get(self.flame_slot) as usize & 3];
+    Flame { spawn_frame, x_pos: x_pos << 8, y_pos, target_y_pos, y_spd: if target_y_pos < y_pos { -1 } else { 1 } }
+  }
+}
+
+/// Flame breathed by Bowser, x_pos including the fractional part.
+struct Flame {
+  spawn_frame: usize,
+  x_pos: i32,
+  y_pos: i32,
+  target_y_pos: i32,
+  y_spd: i32,
+}
+impl Flame {
+  /// ProcBowserFlame: moves left, and vertically towards its target position.
+  fn next_frame(&mut self, x_spd: i32) -> () {
+    self.x_pos -= x_spd;
+    if self.y_pos != self.target_y_pos { self.y_pos += self.y_spd; }
+  }
+}
+
+/// Bowser's pixel position and the flames in a single frame, with the frame the flame was breathed in.
+#[derive(Clone,Debug)]
+pub struct BowserFrame {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub faces_player: bool, // whether B_FaceP checks the player's position
+  pub flames: Vec<(usize, i32, i32)>,
+}
+
+/// Precomputed movement of Bowser and his flames. Once the player passes him (State::bowser_passed), Bowser stops
+/// breathing fire and follows the player to the right, which is assumed to continue even if the player turns back.
+pub struct BowserPath {
+  pub frames: Vec<BowserFrame>,
+  pub frame_counter: u8,
+}
+impl BowserPath {
+  /// Number of frames the path covers after the start states.
+  pub fn len(&self) -> usize { self.frames.len() - 1 }
+  fn frame(&self, frame: usize) -> &BowserFrame {
+    assert!(frame < self.frames.len(), "frame {} is past the Bowser path of {} frames", frame, self.len());
+    &self.frames[frame]
+  }
+  /// B_FaceP: records the frame in which the player is first right of Bowser in State::bowser_pass_frame. Bowser
+  /// doesn't move in the start states, so the player can't pass him before frame 1.
+  pub fn update_pass_frame(&self, s: &mut State) -> () {
+    let frame = s.object_frame as usize;
+    if s.bowser_passed || frame == 0 { return; }
+    if self.frame(frame).faces_player && self.frame(frame - 1).x_pos < (s.x_pos >> 8) {
+      s.bowser_passed = true;
+      s.bowser_pass_frame = s.object_frame;
+    }
+  }
+  /// Bowser's pixel position in the current frame.
+  pub fn bowser_pos(&self, s: &State) -> (i32, i32) {
+    let frame = s.object_frame as usize;
+    if !s.bowser_passed { return (self.frame(frame).x_pos, self.frame(frame).y_pos); }
+    let pass_frame = s.bowser_pass_frame as usize;
+    assert!(pass_frame > 0 && pass_frame <= frame, "Bowser passed in frame {} at frame {}", pass_frame, frame);
+    let (mut x_pos, y_pos) = (self.frame(pass_frame - 1).x_pos, self.frame(pass_frame - 1).y_pos);
+    for f in pass_frame..frame + 1 {
+      if self.frame_counter.wrapping_add(f as u8) & 3 == 0 && (x_pos & 0xff) < BOWSER_TURN_X_POS {
+        x_pos += BOWSER_MOVEMENT_SPEED as i32;
+      }
+    }
+    (x_pos, y_pos)
+  }
+  /// PlayerEnemyCollision with either half of Bowser or any of his flames.
+  pub fn hits_player<O: Options>(&self, s: &State) -> bool {
+    let player = Hitbox::player::<O>(s);
+    let (x_pos, y_pos) = self.bowser_pos(s);
+    if player.intersects(&Hitbox::new(x_pos, 0x100 + y_pos, BOWSER_BOUNDING_BOX)) { return true; }
+    if player.intersects(&Hitbox::new(x_pos + BOWSER_REAR_X_OFFSET, 0x100 + y_pos, BOWSER_BOUNDING_BOX)) { return true; }
+    let pass_frame = if s.bowser_passed { s.bowser_pass_frame as usize } else { usize::max_value() };
+    self.frame(s.object_frame as usize).flames.iter()
+        .filter(|&&(spawn_frame, _, _)| spawn_frame < pass_frame)
+        .any(|&(_, x, y)| player.intersects(&Hitbox::new(x, 0x100 + y, FLAME_BOUNDING_BOX)))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use options::*;
+  use state::PlayerState;
+
+  enum Castle {}
+  impl Options for Castle {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+
+  fn bowser_path(frames: usize) -> BowserPath {
+    Bowser::new(0x180, 0x80, 1, 0).path(PseudoRandom([0xa5, 0, 0, 0, 0, 0, 0]), 0, frames)
+  }
+
+  #[test]
+  fn player_passes_bowser_once() {
+    let path = bowser_path(0x40);
+    let mut s = ::transition::entrance_state::<Castle>(0x20000, 0x18000, PlayerState::STANDING);
+    path.update_pass_frame(&mut s);
+    assert!(!s.bowser_passed, "passed in the start states");
+    s.object_frame = 1;
+    path.update_pass_frame(&mut s);
+    assert!(s.bowser_passed);
+    assert_eq!(s.bowser_pass_frame, 1);
+    s.object_frame = 8;
+    path.update_pass_frame(&mut s);
+    assert_eq!(s.bowser_pass_frame, 1);
+    // following the player every fourth frame from where he was before the pass
+    assert_eq!(path.bowser_pos(&s), (0x184, 0x80));
+  }
+
+  #[test]
+  fn bowser_and_flames_hit_player() {
+    let path = bowser_path(0x180);
+    let s = ::transition::entrance_state::<Castle>(0x18000, 0x18000, PlayerState::STANDING);
+    assert!(path.hits_player::<Castle>(&s));
+    let s = ::transition::entrance_state::<Castle>(0x10000, 0x18000, PlayerState::STANDING);
+    assert!(!path.hits_player::<Castle>(&s));
+
+    let frame = path.frames.iter().position(|f| !f.flames.is_empty()).expect("no flame breathed");
+    let (spawn_frame, x_pos, y_pos) = path.frames[frame + 0x40].flames[0];
+    let mut s = ::transition::entrance_state::<Castle>(x_pos << 8, (0x100 + y_pos - 0x10) << 8, PlayerState::FALLING);
+    s.object_frame = (frame + 0x40) as u16;
+    assert!(path.hits_player::<Castle>(&s));
+    // flames breathed after the player passed Bowser don't exist
+    s.bowser_passed = true;
+    s.bowser_pass_frame = spawn_frame as u16;
+    assert!(!path.hits_player::<Castle>(&s));
+  }
+
+  #[test]
+  #[should_panic(expected = "past the Bowser path")]
+  fn bowser_path_ends() {
+    let path = bowser_path(0x40);
+    let mut s = ::transition::entrance_state::<Castle>(0x10000, 0x18000, PlayerState::STANDING);
+    s.object_frame = 0x41;
+    path.hits_player::<Castle>(&s);
+  }
+}
@@ -1,7 +1,8 @@
 use blockbuffer::BlockBuffer;
-use emu::{Emu,SmbEmu,print_rle};
+use emu::{Emu,EmuResult,Input,SmbEmu,parse_rle,print_rle};
 use heuristics::SearchGoal;
 use ida::{IDA, InputFetcher, Search, SearchResult, SmbInputFetcher};
+use levelend::GameTimer;
 use options::*;
 use state::{Dir, Dist, State};
 use store::StateStore;
@@ -23,6 +24,10 @@ pub mod w73;
 pub mod w82;
 pub mod w84;
 
+// suffixes of the files a solution is written to next to the case's SOLUTION_MOVIE
+const SPLICED_MOVIE_SUFFIX: &str = ".solution.fm2";
+const BK2_FRAGMENT_SUFFIX: &str = ".solution.bk2.txt";
+
 #[allow(dead_code)]
 pub fn with_left_and_right_facing_dir(states: Vec<State>) -> Vec<State> {
   states.into_iter().flat_map(|s| {
@@ -80,6 +85,93 @@ pub fn with_all_y_pos_subpixels(states: Vec<State>) -> Vec<State> {
   }).collect()
 }
 
+/// s with the parity of a RAM dump taken in the frame of s, for cases depending on the timers in the start state. Keeps
+/// the parity of s if there is no such dump, for cases with a hand-built fallback.
+pub fn with_start_ram_parity_if_present<O: Options>(s: State, name: &str) -> State {
+  match ::ram::try_load_ram(name) {
+    Some(ram) => {
+      let (ram_state, _) = ::ram::state_from_ram::<O>(&ram);
+      assert!(ram_state.x_pos == s.x_pos && ram_state.y_pos == s.y_pos, "RAM dump {} is at {:x},{:x} instead of {:x},{:x}", name, ram_state.x_pos, ram_state.y_pos, s.x_pos, s.y_pos);
+      State { parity: ram_state.parity, ..s }
+    },
+    None => s,
+  }
+}
+
+/// Player State from the RAM dump with the given name, printing the warnings of ram::state_from_ram.
+pub fn load_start_state<O: Options>(name: &str) -> State {
+  let (s, warnings) = ::ram::state_from_ram::<O>(&::ram::load_ram(name));
+  for warning in warnings { println!("WARNING: {}: {}", name, warning); }
+  s
+}
+
+/// Contents of the RAM dump with the given name, or None with a warning describing what the case does without it.
+pub fn optional_ram(name: &str, fallback: &str) -> Option<Vec<u8>> {
+  let ram = ::ram::try_load_ram(name);
+  if ram.is_none() { println!("WARNING: no RAM dump {}, {}", name, fallback); }
+  ram
+}
+
+/// Game timer of a RAM dump taken in the start state of a flag case, or the assumed game timer if there is no such
+/// dump.
+pub fn flag_game_timer(name: &str, assumed: GameTimer) -> GameTimer {
+  match optional_ram(name, &format!("assuming game timer {} and the parity of the start state", assumed.display)) {
+    Some(ram) => ::ram::game_timer_from_ram(&ram),
+    None => assumed,
+  }
+}
+
+/// Whether the flag contact in s grabs the 1-1/1-2 flag pole below its bottom half block, touching it from the right
+/// half of its block.
+pub fn is_half_flag_pole_grab(s: &State, emu_result: &EmuResult) -> bool {
+  if let &EmuResult::StateChangeFlag(cx, cy) = emu_result {
+    cx == 0xc6 && cy == 9 && s.y_pos >= 0x1a200 && (s.x_pos & 0xf0) >= 0x70
+  } else { false }
+}
+
+/// Frames from the flag contact in s until the next level loads, for cases tracking the frame rule with Parity21.
+/// game_timer is the game timer in the start states, and steps the number of steps until the flag contact.
+pub fn frames_until_next_level<O: Options, B: BlockBuffer>(s: &State, game_timer: &GameTimer, steps: Dist) -> Dist {
+  assert!(O::Parity::PARITY == 21);
+  let contact_game_timer = game_timer.after_frames(steps - 1).display; // RunGameTimer doesn't run in the contact frame
+  ::levelend::frames_until_next_level::<O, B>(s, contact_game_timer, 0x14 - s.parity)
+}
+
+/// Splices the inputs into the FCEUX movie at movie_path at the given frame, writing the result next to it, together with
+/// the BizHawk input log lines of the inputs to be pasted at the same frame. Returns the paths of the written files.
+fn splice_solution(movie_path: &str, frame: usize, inputs: &[Input]) -> ::std::io::Result<(String, String)> {
+  let stem = movie_path.trim_end_matches(".fm2");
+  let (spliced_path, fragment_path) = (format!("{}{}", stem, SPLICED_MOVIE_SUFFIX), format!("{}{}", stem, BK2_FRAGMENT_SUFFIX));
+  let movie = ::std::fs::read_to_string(movie_path)?;
+  ::std::fs::write(&spliced_path, ::movie::fm2::splice(&movie, inputs, frame))?;
+  ::movie::bk2::write_input_log_fragment(&mut ::std::fs::File::create(&fragment_path)?, inputs, 0)?;
+  Ok((spliced_path, fragment_path))
+}
+
+/// Final State and EmuResult of replaying an input sequence in the format written by print_rle from the first start
+/// state of a search case, e.g. the documented solution of the case. Stops at the first step that is not successful.
+#[cfg(test)]
+pub fn replay<T: SmbSearchCase>(rle: &str) -> (State, EmuResult) {
+  let inputs = parse_rle(rle).unwrap_or_else(|e| panic!("can't parse input sequence: {}", e));
+  let trace = ::replay::trace(&T::Emu::new(), T::start_states().remove(0), &inputs);
+  assert!(trace.len() == inputs.len(), "replay ended with {:?} after {} of {} inputs", trace.last().map(|t| &t.1), trace.len(), inputs.len());
+  trace.into_iter().last().unwrap()
+}
+
+/// Replays an input sequence in the format written by print_rle from every start state of a case, printing the final
+/// State and EmuResult of each replay.
+pub fn replay_rle<T: SmbSearchCase>(rle: &str) -> () {
+  let inputs = parse_rle(rle).unwrap_or_else(|e| panic!("can't parse input sequence: {}", e));
+  let emu = T::Emu::new();
+  for (i, s) in T::start_states().into_iter().enumerate() {
+    let trace = ::replay::trace(&emu, s, &inputs);
+    match trace.last() {
+      Some(&(ref s, ref emu_result)) => println!("Start state {}: {:?} after {} of {} inputs, final state: {}", i, emu_result, trace.len(), inputs.len(), s),
+      None => println!("Start state {}: no inputs", i),
+    }
+  }
+}
+
 pub trait Case {
   fn run() -> ();
 }
@@ -97,7 +189,7 @@ impl<T: SmbCase> Case for T {
 }
 
 pub trait SmbSearchCase: Options + Sized {
-  type SearchGoal: SearchGoal;
+  type SearchGoal: SearchGoal<Params = ()>;
   type StateStore: StateStore;
 
   type BlockBuffer: BlockBuffer;
@@ -109,6 +201,9 @@ pub trait SmbSearchCase: Options + Sized {
   fn start_states() -> Vec<State>;
   const INITIAL_SEARCH_DISTANCE: Dist = 0;
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
+  /// FCEUX movie of the TAS to splice a found solution into, and the frame of the start states in it. No movies are
+  /// written without it.
+  const SOLUTION_MOVIE: Option<(&'static str, usize)> = None;
 }
 
 impl<T: SmbSearchCase> SmbCase for T {
@@ -122,8 +217,107 @@ impl<T: SmbSearchCase> SmbCase for T {
       println!("Final state: {}", states[states.len() - 1]);
       print!("Input sequence: ");
       print_rle(inputs.iter());
+      if let Some((movie_path, frame)) = Self::SOLUTION_MOVIE {
+        match splice_solution(movie_path, frame, &inputs) {
+          Ok((spliced_path, fragment_path)) => println!("Spliced solution at frame {} into {} and {}", frame, spliced_path, fragment_path),
+          Err(e) => println!("Failed to splice solution into {}: {}", movie_path, e),
+        }
+      }
     } else {
       println!("No solutions found!");
     }
   }
-}
\ No newline at end of file
+}
+
+/// Function generic over the search case, called for a case chosen by name with with_search_case.
+pub trait SearchCaseFn {
+  fn call<T: SmbSearchCase>(self) -> ();
+}
+
+/// Calls f for the search case with the given name, e.g. "W11Flag". Returns false if there is no such case.
+pub fn with_search_case<F: SearchCaseFn>(name: &str, f: F) -> bool {
+  match name {
+    "W11Speedup" => f.call::<w11::W11Speedup>(),
+    "W11VertPipeEntry" => f.call::<w11::W11VertPipeEntry>(),
+    "W11SubSpeedup" => f.call::<w11::W11SubSpeedup>(),
+    "W11SubSidePipeEntry" => f.call::<w11::W11SubSidePipeEntry>(),
+    "W11SubRoute" => f.call::<w11::W11SubRoute>(),
+    "W11PipeSpeedup" => f.call::<w11::W11PipeSpeedup>(),
+    "W11Flag" => f.call::<w11::W11Flag>(),
+    "W11Flag2" => f.call::<w11::W11Flag2>(),
+    "W12Speedup" => f.call::<w12::W12Speedup>(),
+    "W12Powerup" => f.call::<w12::W12Powerup>(),
+    "W12Flag" => f.call::<w12::W12Flag>(),
+    "W13PowerupSmall" => f.call::<w13::W13PowerupSmall>(),
+    "W13PowerupBig" => f.call::<w13::W13PowerupBig>(),
+    "W13FloorClip" => f.call::<w13::W13FloorClip>(),
+    "W13FloorClipSpeedup" => f.call::<w13::W13FloorClipSpeedup>(),
+    "W13FloorFlag" => f.call::<w13::W13FloorFlag>(),
+    "W13Lift" => f.call::<w13::W13Lift>(),
+    "W14Speedup" => f.call::<w14::W14Speedup>(),
+    "W14Powerup" => f.call::<w14::W14Powerup>(),
+    "W14Bowser" => f.call::<w14::W14Bowser>(),
+    "W21ScreenScrollRightCollision" => f.call::<w21::W21ScreenScrollRightCollision>(),
+    "W21ScreenScrollLeftCollision" => f.call::<w21::W21ScreenScrollLeftCollision>(),
+    "W21ScreenScrollBlock3Clip" => f.call::<w21::W21ScreenScrollBlock3Clip>(),
+    "W21ScreenScrollBlock3ClipSpeedup" => f.call::<w21::W21ScreenScrollBlock3ClipSpeedup>(),
+    "W21ScreenScrollBlock3PipeClip" => f.call::<w21::W21ScreenScrollBlock3PipeClip>(),
+    "W21FloorClip" => f.call::<w21::W21FloorClip>(),
+    "W21FloorFlag" => f.call::<w21::W21FloorFlag>(),
+    "W21Jumpspring" => f.call::<w21::W21Jumpspring>(),
+    "W22Speedup" => f.call::<w22::W22Speedup>(),
+    "W23FloorClip" => f.call::<w23::W23FloorClip>(),
+    "W23FloorFlag" => f.call::<w23::W23FloorFlag>(),
+    "W31ScreenScrollBlock3PipeClip" => f.call::<w31::W31ScreenScrollBlock3PipeClip>(),
+    "W32FloorClipSpeedup" => f.call::<w32::W32FloorClipSpeedup>(),
+    "W33FloorClipSpeedup" => f.call::<w33::W33FloorClipSpeedup>(),
+    "W33FloorFlag" => f.call::<w33::W33FloorFlag>(),
+    "W44Speedup" => f.call::<w44::W44Speedup>(),
+    "W44Clip" => f.call::<w44::W44Clip>(),
+    "W62Vine" => f.call::<w62::W62Vine>(),
+    "W62VineSpeedup" => f.call::<w62::W62VineSpeedup>(),
+    "W62VineClimb" => f.call::<w62::W62VineClimb>(),
+    "W71Launcher2Clip" => f.call::<w71::W71Launcher2Clip>(),
+    "W71Pipe2Clip" => f.call::<w71::W71Pipe2Clip>(),
+    "W71Pipe1Clip" => f.call::<w71::W71Pipe1Clip>(),
+    "W73FloorClip" => f.call::<w73::W73FloorClip>(),
+    "W73FloorFlag" => f.call::<w73::W73FloorFlag>(),
+    "W82PiranhaPass" => f.call::<w82::W82PiranhaPass>(),
+    "W84Speedup" => f.call::<w84::W84Speedup>(),
+    "W84Part2Speedup" => f.call::<w84::W84Part2Speedup>(),
+    "W84Part2VertPipeEntry" => f.call::<w84::W84Part2VertPipeEntry>(),
+    "W84Part3Speedup" => f.call::<w84::W84Part3Speedup>(),
+    "W84Part3VertPipeEntry" => f.call::<w84::W84Part3VertPipeEntry>(),
+    "W84Part4Speedup" => f.call::<w84::W84Part4Speedup>(),
+    _ => return false,
+  }
+  true
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use emu::inputs::*;
+
+  #[test]
+  fn solution_is_spliced_next_to_movie() {
+    let movie_path = ::std::env::temp_dir().join(format!("smb-opt-{}.fm2", ::std::process::id()));
+    let movie_path = movie_path.to_str().unwrap();
+    ::std::fs::write(movie_path, "version 3\n|0|........|||\n|0|........|||\n|0|........|||\n").unwrap();
+    let (spliced_path, fragment_path) = splice_solution(movie_path, 1, &[R, A|R]).unwrap();
+    assert!(spliced_path.ends_with(".solution.fm2") && fragment_path.ends_with(".solution.bk2.txt"));
+    assert_eq!(::std::fs::read_to_string(&spliced_path).unwrap(), "version 3\n|0|........|||\n|0|R.......|||\n|0|R......A|||\n");
+    assert_eq!(::std::fs::read_to_string(&fragment_path).unwrap(), "|..|...R....|\n|..|...R...A|\n");
+    for path in &[movie_path, &spliced_path, &fragment_path] { ::std::fs::remove_file(path).unwrap(); }
+  }
+
+  #[test]
+  fn half_flag_pole_grab_needs_right_half_of_block() {
+    let s = State { x_pos: 0xc5680, y_pos: 0x1a200, ..::transition::entrance_state::<w11::W11Flag>(0, 0, ::state::PlayerState::FALLING) };
+    assert!(is_half_flag_pole_grab(&s, &EmuResult::StateChangeFlag(0xc6, 9)));
+    assert!(!is_half_flag_pole_grab(&s, &EmuResult::StateChangeFlag(0xc6, 8)));
+    assert!(!is_half_flag_pole_grab(&s, &EmuResult::Success));
+    assert!(!is_half_flag_pole_grab(&State { x_pos: 0xc5660, ..s.clone() }, &EmuResult::StateChangeFlag(0xc6, 9)));
+    assert!(!is_half_flag_pole_grab(&State { y_pos: 0x1a1ff, ..s }, &EmuResult::StateChangeFlag(0xc6, 9)));
+  }
+}
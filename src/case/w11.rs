@@ -1,10 +1,13 @@
 use blockbuffer::world1::*;
 use emu::{Emu, EmuResult, SmbEmu};
 use emu::inputs::*;
-use heuristics::{BoundsHeuristic, SearchGoal};
+use heuristics::{BoundsHeuristic, SearchGoal, min_x_pos_heuristic};
 use heuristics::xpos::XPosHeuristic;
+use levelend::{GameTimer, LevelEndBounds};
+use multiarea::{AreaConnections, MultiAreaEmu};
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
+use transition::{Entrance, enter_area};
 
 fn w11_start<O: Options>() -> State {
   State {
@@ -29,59 +32,31 @@ fn w11_start<O: Options>() -> State {
     powerup_block_hit: false,
     powerup_collected: false,
     parity: 0,
+    injured: false,
+    injury_timer: 0,
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
   }
 }
 
 fn w11_sub_start<O: Options>() -> State {
-  SmbEmu::<O, BB11Sub>::iterate_entrance(State {
-    x_pos: 0x1800,
-    y_pos: 0x12000,
-    x_spd: 0,
-    v_force: O::Platform::V_FORCE_AREA_INIT,
-    y_spd: 0,
-    v_force_down: O::Platform::V_FORCE_AREA_INIT,
-    facing_dir: Dir::RIGHT,
-    moving_dir: Dir::empty(),
-    player_state: PlayerState::FALLING,
-    x_spd_abs: 0,
-    running_speed: false,
-    collision_bits: Dir::LR,
-    side_collision_timer: 0,
-    left_screen_edge_pos: 0,
-    jump_swim_timer: 0,
-    running_timer: 0,
-    is_crouching: false,
-    collected_coins: 0,
-    powerup_block_hit: false,
-    powerup_collected: false,
-    parity: 0,
-  })
+  enter_area(&SmbEmu::<O, BB11Sub>::new(), Entrance::Fall(0x1800, 0x12000)).0
 }
 
 fn w11_pipe_start<O: Options>() -> State {
-  State {
-    x_pos: 0xa3800,
-    y_pos: 0x19000,
-    x_spd: 0x0,
-    v_force: O::Platform::V_FORCE_AREA_INIT,
-    y_spd: 0x0,
-    v_force_down: O::Platform::V_FORCE_AREA_INIT,
-    facing_dir: Dir::RIGHT,
-    moving_dir: Dir::empty(),
-    player_state: PlayerState::STANDING,
-    x_spd_abs: 0,
-    running_speed: false,
-    collision_bits: Dir::LR,
-    side_collision_timer: 0,
-    left_screen_edge_pos: 0x0,
-    jump_swim_timer: 0,
-    running_timer : 0,
-    is_crouching: false,
-    collected_coins: 0,
-    powerup_block_hit: false,
-    powerup_collected: false,
-    parity: 0,
-  }
+  enter_area(&SmbEmu::<O, BB11>::new(), Entrance::PipeExit(0xa3800)).0
 }
 
 /// Initial speed-up starting in 1-1
@@ -114,7 +89,7 @@ impl super::SmbSearchCase for W11Speedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 59;
 }
 impl SearchGoal for W11Speedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x9190))
   }
@@ -153,7 +128,7 @@ impl super::SmbSearchCase for W11VertPipeEntry {
   const SEARCH_SPACE_SIZE_HINT: usize = 283205;
 }
 impl SearchGoal for W11VertPipeEntry {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x39400))
   }
@@ -194,7 +169,7 @@ impl super::SmbSearchCase for W11SubSpeedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 1523576;
 }
 impl SearchGoal for W11SubSpeedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x91e0))
   }
@@ -249,7 +224,7 @@ impl super::SmbSearchCase for W11SubSidePipeEntry {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000; //368488637;
 }
 impl SearchGoal for W11SubSidePipeEntry {
-  fn new() -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
+  fn new(_: ()) -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     if s.x_pos >= 0xae00 && s.collected_coins.count_ones() < 13 { return None }
     if s.x_pos >= 0x9e00 && s.collected_coins.count_ones() < 11 { return None }
@@ -265,6 +240,61 @@ impl SearchGoal for W11SubSidePipeEntry {
   }
 }
 
+/// Start of 1-1 through the sub area until exiting the pipe, as a single search across areas
+#[allow(dead_code)]
+pub struct W11SubRoute {}
+impl Options for W11SubRoute {
+  type CoinHandler = IgnoreCoins;
+  type Platform = NTSC;
+  type PlayerSize = Small;
+  type Swim = NotSwimming;
+  type PowerupHandler = NoPowerups;
+  type RunningTimer = NoRunningTimer;
+  type ScrollPos = NoScrollPos;
+  type Parity = NoParity;
+  type VerticalPipeHandler = EnterVerticalPipe<::typenum::U57, ::typenum::U7>;
+  type YPosFractionalBehavior = KeepYPosFractionals;
+  type AreaHandler = MultiArea;
+}
+impl AreaConnections for W11SubRoute {
+  type Area0 = BB11;
+  type Area1 = BB11Sub;
+
+  fn connect(area: u8, _: &State, emu_result: &EmuResult) -> Option<(u8, Entrance)> {
+    match (area, emu_result) {
+      (0, &EmuResult::StateChangeVerticalPipe(0x39, 7)) => Some((1, Entrance::Fall(0x1800, 0x12000))),
+      (1, &EmuResult::StateChangeSidePipe(13, 10)) => Some((0, Entrance::PipeExit(0xa3800))),
+      _ => None,
+    }
+  }
+}
+impl super::SmbSearchCase for W11SubRoute {
+  type SearchGoal = Self;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
+
+  type BlockBuffer = BB11;
+  type Emu = MultiAreaEmu<Self, Self>;
+
+  fn start_states() -> Vec<State> {
+    vec![w11_start::<Self>()]
+  }
+  const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
+}
+impl SearchGoal for W11SubRoute {
+  fn new(_: ()) -> Self { Self {} }
+  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    let area_steps = match s.area {
+      0 if s.x_pos < 0xa0000 => min_x_pos_heuristic::<<Self as Options>::Platform>(s, 0x39400),
+      1 => min_x_pos_heuristic::<<Self as Options>::Platform>(s, 0xc300),
+      _ => 0,
+    };
+    Some(s.area_change_timer + area_steps)
+  }
+  fn is_goal_state(&self, s: &State, _: &EmuResult) -> bool {
+    s.area == 0 && s.x_pos >= 0xa0000 && s.area_change_timer == 0
+  }
+}
+
 /// Initial speed-up starting in 1-1-pipe
 /// Input sequence: [1x L|R, 1x A|R, 19x R, 1x NIL, 1x B|R, 1x L, 13x R, 68x B|R, 2x A, 9x R, 12x B|R, 3x R, 4x B|R, 1x A, 9x R, 3x B|R, 1x A, 9x R, 2x B|R, 1x A, 9x R, 4x B|R] (len: 174)
 #[allow(dead_code)]
@@ -295,7 +325,7 @@ impl super::SmbSearchCase for W11PipeSpeedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 266;
 }
 impl SearchGoal for W11PipeSpeedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0xbc110))
   }
@@ -304,11 +334,21 @@ impl SearchGoal for W11PipeSpeedup {
   }
 }
 
-/// Stairs to half flag pole glitch
+// RAM dump taken in the frame of the W11Flag start state, for the game timer and IntervalTimerControl deciding the level
+// end timing
+const FLAG_START_RAM: &str = "w11flag.ram";
+// Game timer in the start state assumed without the RAM dump
+const FLAG_GAME_TIMER: GameTimer = GameTimer { display: 300, ctrl_timer: 0x18 };
+// Flag contacts covered by the level end bounds of the search
+const FLAG_MAX_STEPS: Dist = 0x100;
+
+/// Stairs to half flag pole glitch, reaching the next level as early as possible
 /// Input sequence: [3x B|R, 1x A, 42x R, 2x L, 3x NIL, 2x R, 1x L, 1x NIL, 1x R, 1x A|L, 1x NIL, 1x R] (len: 59)
 #[allow(dead_code)]
 pub struct W11Flag {
   heuristic: BoundsHeuristic,
+  game_timer: GameTimer,
+  level_end: LevelEndBounds,
 }
 impl Options for W11Flag {
   type CoinHandler = IgnoreCoins;
@@ -318,13 +358,13 @@ impl Options for W11Flag {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = NoScrollPos;
-  type Parity = NoParity;
+  type Parity = Parity21;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
 }
 impl super::SmbSearchCase for W11Flag {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 10]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
 
   type BlockBuffer = BB11;
 
@@ -351,22 +391,48 @@ impl super::SmbSearchCase for W11Flag {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    // let s = Self::Emu::run_steps_nr(s, &[B|R; 12]);
-    vec![s]
+    // let s = Self::Emu::new().run_steps_nr(s, &[B|R; 12]);
+    vec![super::with_start_ram_parity_if_present::<Self>(s, FLAG_START_RAM)]
   }
   // const INITIAL_SEARCH_DISTANCE: Dist = 59;
   const SEARCH_SPACE_SIZE_HINT: usize = 83848968; // initial dist 0: 165497479;
 }
 impl SearchGoal for W11Flag {
-  fn new() -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
-  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
-    Some(self.heuristic.get_steps_until_bounds_at_least(s, 0xc5600, 0x1a200))
+  fn new(_: ()) -> Self {
+    let start_states = <Self as super::SmbSearchCase>::start_states();
+    let game_timer = super::flag_game_timer(FLAG_START_RAM, FLAG_GAME_TIMER);
+    Self {
+      heuristic: BoundsHeuristic::new::<Self>(&start_states),
+      game_timer,
+      level_end: LevelEndBounds::new(game_timer, start_states[0].parity, FLAG_MAX_STEPS),
+    }
+  }
+  fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
+    let steps_until_flag = self.heuristic.get_steps_until_bounds_at_least(s, 0xc5600, 0x1a200);
+    self.level_end.min_frames_until_next_level(steps_already_taken, steps_already_taken + steps_until_flag)
   }
   fn is_goal_state(&self, s: &State, emu_result: &EmuResult) -> bool {
-    if let &EmuResult::StateChangeFlag(cx, cy) = emu_result {
-      cx == 0xc6 && cy == 9 && s.y_pos >= 0x1a200 && (s.x_pos & 0xf0) >= 0x70
-    } else { false }
+    super::is_half_flag_pole_grab(s, emu_result)
+  }
+  fn frames_after_goal(&self, s: &State, steps: Dist) -> Dist {
+    super::frames_until_next_level::<Self, BB11>(s, &self.game_timer, steps)
   }
 }
 
@@ -417,6 +483,22 @@ impl super::SmbSearchCase for W11Flag2 {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
       vec![s]
@@ -426,7 +508,7 @@ impl super::SmbSearchCase for W11Flag2 {
   const SEARCH_SPACE_SIZE_HINT: usize = 83848968; // initial dist 0: 165497479;
 }
 impl SearchGoal for W11Flag2 {
-  fn new() -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
+  fn new(_: ()) -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.heuristic.get_steps_until_bounds_at_least(s, 0xc5600, 0x1a200))
   }
@@ -441,9 +523,10 @@ impl SearchGoal for W11Flag2 {
       s.x_pos = 0xc6b00 + (s.x_pos & 0xff);
       s.facing_dir = Dir::LEFT;
       s.collision_bits = Dir::LR;
+      let emu = <Self as super::SmbSearchCase>::Emu::new();
       let mut count = 0;
       while s.collision_bits.contains(Dir::RIGHT) {
-        s = <Self as super::SmbSearchCase>::Emu::run_step_nr(s, R);
+        s = emu.run_step_nr(s, R);
         count += 1;
       }
       count < 78
@@ -474,12 +557,13 @@ impl super::SmbCase for EmulatorTesting {
     let _y_heuristic = ::heuristics::ypos::YPosHeuristic::new::<Self>(&vec![s.clone()]);
 
     let s = w11_start::<Self>();
+    let emu = Self::Emu::new();
     println!("Old State: {}", s);
-    let s = Self::Emu::run_steps_nr(s, &[L|R, A|R]);
-    let s = Self::Emu::run_steps_nr(s, &[R; 19]);
-    let s = Self::Emu::run_steps_nr(s, &[NIL, B|R, L, A|R]);
-    let s = Self::Emu::run_steps_nr(s, &[R; 7]);
-    let s = Self::Emu::run_steps_nr(s, &[NIL; 13]);
+    let s = emu.run_steps_nr(s, &[L|R, A|R]);
+    let s = emu.run_steps_nr(s, &[R; 19]);
+    let s = emu.run_steps_nr(s, &[NIL, B|R, L, A|R]);
+    let s = emu.run_steps_nr(s, &[R; 7]);
+    let s = emu.run_steps_nr(s, &[NIL; 13]);
     println!("New State: {}", s);
   }
-}
\ No newline at end of file
+}
@@ -1,13 +1,14 @@
 use blockbuffer::world1::*;
-use emu::{Emu, EmuResult, SmbEmu};
-#[allow(unused_imports)] use emu::inputs::*;
+use emu::{Emu, EmuResult, Input, SmbEmu};
+use emu::inputs::*;
 use heuristics::{BoundsHeuristic, SearchGoal};
 use heuristics::xpos::XPosHeuristic;
+use levelend::{GameTimer, LevelEndBounds};
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
 
 fn w12_start<O: Options>() -> State {
-  SmbEmu::<O, BB12>::iterate_entrance(State {
+  SmbEmu::<O, BB12>::new().iterate_entrance(State {
     x_pos: 0x2800,
     y_pos: 0x12000,
     x_spd: 0x0,
@@ -29,6 +30,22 @@ fn w12_start<O: Options>() -> State {
     powerup_block_hit: false,
     powerup_collected: false,
     parity: 0,
+    injured: false,
+    injury_timer: 0,
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
   })
 }
 
@@ -62,7 +79,7 @@ impl super::SmbSearchCase for W12Speedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 59;
 }
 impl SearchGoal for W12Speedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x9190))
   }
@@ -118,13 +135,29 @@ impl super::SmbSearchCase for W12Powerup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     }]
   }
   const INITIAL_SEARCH_DISTANCE: Dist = 68;
   const SEARCH_SPACE_SIZE_HINT: usize = 277409196; // INITIAL_SEARCH_DISTANCE=0: 277624442;
 }
 impl SearchGoal for W12Powerup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
     const MAX_STEPS_TO_HIT: Dist = 14;
     if (steps_already_taken < MAX_STEPS_TO_HIT-3 && s.powerup_block_hit) || (steps_already_taken >= MAX_STEPS_TO_HIT && !s.powerup_block_hit) {
@@ -152,12 +185,78 @@ impl SearchGoal for W12Powerup {
   }
 }
 
+// RAM dump taken in the frame of the W12Flag pipe exit state, for the game timer and IntervalTimerControl deciding the
+// level end timing
+const FLAG_START_RAM: &str = "w12flag.ram";
+// Game timer in the pipe exit state assumed without the RAM dump
+const FLAG_GAME_TIMER: GameTimer = GameTimer { display: 300, ctrl_timer: 0x18 };
+// Flag contacts covered by the level end bounds of the search
+const FLAG_MAX_STEPS: Dist = 0x100;
+
+fn w12_flag_pipe_exit<O: Options>() -> State {
+  State {
+    x_pos: 0xb3800,
+    y_pos: 0x19000,
+    x_spd: 0x0,
+    v_force: O::Platform::V_FORCE_AREA_INIT,
+    y_spd: 0x0,
+    v_force_down: O::Platform::V_FORCE_AREA_INIT,
+    facing_dir: Dir::RIGHT,
+    moving_dir: Dir::empty(),
+    player_state: PlayerState::STANDING,
+    x_spd_abs: 0x0,
+    running_speed: false,
+    collision_bits: Dir::LR,
+    side_collision_timer: 0,
+    left_screen_edge_pos: 0x0,
+    jump_swim_timer: 0,
+    running_timer : 0,
+    is_crouching: false,
+    collected_coins: 0,
+    powerup_block_hit: false,
+    powerup_collected: false,
+    parity: 0,
+    injured: false,
+    injury_timer: 0,
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
+  }
+}
+
+// Inputs from the pipe exit to the W12Flag start state
+fn w12_flag_prefix() -> Vec<Input> {
+  let mut inputs = vec![L|R, A|R];
+  inputs.extend_from_slice(&[R; 19]);
+  inputs.extend_from_slice(&[NIL, B|R, B|L]);
+  inputs.extend_from_slice(&[B|R; 4]);
+  inputs.extend_from_slice(&[A|B|R; 2]);
+  inputs.extend_from_slice(&[B|R; 2]);
+  inputs.extend_from_slice(&[B; 9]);
+  inputs.extend_from_slice(&[B|R; 2]);
+  inputs.extend_from_slice(&[B|R, B|L]);
+  inputs
+}
+
 /// Exit pipe to half flag pole glitch
 /// Input sequence: [1x L|R, 1x A|R, 19x R, 1x NIL, 1x B|R, 1x L, 4x R, 2x A|R, 11x R, 4x B|R, 1x A, 9x R, 3x B|R, 1x A, 9x R, 14x B|R, 1x A, 36x R, 1x L, 1x NIL, 4x L, 7x R, 1x L, 1x NIL, 1x R, 1x A|L, 1x NIL, 1x R] (len: 138)
 /// Input sequence: [1x L|R, 1x A|R, 19x R, 1x NIL, 1x B|R, 1x B|L, 4x B|R, 2x A|B|R, 2x B|R, 9x B, 2x B|R, 1x R, 1x B|L, 1x A, 9x R, 3x B|R, 1x A, 9x R, 14x B|R, 1x A, 34x R, 4x NIL, 2x L, 1x NIL, 2x L, 2x NIL, 4x R, 1x L, 1x NIL, 1x R, 1x A|L, 1x NIL, 1x R] (len: 138)
 #[allow(dead_code)]
 pub struct W12Flag {
   heuristic: BoundsHeuristic,
+  game_timer: GameTimer,
+  level_end: LevelEndBounds,
 }
 impl Options for W12Flag {
   type CoinHandler = IgnoreCoins;
@@ -167,64 +266,44 @@ impl Options for W12Flag {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = NoScrollPos;
-  type Parity = NoParity;
+  type Parity = Parity21;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
 }
 impl super::SmbSearchCase for W12Flag {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 10]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
 
   type BlockBuffer = BB11;
 
   fn start_states() -> Vec<State> {
-    let s = State {
-      x_pos: 0xb3800,
-      y_pos: 0x19000,
-      x_spd: 0x0,
-      v_force: <Self as Options>::Platform::V_FORCE_AREA_INIT,
-      y_spd: 0x0,
-      v_force_down: <Self as Options>::Platform::V_FORCE_AREA_INIT,
-      facing_dir: Dir::RIGHT,
-      moving_dir: Dir::empty(),
-      player_state: PlayerState::STANDING,
-      x_spd_abs: 0x0,
-      running_speed: false,
-      collision_bits: Dir::LR,
-      side_collision_timer: 0,
-      left_screen_edge_pos: 0x0,
-      jump_swim_timer: 0,
-      running_timer : 0,
-      is_crouching: false,
-      collected_coins: 0,
-      powerup_block_hit: false,
-      powerup_collected: false,
-      parity: 0,
-    };
-    let s = Self::Emu::run_steps_nr(s, &[L|R, A|R]);
-    let s = Self::Emu::run_steps_nr(s, &[R; 19]);
-    let s = Self::Emu::run_steps_nr(s, &[NIL, B|R, B|L]);
-    let s = Self::Emu::run_steps_nr(s, &[B|R; 4]);
-    let s = Self::Emu::run_steps_nr(s, &[A|B|R; 2]);
-    let s = Self::Emu::run_steps_nr(s, &[B|R; 2]);
-    let s = Self::Emu::run_steps_nr(s, &[B; 9]);
-    let s = Self::Emu::run_steps_nr(s, &[B|R; 2]);
-    let s = Self::Emu::run_steps_nr(s, &[B|R, B|L]);
-    vec![s]
+    let s = super::with_start_ram_parity_if_present::<Self>(w12_flag_pipe_exit::<Self>(), FLAG_START_RAM);
+    vec![Self::Emu::new().run_steps_nr(s, &w12_flag_prefix())]
   }
   //const INITIAL_SEARCH_DISTANCE: Dist = 138;
   const SEARCH_SPACE_SIZE_HINT: usize = 30383304;
 }
 impl SearchGoal for W12Flag {
-  fn new() -> Self { Self { heuristic: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) } }
-  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
-    if s.x_pos < 0xc5310 - 0x5000 { Some(7 + 1 + 32 + self.heuristic.get_steps_until_x_pos_at_least(s, 0xc5310 - 0x5000)) } // ensure fast speedup
-    else if s.x_pos < 0xc53f0 { Some(7 + self.heuristic.get_steps_until_bounds_at_least(s, 0xc53f0, 0x19b00)) } // align for clip
-    else { Some(self.heuristic.get_steps_until_bounds_at_least(s, 0xc5600, 0x1a200)) } // do the clip
+  fn new(_: ()) -> Self {
+    let start_states = <Self as super::SmbSearchCase>::start_states();
+    let game_timer = super::flag_game_timer(FLAG_START_RAM, FLAG_GAME_TIMER).after_frames(w12_flag_prefix().len() as Dist);
+    Self {
+      heuristic: BoundsHeuristic::new::<Self>(&start_states),
+      game_timer,
+      level_end: LevelEndBounds::new(game_timer, start_states[0].parity, FLAG_MAX_STEPS),
+    }
+  }
+  fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
+    let steps_until_flag =
+      if s.x_pos < 0xc5310 - 0x5000 { 7 + 1 + 32 + self.heuristic.get_steps_until_x_pos_at_least(s, 0xc5310 - 0x5000) } // ensure fast speedup
+      else if s.x_pos < 0xc53f0 { 7 + self.heuristic.get_steps_until_bounds_at_least(s, 0xc53f0, 0x19b00) } // align for clip
+      else { self.heuristic.get_steps_until_bounds_at_least(s, 0xc5600, 0x1a200) }; // do the clip
+    self.level_end.min_frames_until_next_level(steps_already_taken, steps_already_taken + steps_until_flag)
   }
   fn is_goal_state(&self, s: &State, emu_result: &EmuResult) -> bool {
-    if let &EmuResult::StateChangeFlag(cx, cy) = emu_result {
-      cx == 0xc6 && cy == 9 && s.y_pos >= 0x1a200 && (s.x_pos & 0xf0) >= 0x70
-    } else { false }
+    super::is_half_flag_pole_grab(s, emu_result)
+  }
+  fn frames_after_goal(&self, s: &State, steps: Dist) -> Dist {
+    super::frames_until_next_level::<Self, BB11>(s, &self.game_timer, steps)
   }
 }
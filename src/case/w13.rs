@@ -1,8 +1,10 @@
 use blockbuffer::world1::*;
 use emu::{Emu, EmuResult};
 #[allow(unused_imports)] use emu::inputs::*;
-use heuristics::{BoundsHeuristic, SearchGoal};
+use enemy::{ENEMY_BOUNDING_BOX, Enemy, EnemyKind};
+use heuristics::{BoundsHeuristic, SearchGoal, min_x_pos_heuristic};
 use heuristics::xpos::XPosHeuristic;
+use lift::{Lift, lift_path};
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
 
@@ -57,6 +59,22 @@ impl super::SmbSearchCase for W13PowerupSmall {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     }]
     // )), 10)
   }
@@ -64,7 +82,7 @@ impl super::SmbSearchCase for W13PowerupSmall {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000; //79761384;
 }
 impl SearchGoal for W13PowerupSmall {
-  fn new() -> Self { return Self { max_x_pos: 0x3de70, h: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x3de70, h: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
     if rel_x_pos > 0x70 { return None; }
@@ -158,6 +176,22 @@ impl super::SmbSearchCase for W13PowerupBig {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     println!("start state {}", s);
     // super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
@@ -168,7 +202,7 @@ impl super::SmbSearchCase for W13PowerupBig {
   const SEARCH_SPACE_SIZE_HINT: usize = 59989102;
 }
 impl SearchGoal for W13PowerupBig {
-  fn new() -> Self { return Self { max_x_pos: 0x3d3d0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x3d3d0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
     let steps_already_taken = steps_already_taken;
 
@@ -216,6 +250,14 @@ impl SearchGoal for W13PowerupBig {
   }
 }
 
+// Red Koopa Troopa of W13FloorClip, walking left by half a pixel per frame (Enemy_X_Speed 0xf8) on the ground. It is
+// at pixel x 0x80b (subpixel unknown) in the frame the player bounces off it, 42 steps into the search after the 15
+// steps of the start states.
+const KOOPA_BOUNCE_FRAME: i32 = 15 + 42;
+const KOOPA_X_POS: i32 = 0x80b80;
+const KOOPA_X_SPD: i32 = 0x80;
+const KOOPA_Y_POS: i32 = 0xb8;
+
 /// Floor clip using Red Koopa in 1-3 as big Mario
 /// Input sequence: [20x R, 7x NIL, 2x L, 1x NIL, 8x L, 2x NIL, 10x R, 1x NIL, 1x L, 6x NIL] (len: 58)
 // Possible jump heights: Standing jump for 15 or 22 frames (all other heights or speeds don't get far enough into the block for a successful clip) 
@@ -230,16 +272,25 @@ impl Options for W13FloorClip {
   type Platform = NTSC;
   type PlayerSize = Big;
   type Swim = NotSwimming;
-  type PowerupHandler = ImaginaryPowerup; // used to store whether Koopa was hit
+  type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = NoScrollPos;
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
+  type EnemyHandler = Self;
+}
+impl EnemyHandler for W13FloorClip {
+  const ENEMY_COUNT: usize = 1;
+  const ENEMY_FRAME_BITS: usize = 7;
+  fn get_enemy(s: &State, _: usize) -> Enemy {
+    let frames_until_bounce = KOOPA_BOUNCE_FRAME - s.object_frame as i32;
+    Enemy { x_pos: (KOOPA_X_POS + frames_until_bounce * KOOPA_X_SPD) >> 8, y_pos: KOOPA_Y_POS, kind: EnemyKind::KoopaTroopa, bounding_box: ENEMY_BOUNDING_BOX }
+  }
 }
 impl super::SmbSearchCase for W13FloorClip {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 10]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
 
   type BlockBuffer = BB13;
 
@@ -266,9 +317,25 @@ impl super::SmbSearchCase for W13FloorClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    let s = Self::Emu::run_steps_nr(s, &[A; 14]); // 15xA
-    let s = Self::Emu::run_steps_nr(s, &[NIL]);
+    let s = Self::Emu::new().run_steps_nr(s, &[A; 14]); // 15xA
+    let s = Self::Emu::new().run_steps_nr(s, &[NIL]);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
     // )), 16)
@@ -277,14 +344,8 @@ impl super::SmbSearchCase for W13FloorClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W13FloorClip {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
-    // Corresponds to Koopa position 0x80bxx
-    if _steps_already_taken == 42 && !s.powerup_block_hit && s.y_pos < 0x1ba00 + if s.is_crouching { 0 } else { 0xc00 } && s.y_pos >= 0x1a100 && s.x_pos >= 0x7ff00 { // Koopa collision
-      if s.y_spd < 0x100 { return None; } // Injured by Koopa
-      s.powerup_block_hit = true;
-      s.y_spd = -0x400 + (s.y_spd & 0xff);
-    }
     if s.x_pos >= 0x80800 && s.y_pos < 0x1b500 { return None; } // no floor clip
 
     if s.x_pos < 0x7d580 { return None; } // going backwards
@@ -357,6 +418,22 @@ impl super::SmbSearchCase for W13FloorClipSpeedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -366,7 +443,7 @@ impl super::SmbSearchCase for W13FloorClipSpeedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W13FloorClipSpeedup {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     if s.y_pos < 0x1b500 { return None; } // no floor clip
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x84470))
@@ -435,9 +512,25 @@ impl super::SmbSearchCase for W13FloorFlag {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    let s = Self::Emu::run_steps_nr(s, &[B|R; 120]);
-    //let s = Self::Emu::run_steps_nr(s, &[L; 8]);
+    let s = Self::Emu::new().run_steps_nr(s, &[B|R; 120]);
+    //let s = Self::Emu::new().run_steps_nr(s, &[L; 8]);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
     // )), 16)
@@ -446,7 +539,7 @@ impl super::SmbSearchCase for W13FloorFlag {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W13FloorFlag {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x97600))
   }
@@ -504,85 +597,184 @@ impl super::SmbCase for EmulatorTesting {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     let h = ::heuristics::xpos::XPosHeuristic::new::<Self>(&vec![s.clone()]);
+    let emu = Self::Emu::new();
 
     println!("Old State: {}", s);
-    let s = Self::Emu::run_steps_nr(s, &[A|R, R]);
-    let s = Self::Emu::run_steps_nr(s, &[NIL; 8]);
-    let s = Self::Emu::run_steps_nr(s, &[L; 10]);
+    let s = emu.run_steps_nr(s, &[A|R, R]);
+    let s = emu.run_steps_nr(s, &[NIL; 8]);
+    let s = emu.run_steps_nr(s, &[L; 10]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[A|L]);
+    let s = emu.run_steps_nr(s, &[A|L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[NIL]);
+    let s = emu.run_steps_nr(s, &[NIL]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[NIL]);
+    let s = emu.run_steps_nr(s, &[NIL]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L|R]);
+    let s = emu.run_steps_nr(s, &[L|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L|R]);
+    let s = emu.run_steps_nr(s, &[L|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L|R]);
+    let s = emu.run_steps_nr(s, &[L|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L|R]);
+    let s = emu.run_steps_nr(s, &[L|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L|R]);
+    let s = emu.run_steps_nr(s, &[L|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[B|R]);
+    let s = emu.run_steps_nr(s, &[B|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[L]);
+    let s = emu.run_steps_nr(s, &[L]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[A]);
+    let s = emu.run_steps_nr(s, &[A]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[NIL]);
+    let s = emu.run_steps_nr(s, &[NIL]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[NIL]);
+    let s = emu.run_steps_nr(s, &[NIL]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let mut s = Self::Emu::run_steps_nr(s, &[R]);
+    let mut s = emu.run_steps_nr(s, &[R]);
     s.player_state = PlayerState::STANDING;
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[A|R]);
+    let s = emu.run_steps_nr(s, &[A|R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
-    let s = Self::Emu::run_steps_nr(s, &[R]);
+    let s = emu.run_steps_nr(s, &[R]);
     println!("h: {}", h.get_steps_until_x_pos_at_least(&s, 0x3d330));
     println!("New State: {}", s);
   }
-}
\ No newline at end of file
+}
+
+// RAM dump taken in front of the first lift of 1-3, for the player and the lifts in the start state. There is no
+// hand-built fallback, the case fails without it.
+const LIFT_START_RAM: &str = "w13lift.ram";
+// Lifts loaded in the dump
+const LIFT_COUNT: usize = 1;
+const LIFT_FRAME_BITS: usize = 9;
+
+lazy_static! {
+  static ref W13_LIFT_PATHS: Vec<Vec<Lift>> = {
+    let (motions, frame_counter) = ::ram::lifts_from_ram(&::ram::load_ram(LIFT_START_RAM));
+    assert!(motions.len() == LIFT_COUNT, "{} has {} lifts instead of {}", LIFT_START_RAM, motions.len(), LIFT_COUNT);
+    motions.into_iter().map(|motion| lift_path(motion, frame_counter, (1 << LIFT_FRAME_BITS) - 1)).collect()
+  };
+}
+
+/// Ride the first lift of 1-3 and get past it
+#[allow(dead_code)]
+pub struct W13Lift {
+  goal_x_pos: i32,
+}
+impl Options for W13Lift {
+  type CoinHandler = IgnoreCoins;
+  type Platform = NTSC;
+  type PlayerSize = Small;
+  type Swim = NotSwimming;
+  type PowerupHandler = NoPowerups;
+  type RunningTimer = NoRunningTimer;
+  type ScrollPos = NoScrollPos;
+  type Parity = NoParity;
+  type VerticalPipeHandler = IgnoreVerticalPipes;
+  type YPosFractionalBehavior = KeepYPosFractionals;
+  type LiftHandler = Self;
+}
+impl LiftHandler for W13Lift {
+  const LIFT_COUNT: usize = LIFT_COUNT;
+  const LIFT_FRAME_BITS: usize = LIFT_FRAME_BITS;
+  fn get_lift(s: &State, lift: usize) -> Lift {
+    W13_LIFT_PATHS[lift][s.object_frame as usize]
+  }
+}
+impl super::SmbSearchCase for W13Lift {
+  type SearchGoal = Self;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
+
+  type BlockBuffer = BB13;
+
+  fn start_states() -> Vec<State> {
+    vec![super::load_start_state::<Self>(LIFT_START_RAM)]
+  }
+  const SEARCH_SPACE_SIZE_HINT: usize = 10000000;
+}
+impl SearchGoal for W13Lift {
+  fn new(_: ()) -> Self {
+    assert!(W13_LIFT_PATHS.iter().all(|path| path.iter().all(|lift| lift.x_pos >> 8 == path[0].x_pos >> 8)), "heuristic assumes lifts that don't move the player right");
+    // right edge of the lift anywhere on its path
+    let goal_x_pos = W13_LIFT_PATHS.iter().flat_map(|path| path.iter()).map(|lift| lift.hitbox().x2 << 8).max().unwrap();
+    Self { goal_x_pos }
+  }
+  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    if s.object_frame as usize + 1 >= 1 << LIFT_FRAME_BITS { return None; } // lift paths end
+    Some(min_x_pos_heuristic::<<Self as Options>::Platform>(s, self.goal_x_pos))
+  }
+  fn is_goal_state(&self, s: &State, _: &EmuResult) -> bool {
+    s.x_pos >= self.goal_x_pos
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn floor_clip_replay_reaches_goal() {
+    let (s, emu_result) = ::case::replay::<W13FloorClip>("[20x R, 7x NIL, 2x L, 1x NIL, 8x L, 2x NIL, 10x R, 1x NIL, 1x L, 6x NIL] (len: 58)");
+    assert_eq!(emu_result, EmuResult::Success);
+    // bounced off the Koopa into the floor, at the goal x_pos
+    assert_eq!(s.stomped_enemies, 1);
+    assert_eq!((s.x_pos, s.y_pos, s.x_spd, s.y_spd), (0x80fe0, 0x1c0d0, 0x40, 0));
+    assert_eq!((s.player_state, s.is_crouching, s.collision_bits), (PlayerState::STANDING, false, Dir::RIGHT));
+  }
+}
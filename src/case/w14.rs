@@ -1,4 +1,6 @@
+use blockbuffer::runtime::{Level, LevelSource, RuntimeBlockBuffer};
 use blockbuffer::world1::*;
+use bowser::BowserPath;
 #[allow(unused_imports)] use emu::{Emu, EmuResult};
 #[allow(unused_imports)] use emu::inputs::*;
 use heuristics::SearchGoal;
@@ -6,6 +8,25 @@ use heuristics::xpos::XPosHeuristic;
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
 
+// The castle at the end of 1-4 is decoded from the ROM at SMB_ROM, Bowser is read from a RAM dump taken in the start
+// state. There is no hand-built fallback for either.
+const BOWSER_START_RAM: &str = "w14bowser.ram";
+const BOWSER_FRAME_BITS: usize = 8;
+
+lazy_static! {
+  static ref W14_BOWSER: BowserPath = ::ram::bowser_path_from_ram(&::ram::load_ram(BOWSER_START_RAM), (1 << BOWSER_FRAME_BITS) - 1);
+}
+
+/// The castle of 1-4, decoded from the ROM.
+pub enum W14Castle {}
+impl LevelSource for W14Castle {
+  fn level() -> Level {
+    let (level, warnings) = ::blockbuffer::areaparser::decode_rom_area(&::rom::rom_path(), 0, 4);
+    for warning in warnings { println!("WARNING: 1-4 castle: {}", warning); }
+    level
+  }
+}
+
 fn w14_start<O: Options>() -> State {
   State {
     x_pos: 0x2800,
@@ -29,6 +50,22 @@ fn w14_start<O: Options>() -> State {
     powerup_block_hit: false,
     powerup_collected: false,
     parity: 0,
+    injured: false,
+    injury_timer: 0,
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
   }
 }
 
@@ -62,7 +99,7 @@ impl super::SmbSearchCase for W14Speedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 0;
 }
 impl SearchGoal for W14Speedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x92a0))
   }
@@ -120,6 +157,22 @@ impl super::SmbSearchCase for W14Powerup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     println!("start state {}", s);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
@@ -130,7 +183,7 @@ impl super::SmbSearchCase for W14Powerup {
   const SEARCH_SPACE_SIZE_HINT: usize = 12550052;
 }
 impl SearchGoal for W14Powerup {
-  fn new() -> Self { return Self { max_x_pos: 0x22890, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x22890, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
     if s.y_pos >= 0x18500 { return None } // too low
 
@@ -175,3 +228,62 @@ impl SearchGoal for W14Powerup {
     println!("best max_x_pos so far: {:x}", self.max_x_pos);
   }
 }
+
+
+/// Run past Bowser and grab the axe in 1-4
+#[allow(dead_code)]
+pub struct W14Bowser {
+  max_x_pos: i32,
+  h: XPosHeuristic,
+}
+impl Options for W14Bowser {
+  type CoinHandler = IgnoreCoins;
+  type Platform = NTSC;
+  type PlayerSize = Big;
+  type Swim = NotSwimming;
+  type PowerupHandler = NoPowerups;
+  type RunningTimer = NoRunningTimer;
+  type ScrollPos = NoScrollPos;
+  type Parity = NoParity;
+  type VerticalPipeHandler = IgnoreVerticalPipes;
+  type YPosFractionalBehavior = KeepYPosFractionals;
+  type HazardHandler = Self;
+}
+impl HazardHandler for W14Bowser {
+  const HAZARDS: bool = true;
+  const HAZARD_FRAME_BITS: usize = BOWSER_FRAME_BITS;
+  const PIRANHA_PLANT_BITS: usize = 0;
+  const BOWSER_BITS: usize = BOWSER_FRAME_BITS + 1;
+  fn is_touching_hazard(s: &State) -> bool { W14_BOWSER.hits_player::<Self>(s) }
+  fn move_hazards(s: &mut State) -> () { W14_BOWSER.update_pass_frame(s) }
+}
+impl super::SmbSearchCase for W14Bowser {
+  type SearchGoal = Self;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 12]>, Dist>;
+
+  type BlockBuffer = RuntimeBlockBuffer<W14Castle>;
+
+  fn start_states() -> Vec<State> {
+    vec![super::load_start_state::<Self>(BOWSER_START_RAM)]
+  }
+  const SEARCH_SPACE_SIZE_HINT: usize = 100000000;
+}
+impl SearchGoal for W14Bowser {
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    if s.object_frame as usize >= W14_BOWSER.len() { return None; } // Bowser path ends
+    Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
+  }
+  fn is_goal_state(&self, _: &State, emu_result: &EmuResult) -> bool {
+    if let &EmuResult::StateChangeAxe(_, _) = emu_result { true } else { false }
+  }
+  fn track_metric(&mut self, s: &State) -> () {
+    if self.max_x_pos < s.x_pos {
+      self.max_x_pos = s.x_pos;
+      println!("new best max_x_pos: {:x}", self.max_x_pos);
+    }
+  }
+  fn report_metrics(&self) -> () {
+    println!("best max_x_pos so far: {:x}", self.max_x_pos);
+  }
+}
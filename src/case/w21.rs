@@ -1,7 +1,8 @@
+use blockbuffer::BlockBuffer;
 use blockbuffer::world2::*;
 #[allow(unused_imports)] use emu::{Emu, EmuResult};
 #[allow(unused_imports)] use emu::inputs::*;
-use heuristics::SearchGoal;
+use heuristics::{SearchGoal, min_x_pos_heuristic};
 use heuristics::xpos::XPosHeuristic;
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
@@ -24,6 +25,7 @@ impl Options for W21ScreenScrollRightCollision {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
+  type ScrollLock = NoScrollLock; // 2-1 has no ScrollLockObject
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
@@ -57,6 +59,22 @@ impl super::SmbSearchCase for W21ScreenScrollRightCollision {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -66,7 +84,7 @@ impl super::SmbSearchCase for W21ScreenScrollRightCollision {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21ScreenScrollRightCollision {
-  fn new() -> Self { return Self { max_x_pos: 0x10680, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x10680, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -115,6 +133,7 @@ impl Options for W21ScreenScrollLeftCollision {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
+  type ScrollLock = NoScrollLock; // 2-1 has no ScrollLockObject
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
@@ -148,6 +167,22 @@ impl super::SmbSearchCase for W21ScreenScrollLeftCollision {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -157,7 +192,7 @@ impl super::SmbSearchCase for W21ScreenScrollLeftCollision {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21ScreenScrollLeftCollision {
-  fn new() -> Self { return Self { max_x_pos: 0x13440, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x13440, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -203,6 +238,7 @@ impl Options for W21ScreenScrollBlock3Clip {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
+  type ScrollLock = NoScrollLock; // 2-1 has no ScrollLockObject
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
@@ -236,6 +272,22 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3Clip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -245,7 +297,7 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3Clip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21ScreenScrollBlock3Clip {
-  fn new() -> Self { return Self { max_x_pos: 0x23900, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x23900, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -293,6 +345,7 @@ impl Options for W21ScreenScrollBlock3ClipSpeedup {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
+  type ScrollLock = NoScrollLock; // 2-1 has no ScrollLockObject
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
@@ -326,6 +379,22 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3ClipSpeedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -335,7 +404,7 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3ClipSpeedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21ScreenScrollBlock3ClipSpeedup {
-  fn new() -> Self { return Self { max_x_pos: 0x24de0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x24de0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -408,6 +477,7 @@ impl Options for W21ScreenScrollBlock3PipeClip {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
+  type ScrollLock = NoScrollLock; // 2-1 has no ScrollLockObject
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
@@ -441,8 +511,24 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3PipeClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    let s = Self::Emu::run_steps_nr(s, &[A; 11-5]);
+    let s = Self::Emu::new().run_steps_nr(s, &[A; 11-5]);
     super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
     )), 0)
@@ -451,7 +537,7 @@ impl super::SmbSearchCase for W21ScreenScrollBlock3PipeClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21ScreenScrollBlock3PipeClip {
-  fn new() -> Self { return Self { max_x_pos: 0x4c000, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x4c000, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -532,6 +618,22 @@ impl super::SmbSearchCase for W21FloorClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -541,7 +643,7 @@ impl super::SmbSearchCase for W21FloorClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W21FloorClip {
-  fn new() -> Self { return Self { max_x_pos: 0x9cf20, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x9cf20, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     // Corresponds to Koopa position 0x80bxx
     if _steps_already_taken == 11 && s.y_pos >= 0x1c500 { // Koopa collision
@@ -618,6 +720,22 @@ impl super::SmbSearchCase for W21FloorFlag {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -627,7 +745,7 @@ impl super::SmbSearchCase for W21FloorFlag {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W21FloorFlag {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0xc7600))
   }
@@ -637,3 +755,51 @@ impl SearchGoal for W21FloorFlag {
     } else { false }
   }
 }
+
+// Jumpspring in front of the wall at the end of 2-1, and the ground left of it
+const JUMPSPRING_X_POS: i32 = 0xbc000;
+const JUMPSPRING_START_X_POS: i32 = 0xb6000;
+
+/// Bounce off the jumpspring at the end of 2-1 over the wall and grab the flag pole, for any FrameCounter alignment
+#[allow(dead_code)]
+pub struct W21Jumpspring {}
+impl Options for W21Jumpspring {
+  type CoinHandler = IgnoreCoins;
+  type Platform = NTSC;
+  type PlayerSize = Big;
+  type Swim = NotSwimming;
+  type PowerupHandler = NoPowerups;
+  type RunningTimer = NoRunningTimer;
+  type ScrollPos = NoScrollPos;
+  type Parity = Parity4; // FrameCounter & 3
+  type VerticalPipeHandler = IgnoreVerticalPipes;
+  type YPosFractionalBehavior = KeepYPosFractionals;
+  type JumpspringHandler = Jumpsprings;
+}
+impl super::SmbSearchCase for W21Jumpspring {
+  type SearchGoal = Self;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
+
+  type BlockBuffer = BB21;
+
+  fn start_states() -> Vec<State> {
+    (0..4).map(|parity| {
+      let mut s = ::transition::entrance_state::<Self>(JUMPSPRING_START_X_POS, 0x1b000, PlayerState::STANDING);
+      s.parity = parity;
+      s
+    }).collect()
+  }
+  const SEARCH_SPACE_SIZE_HINT: usize = 10000000;
+}
+impl SearchGoal for W21Jumpspring {
+  fn new(_: ()) -> Self {
+    assert!(BB21.get_block_at((JUMPSPRING_X_POS >> 12) as usize, 9) == 0x67, "no jumpspring at {:#x}", JUMPSPRING_X_POS);
+    Self {}
+  }
+  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    Some(min_x_pos_heuristic::<<Self as Options>::Platform>(s, 0xc7600))
+  }
+  fn is_goal_state(&self, _: &State, emu_result: &EmuResult) -> bool {
+    if let &EmuResult::StateChangeFlag(cx, _) = emu_result { cx == 0xc8 } else { false }
+  }
+}
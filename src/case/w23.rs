@@ -55,6 +55,22 @@ impl super::SmbSearchCase for W23FloorClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // State { // created by jump x 11
     //   x_pos: 0xcdd10,
@@ -78,6 +94,22 @@ impl super::SmbSearchCase for W23FloorClip {
     //   powerup_block_hit: false,
     //   powerup_collected: false,
     //   parity: 0,
+    //   injured: false,
+    //   injury_timer: 0,
+    //   climb_side_timer: 0,
+    //   vine_timer: 0,
+    //   area: 0,
+    //   area_change_timer: 0,
+    //   object_frame: 0,
+    //   jumpspring_anim_ctrl: 0,
+    //   jumpspring_high_bounce: false,
+    //   piranha_plant_y_pos: 0,
+    //   piranha_plant_timer: 0,
+    //   piranha_plant_rising: false,
+    //   piranha_plant_moving: false,
+    //   bowser_pass_frame: 0,
+    //   bowser_passed: false,
+    //   stomped_enemies: 0,
     // };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -87,7 +119,7 @@ impl super::SmbSearchCase for W23FloorClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W23FloorClip {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     if s.y_pos < 0x1b500 { return None; } // no floor clip
 
@@ -158,6 +190,22 @@ impl super::SmbSearchCase for W23FloorFlag {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -167,7 +215,7 @@ impl super::SmbSearchCase for W23FloorFlag {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W23FloorFlag {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0xe0600))
   }
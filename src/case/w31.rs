@@ -55,8 +55,24 @@ impl super::SmbSearchCase for W31ScreenScrollBlock3PipeClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    let s = Self::Emu::run_steps_nr(s, &[A; 1]);
+    let s = Self::Emu::new().run_steps_nr(s, &[A; 1]);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
     // )), 0)
@@ -65,7 +81,7 @@ impl super::SmbSearchCase for W31ScreenScrollBlock3PipeClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W31ScreenScrollBlock3PipeClip {
-  fn new() -> Self { return Self { max_x_pos: 0x67000, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x67000, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -54,12 +54,28 @@ impl super::SmbSearchCase for W44Speedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     }]
   }
   const SEARCH_SPACE_SIZE_HINT: usize = 0;
 }
 impl SearchGoal for W44Speedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x9270))
   }
@@ -117,6 +133,22 @@ impl super::SmbSearchCase for W44Clip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // State {
     //   x_pos: 0x5a3f0 - 0x30,
@@ -140,11 +172,27 @@ impl super::SmbSearchCase for W44Clip {
     //   powerup_block_hit: false,
     //   powerup_collected: false,
     //   parity: 0,
+    //   injured: false,
+    //   injury_timer: 0,
+    //   climb_side_timer: 0,
+    //   vine_timer: 0,
+    //   area: 0,
+    //   area_change_timer: 0,
+    //   object_frame: 0,
+    //   jumpspring_anim_ctrl: 0,
+    //   jumpspring_high_bounce: false,
+    //   piranha_plant_y_pos: 0,
+    //   piranha_plant_timer: 0,
+    //   piranha_plant_rising: false,
+    //   piranha_plant_moving: false,
+    //   bowser_pass_frame: 0,
+    //   bowser_passed: false,
+    //   stomped_enemies: 0,
     // };
-    // let s = Self::Emu::run_steps_nr(s, &[B; 3]);
-    // let s = Self::Emu::run_steps_nr(s, &[B|R; 3+5]);
-    // let s = Self::Emu::run_steps_nr(s, &[A|B|D; 1]);
-    // let s = Self::Emu::run_steps_nr(s, &[B; 7]);
+    // let s = Self::Emu::new().run_steps_nr(s, &[B; 3]);
+    // let s = Self::Emu::new().run_steps_nr(s, &[B|R; 3+5]);
+    // let s = Self::Emu::new().run_steps_nr(s, &[A|B|D; 1]);
+    // let s = Self::Emu::new().run_steps_nr(s, &[B; 7]);
     println!("start state {}", s);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
       vec![s]
@@ -154,7 +202,7 @@ impl super::SmbSearchCase for W44Clip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W44Clip {
-  fn new() -> Self { return Self { max_x_pos: 0x60d00, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0x60d00, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
 
@@ -190,3 +238,17 @@ impl SearchGoal for W44Clip {
     println!("best max_x_pos so far: {:x}", self.max_x_pos);
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn clip_replay_reaches_goal() {
+    let (s, emu_result) = ::case::replay::<W44Clip>("[1x L, 1x L|R, 18x NIL, 1x A | DOWN, 1x A, 4x NIL, 2x R, 1x NIL, 13x R, 1x B|R, 1x L, 1x A|R, 3x R, 1x A|R, 3x R] (len: 52)");
+    assert_eq!(emu_result, EmuResult::Success);
+    // ejected through the wall, at the goal x_pos
+    assert_eq!((s.x_pos, s.y_pos, s.x_spd, s.y_spd), (0x60d80, 0x1b028, 0x2868, 0));
+    assert_eq!((s.player_state, s.is_crouching, s.collision_bits), (PlayerState::STANDING, false, Dir::LR));
+  }
+}
@@ -1,7 +1,7 @@
 use blockbuffer::world6::*;
 use emu::EmuResult;
 #[allow(unused_imports)] use emu::inputs::*;
-use heuristics::{BoundsHeuristic, SearchGoal};
+use heuristics::{BoundsHeuristic, SearchGoal, min_x_pos_heuristic};
 use heuristics::xpos::XPosHeuristic;
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
@@ -55,6 +55,22 @@ impl super::SmbSearchCase for W62Vine {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     println!("start state {}", s);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
@@ -65,7 +81,7 @@ impl super::SmbSearchCase for W62Vine {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W62Vine {
-  fn new() -> Self { return Self { max_x_pos: 0, h: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: BoundsHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
     let steps_already_taken = steps_already_taken.saturating_add(1);
     // let rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
@@ -146,13 +162,29 @@ impl super::SmbSearchCase for W62VineSpeedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     }]
   }
   const INITIAL_SEARCH_DISTANCE: Dist = 58;
   const SEARCH_SPACE_SIZE_HINT: usize = 100000000;
 }
 impl SearchGoal for W62VineSpeedup {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x1f000))
   }
@@ -160,3 +192,49 @@ impl SearchGoal for W62VineSpeedup {
     s.x_pos >= 0x1f000
   }
 }
+
+
+/// Vine grab in 6-2 and the following speed-up, climbing the vine in between
+#[allow(dead_code)]
+pub struct W62VineClimb {}
+impl Options for W62VineClimb {
+  type CoinHandler = IgnoreCoins;
+  type Platform = NTSC;
+  type PlayerSize = Big;
+  type Swim = NotSwimming;
+  type PowerupHandler = SinglePowerupHandler<::typenum::U17, ::typenum::U3>; // (0x11, 0x3);
+  type RunningTimer = NoRunningTimer;
+  type ScrollPos = NoScrollPos;
+  type Parity = NoParity;
+  type VerticalPipeHandler = IgnoreVerticalPipes;
+  type YPosFractionalBehavior = KeepYPosFractionals;
+  type VineHandler = ClimbGrowingVine<::typenum::U17, ::typenum::U3>;
+}
+impl super::SmbSearchCase for W62VineClimb {
+  type SearchGoal = Self;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 12]>, Dist>;
+
+  type BlockBuffer = BB62Vine;
+
+  fn start_states() -> Vec<State> {
+    <W62Vine as super::SmbSearchCase>::start_states()
+  }
+  const INITIAL_SEARCH_DISTANCE: Dist = 77;
+  const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
+}
+impl SearchGoal for W62VineClimb {
+  fn new(_: ()) -> Self { return Self {}; }
+  fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist> {
+    let steps_already_taken = steps_already_taken.saturating_add(1);
+
+    if steps_already_taken >= 15 && !s.powerup_block_hit { return None; } // hit vine block too late
+    if s.x_pos < 0xdc00 { return None; } // went too far left
+    if s.y_pos >= 0x18100 { return None; } // went too low
+
+    if s.x_pos < 0x12000 { return Some(0); } // climbing the vine can move the player arbitrarily
+    Some(min_x_pos_heuristic::<<Self as Options>::Platform>(s, 0x1f000))
+  }
+  fn is_goal_state(&self, s: &State, _: &EmuResult) -> bool {
+    s.x_pos >= 0x1f000
+  }
+}
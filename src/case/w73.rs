@@ -8,6 +8,7 @@ use state::{CompressedState, Dir, Dist, PlayerState, State};
 
 
 /// Floor clip using Cheep Cheep in 7-3 as big Mario
+/// Input sequence: [2x R, 1x NIL, 2x R, 1x NIL, 1x L, 1x R, 3x NIL, 1x R, 10x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 2x L|R, 16x B|R] (len: 50)
 #[allow(dead_code)]
 pub struct W73FloorClip {
   max_x_pos: i32,
@@ -54,6 +55,22 @@ impl super::SmbSearchCase for W73FloorClip {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -63,7 +80,7 @@ impl super::SmbSearchCase for W73FloorClip {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W73FloorClip {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     if s.y_pos < 0x1b500 { return None; } // no floor clip
 
@@ -133,6 +150,22 @@ impl super::SmbSearchCase for W73FloorFlag {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_and_lr_facing_dir(super::with_all_x_spd_subpixels(
     vec![s]
@@ -142,7 +175,7 @@ impl super::SmbSearchCase for W73FloorFlag {
   const SEARCH_SPACE_SIZE_HINT: usize = 10;
 }
 impl SearchGoal for W73FloorFlag {
-  fn new() -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _steps_already_taken: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0xe0600))
   }
@@ -152,3 +185,17 @@ impl SearchGoal for W73FloorFlag {
     } else { false }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn floor_clip_replay_reaches_goal() {
+    let (s, emu_result) = ::case::replay::<W73FloorClip>("[2x R, 1x NIL, 2x R, 1x NIL, 1x L, 1x R, 3x NIL, 1x R, 10x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 1x L|R, 1x B|R, 2x L|R, 16x B|R] (len: 50)");
+    assert_eq!(emu_result, EmuResult::Success);
+    // below the floor at the goal x_pos
+    assert_eq!((s.x_pos, s.y_pos, s.x_spd, s.y_spd), (0xd2120, 0x1c070, 0x280c, 0));
+    assert_eq!((s.player_state, s.is_crouching, s.collision_bits), (PlayerState::STANDING, false, Dir::RIGHT));
+  }
+}
@@ -1,11 +1,43 @@
 use blockbuffer::world8::*;
 use emu::EmuResult;
 #[allow(unused_imports)] use emu::inputs::*;
+use hazard::{HazardPaths, PiranhaPlant};
 use heuristics::SearchGoal;
 use heuristics::xpos::XPosHeuristic;
 use options::*;
 use state::{CompressedState, Dir, Dist, PlayerState, State};
 
+// RAM dump taken in the start state of W84Speedup, for the hazards already loaded in it. Without it, the case runs
+// without hazards.
+const HAZARD_START_RAM: &str = "w84hazards.ram";
+const HAZARD_FRAME_BITS: usize = 7;
+const HAZARD_FRAMES: usize = (1 << HAZARD_FRAME_BITS) - 1;
+
+// RAM dumps taken in the start states of the pipe clip entries, for the piranha plants in the entered pipes. Without
+// them, the cases run without piranha plants.
+const PART2_PLANT_START_RAM: &str = "w84part2plant.ram";
+const PART3_PLANT_START_RAM: &str = "w84part3plant.ram";
+// MovePiranhaPlant state: y_pos (5 bits), timer (7 bits), rising and moving
+const PIRANHA_PLANT_BITS: usize = 14;
+
+lazy_static! {
+  static ref W84_HAZARDS: HazardPaths = match super::optional_ram(HAZARD_START_RAM, "modeling no firebars or Podoboos") {
+    Some(ram) => ::ram::hazard_paths_from_ram(&ram, HAZARD_FRAMES),
+    None => HazardPaths { frames: HAZARD_FRAMES, firebars: vec![], podoboos: vec![] },
+  };
+  static ref W84_PART2_PIRANHA_PLANT: Option<PiranhaPlant> =
+    super::optional_ram(PART2_PLANT_START_RAM, "modeling no piranha plant").map(|ram| ::ram::piranha_plant_from_ram(&ram));
+  static ref W84_PART3_PIRANHA_PLANT: Option<PiranhaPlant> =
+    super::optional_ram(PART3_PLANT_START_RAM, "modeling no piranha plant").map(|ram| ::ram::piranha_plant_from_ram(&ram));
+}
+
+/// s with the movement of the piranha plant in the RAM dump with the given name, or s unchanged without the dump.
+fn with_piranha_plant_if_present(name: &str, s: State) -> State {
+  match ::ram::try_load_ram(name) {
+    Some(ram) => ::ram::with_piranha_plant_from_ram(&ram, s),
+    None => s,
+  }
+}
 
 /// Initial speed-up starting in 8-4
 /// Input sequence: [1x L|R, 1x A|R, 19x R, 9x NIL, 1x B|R, 1x L, 2x A|R, 37x R, 8x B|R] (len: 79)
@@ -25,10 +57,19 @@ impl Options for W84Speedup {
   type Parity = NoParity;
   type VerticalPipeHandler = IgnoreVerticalPipes;
   type YPosFractionalBehavior = KeepYPosFractionals;
+  type HazardHandler = Self;
+}
+impl HazardHandler for W84Speedup {
+  const HAZARDS: bool = true;
+  const HAZARD_FRAME_BITS: usize = HAZARD_FRAME_BITS;
+  const PIRANHA_PLANT_BITS: usize = 0;
+  const BOWSER_BITS: usize = 0;
+  fn is_touching_hazard(s: &State) -> bool { W84_HAZARDS.hits_player::<Self>(s) }
+  fn move_hazards(_: &mut State) -> () {}
 }
 impl super::SmbSearchCase for W84Speedup {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 10]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 11]>, Dist>;
 
   type BlockBuffer = BB84;
 
@@ -55,15 +96,32 @@ impl super::SmbSearchCase for W84Speedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
-    vec![s]
+    vec![super::with_start_ram_parity_if_present::<Self>(s, HAZARD_START_RAM)]
   }
   const INITIAL_SEARCH_DISTANCE: Dist = 79;
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W84Speedup {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    if s.object_frame as usize >= W84_HAZARDS.frames { return None; } // hazard paths end
     // Some(self.h.get_steps_until_x_pos_at_least(s, 0x9270))
     Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
   }
@@ -131,6 +189,22 @@ impl super::SmbSearchCase for W84Part2Speedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     vec![s]
   }
@@ -138,7 +212,7 @@ impl super::SmbSearchCase for W84Part2Speedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W84Part2Speedup {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x7bd10))
     // Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
@@ -174,13 +248,24 @@ impl Options for W84Part2VertPipeEntry {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = NoScrollPos;
-  type Parity = NoParity;
+  type Parity = Parity2;
   type VerticalPipeHandler = EnterVerticalPipe<::typenum::U152, ::typenum::U4>;
   type YPosFractionalBehavior = KeepYPosFractionals;
+  type HazardHandler = Self;
+}
+impl HazardHandler for W84Part2VertPipeEntry {
+  const HAZARDS: bool = true;
+  const HAZARD_FRAME_BITS: usize = 0;
+  const PIRANHA_PLANT_BITS: usize = PIRANHA_PLANT_BITS;
+  const BOWSER_BITS: usize = 0;
+  fn is_touching_hazard(s: &State) -> bool { W84_PART2_PIRANHA_PLANT.as_ref().map_or(false, |plant| plant.hits_player::<Self>(s)) }
+  fn move_hazards(s: &mut State) -> () {
+    if let Some(ref plant) = *W84_PART2_PIRANHA_PLANT { plant.move_plant(s, s.parity) }
+  }
 }
 impl super::SmbSearchCase for W84Part2VertPipeEntry {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 10]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 12]>, Dist>;
 
   type BlockBuffer = BB84;
 
@@ -207,16 +292,33 @@ impl super::SmbSearchCase for W84Part2VertPipeEntry {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
+    let s = super::with_start_ram_parity_if_present::<Self>(s, PART2_PLANT_START_RAM);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
-    vec![s]
+    vec![with_piranha_plant_if_present(PART2_PLANT_START_RAM, s)]
     // )), 16)
   }
   const INITIAL_SEARCH_DISTANCE: Dist = 37;
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W84Part2VertPipeEntry {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0x98400))
     // Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
@@ -287,6 +389,22 @@ impl super::SmbSearchCase for W84Part3Speedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     vec![s]
   }
@@ -294,7 +412,7 @@ impl super::SmbSearchCase for W84Part3Speedup {
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W84Part3Speedup {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(self.h.get_steps_until_x_pos_at_least(s, 0xcbcc0))
     // Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
@@ -330,13 +448,24 @@ impl Options for W84Part3VertPipeEntry {
   type PowerupHandler = NoPowerups;
   type RunningTimer = NoRunningTimer;
   type ScrollPos = WithScrollPos;
-  type Parity = NoParity;
+  type Parity = Parity2;
   type VerticalPipeHandler = EnterVerticalPipe<::typenum::U212, ::typenum::U5>;
   type YPosFractionalBehavior = KeepYPosFractionals;
+  type HazardHandler = Self;
+}
+impl HazardHandler for W84Part3VertPipeEntry {
+  const HAZARDS: bool = true;
+  const HAZARD_FRAME_BITS: usize = 0;
+  const PIRANHA_PLANT_BITS: usize = PIRANHA_PLANT_BITS;
+  const BOWSER_BITS: usize = 0;
+  fn is_touching_hazard(s: &State) -> bool { W84_PART3_PIRANHA_PLANT.as_ref().map_or(false, |plant| plant.hits_player::<Self>(s)) }
+  fn move_hazards(s: &mut State) -> () {
+    if let Some(ref plant) = *W84_PART3_PIRANHA_PLANT { plant.move_plant(s, s.parity) }
+  }
 }
 impl super::SmbSearchCase for W84Part3VertPipeEntry {
   type SearchGoal = Self;
-  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 12]>, Dist>;
+  type StateStore = ::store::VecHashMap<CompressedState<Self, [u8; 14]>, Dist>;
 
   type BlockBuffer = BB84;
 
@@ -363,16 +492,33 @@ impl super::SmbSearchCase for W84Part3VertPipeEntry {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
+    let s = super::with_start_ram_parity_if_present::<Self>(s, PART3_PLANT_START_RAM);
     // super::with_smaller_x_pos::<Self>(super::with_left_and_right_facing_dir(super::with_all_x_spd_subpixels(
-    vec![s]
+    vec![with_piranha_plant_if_present(PART3_PLANT_START_RAM, s)]
     // )), 16)
   }
   const INITIAL_SEARCH_DISTANCE: Dist = 74;
   const SEARCH_SPACE_SIZE_HINT: usize = 800000000;
 }
 impl SearchGoal for W84Part3VertPipeEntry {
-  fn new() -> Self { return Self { min_x_pos: 0xd4e80, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { min_x_pos: 0xd4e80, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     if (s.left_screen_edge_pos < 0x11 || s.left_screen_edge_pos >= 0x40) && s.x_spd < -0x1c8 { return None; } // turned too early
 
@@ -444,13 +590,29 @@ impl super::SmbSearchCase for W84Part4Speedup {
       powerup_block_hit: false,
       powerup_collected: false,
       parity: 0,
+      injured: false,
+      injury_timer: 0,
+      climb_side_timer: 0,
+      vine_timer: 0,
+      area: 0,
+      area_change_timer: 0,
+      object_frame: 0,
+      jumpspring_anim_ctrl: 0,
+      jumpspring_high_bounce: false,
+      piranha_plant_y_pos: 0,
+      piranha_plant_timer: 0,
+      piranha_plant_rising: false,
+      piranha_plant_moving: false,
+      bowser_pass_frame: 0,
+      bowser_passed: false,
+      stomped_enemies: 0,
     };
     vec![s]
   }
   const SEARCH_SPACE_SIZE_HINT: usize = 80127;
 }
 impl SearchGoal for W84Part4Speedup {
-  fn new() -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
+  fn new(_: ()) -> Self { return Self { max_x_pos: 0, h: XPosHeuristic::new::<Self>(&<Self as super::SmbSearchCase>::start_states()) }; }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     // Some(self.h.get_steps_until_x_pos_at_least(s, 0x3d50))
     Some(self.h.get_steps_until_x_pos_at_least(s, self.max_x_pos + 0x10))
@@ -0,0 +1,125 @@
+use case::{Case, SearchCaseFn, SmbSearchCase, with_search_case};
+use emu::{Emu, SmbEmu, parse_rle, print_rle};
+use transition::{Entrance, enter_area};
+
+const USAGE: &str = "usage: <command> <arg>..., with <case> the name of a search case, e.g. W11Flag
+  run <case>                                   searches for a solution from the start states of the case
+  replay <case> <inputs>                       replays inputs in the format of a found solution, e.g.
+                                               \"[2x R, 1x A|R]\", from every start state of the case
+  replay-fm2 <case> <movie> <first> <last>     replays frames first to last of an .fm2 movie from the first start
+                                               state of the case and prints the trace
+  scan <case> <movie> <first> <last> <window> <step>
+                                               searches every window of frames first to last of an .fm2 movie,
+                                               every step frames, for a faster way to the x position at its end
+  entrance <case> fall <x_pos> <y_pos>
+  entrance <case> pipe-exit <x_pos>
+  entrance <case> vine <screen left x_pos> <frame counter>
+                                               prints the first state under player control after entering the area of
+                                               the case and the frames until then, with positions in hex, e.g. 0x1800,
+                                               and the FrameCounter in the first frame of the area for vines
+  movie <inputs> <path> [<leading frames>]     writes inputs in the format of a found solution as a movie starting
+                                               at power-on, preceded by leading frames empty frames, as .fm2 or .bk2
+  enemies <world> <area> [second-quest]        prints the enemy data of an area of a world (both 0-based) in the ROM
+                                               given by SMB_ROM and the enemies it spawns";
+
+struct Run;
+impl SearchCaseFn for Run {
+  fn call<T: SmbSearchCase>(self) -> () {
+    <T as Case>::run();
+  }
+}
+
+struct Replay<'a>(&'a str);
+impl<'a> SearchCaseFn for Replay<'a> {
+  fn call<T: SmbSearchCase>(self) -> () {
+    ::case::replay_rle::<T>(self.0);
+  }
+}
+
+struct ReplayFm2<'a> {
+  path: &'a str,
+  first_frame: usize,
+  last_frame: usize,
+}
+impl<'a> SearchCaseFn for ReplayFm2<'a> {
+  fn call<T: SmbSearchCase>(self) -> () {
+    ::replay::replay_fm2(&T::Emu::new(), T::start_states().remove(0), self.path, self.first_frame, self.last_frame);
+  }
+}
+
+struct Scan<'a> {
+  path: &'a str,
+  first_frame: usize,
+  last_frame: usize,
+  window: usize,
+  step: usize,
+}
+impl<'a> SearchCaseFn for Scan<'a> {
+  fn call<T: SmbSearchCase>(self) -> () {
+    let inputs = ::movie::fm2::load_inputs(self.path, self.first_frame..self.last_frame + 1);
+    let scan = ::scan::scan::<T>(T::start_states().remove(0), &inputs, self.window, self.step, T::SEARCH_SPACE_SIZE_HINT);
+    if scan.replayed_frames < inputs.len() { println!("replay stopped after {} of {} inputs", scan.replayed_frames, inputs.len()); }
+    for improvement in scan.improvements {
+      print!("frame {}: {} instead of {} frames: ", self.first_frame + improvement.start_frame, improvement.frames, improvement.original_frames);
+      print_rle(improvement.inputs.iter());
+    }
+  }
+}
+
+struct EnterArea(Entrance);
+impl SearchCaseFn for EnterArea {
+  fn call<T: SmbSearchCase>(self) -> () {
+    let (s, frames) = enter_area(&SmbEmu::<T, T::BlockBuffer>::new(), self.0);
+    println!("under player control after {} frames:", frames);
+    print!("{}", s);
+  }
+}
+
+fn arg(args: &[String], i: usize) -> &str {
+  args.get(i).map(|a| a.as_str()).unwrap_or_else(|| panic!("{}", USAGE))
+}
+fn number_arg(args: &[String], i: usize) -> usize {
+  arg(args, i).parse().unwrap_or_else(|_| panic!("{}", USAGE))
+}
+fn hex_arg(args: &[String], i: usize) -> i32 {
+  i32::from_str_radix(arg(args, i).trim_start_matches("0x"), 16).unwrap_or_else(|_| panic!("{}", USAGE))
+}
+fn entrance_arg(args: &[String]) -> Entrance {
+  match arg(args, 1) {
+    "fall" => Entrance::Fall(hex_arg(args, 2), hex_arg(args, 3)),
+    "pipe-exit" => Entrance::PipeExit(hex_arg(args, 2)),
+    "vine" => Entrance::Vine(hex_arg(args, 2), arg(args, 3).parse().unwrap_or_else(|_| panic!("{}", USAGE))),
+    _ => panic!("{}", USAGE),
+  }
+}
+/// Calls f for the search case named by the first argument.
+fn with_case<F: SearchCaseFn>(args: &[String], f: F) -> () {
+  let name = arg(args, 0);
+  if !with_search_case(name, f) { panic!("no search case {}", name); }
+}
+
+/// Writes the inputs of a found solution as a movie, in the format given by the file extension.
+fn save_movie(args: &[String]) -> () {
+  let inputs = parse_rle(arg(args, 0)).unwrap_or_else(|e| panic!("can't parse input sequence: {}", e));
+  let path = arg(args, 1);
+  let leading_frames = if args.len() > 2 { number_arg(args, 2) } else { 0 };
+  let result = if path.ends_with(".fm2") { ::movie::fm2::save_movie(path, &inputs, leading_frames) }
+      else if path.ends_with(".bk2") { ::movie::bk2::save_movie(path, &inputs, leading_frames) }
+      else { panic!("unknown movie format of {}, expected .fm2 or .bk2", path) };
+  result.unwrap_or_else(|e| panic!("can't write {}: {}", path, e));
+}
+
+/// Runs a command given on the command line, see USAGE.
+pub fn run(command: &str, args: &[String]) {
+  match command {
+    "run" => with_case(args, Run),
+    "replay" => with_case(args, Replay(arg(args, 1))),
+    "replay-fm2" => with_case(args, ReplayFm2 { path: arg(args, 1), first_frame: number_arg(args, 2), last_frame: number_arg(args, 3) }),
+    "scan" => with_case(args, Scan { path: arg(args, 1), first_frame: number_arg(args, 2), last_frame: number_arg(args, 3), window: number_arg(args, 4), step: number_arg(args, 5) }),
+    "entrance" => with_case(args, EnterArea(entrance_arg(args))),
+    "movie" => save_movie(args),
+    "enemies" => ::enemydata::print_enemies(&::rom::rom_path(), number_arg(args, 0), number_arg(args, 1),
+        match args.get(2).map(|a| a.as_str()) { None => false, Some("second-quest") => true, _ => panic!("{}", USAGE) }),
+    _ => panic!("{}", USAGE),
+  }
+}
@@ -1,5 +1,7 @@
 use blockbuffer::BlockBuffer;
 use blockbuffer::util::*;
+use hitbox::Hitbox;
+use lift::Lift;
 use state::{Dir,PlayerState,State};
 use std::marker::PhantomData;
 use options::*;
@@ -68,24 +70,160 @@ pub fn print_rle<I: Iterator>(mut inputs: I) -> () where I::Item: PartialEq + ::
   println!("] (len: {})", len);
 }
 
+/// Malformed input sequence passed to parse_rle.
+#[derive(Debug, Eq, PartialEq)]
+pub enum RleParseError {
+  MissingBrackets,
+  MalformedRun(String), // not of the form "<count>x <input>"
+  InvalidCount(String),
+  InvalidButton(String),
+  MalformedLength(String), // not of the form "(len: <count>)"
+  LengthMismatch { expected: usize, actual: usize }, // "(len: ...)" suffix disagrees with the runs
+}
+impl ::std::fmt::Display for RleParseError {
+  fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+    match *self {
+      RleParseError::MissingBrackets => write!(f, "input sequence must be enclosed in [...]"),
+      RleParseError::MalformedRun(ref run) => write!(f, "malformed run \"{}\", expected \"<count>x <input>\"", run),
+      RleParseError::InvalidCount(ref count) => write!(f, "invalid run length \"{}\"", count),
+      RleParseError::InvalidButton(ref button) => write!(f, "invalid button \"{}\"", button),
+      RleParseError::MalformedLength(ref len) => write!(f, "malformed length \"{}\", expected \"(len: <count>)\"", len),
+      RleParseError::LengthMismatch { expected, actual } => write!(f, "input sequence has {} inputs, but len says {}", actual, expected),
+    }
+  }
+}
+
+/// Parses a single input as written by Input's Display implementation, e.g. "A|R", "NIL" or "SELECT | START".
+pub fn parse_input(s: &str) -> Result<Input, RleParseError> {
+  if s.trim() == "NIL" { return Ok(Input::empty()); }
+  s.split('|').map(|button| match button.trim() {
+    "A" => Ok(Input::A),
+    "B" => Ok(Input::B),
+    "SELECT" => Ok(Input::SELECT),
+    "START" => Ok(Input::START),
+    "U" | "UP" => Ok(Input::UP),
+    "D" | "DOWN" => Ok(Input::DOWN),
+    "L" | "LEFT" => Ok(Input::LEFT),
+    "R" | "RIGHT" => Ok(Input::RIGHT),
+    other => Err(RleParseError::InvalidButton(other.to_string())),
+  }).fold(Ok(Input::empty()), |input, button| Ok(input? | button?))
+}
+
+/// Parses an input sequence in the format written by print_rle, e.g. "[3x B|R, 1x A, 42x R]", optionally followed by
+/// its "(len: 46)".
+pub fn parse_rle(s: &str) -> Result<Vec<Input>, RleParseError> {
+  let s = s.trim();
+  let (runs, len) = match s.rfind(']') {
+    Some(end) if s.starts_with('[') => (&s[1..end], s[end + 1..].trim()),
+    _ => return Err(RleParseError::MissingBrackets),
+  };
+  let mut inputs = vec![];
+  for run in runs.split(',').map(|run| run.trim()).filter(|run| !run.is_empty()) {
+    let (count, input) = match run.find("x ") {
+      Some(pos) => (&run[..pos], &run[pos + 2..]),
+      None => return Err(RleParseError::MalformedRun(run.to_string())),
+    };
+    let count: usize = count.trim().parse().map_err(|_| RleParseError::InvalidCount(count.to_string()))?;
+    let input = parse_input(input)?;
+    inputs.extend(::std::iter::repeat(input).take(count));
+  }
+  if !len.is_empty() {
+    let expected = len.trim_start_matches("(len:").trim_end_matches(')').trim();
+    let expected: usize = expected.parse().map_err(|_| RleParseError::MalformedLength(len.to_string()))?;
+    if expected != inputs.len() { return Err(RleParseError::LengthMismatch { expected, actual: inputs.len() }); }
+  }
+  Ok(inputs)
+}
+
 pub trait Emu {
-  fn run_step(State, Input) -> (State, EmuResult);
-  fn run_step_nr(s: State, input: Input) -> State {
-    let (new_state, emu_result) = Self::run_step(s, input);
+  fn new() -> Self;
+  fn run_step(&self, s: State, input: Input) -> (State, EmuResult);
+  fn run_step_nr(&self, s: State, input: Input) -> State {
+    let (new_state, emu_result) = self.run_step(s, input);
     assert!(emu_result == EmuResult::Success);
     new_state
   }
-  fn run_steps_nr(mut s: State, inputs: &[Input]) -> State {
-    for input in inputs { s = Self::run_step_nr(s, *input); }
+  fn run_steps_nr(&self, mut s: State, inputs: &[Input]) -> State {
+    for input in inputs { s = self.run_step_nr(s, *input); }
     s
   }
-  fn iterate_entrance(mut s: State) -> State {
-    while (s.y_pos & 0xff00) < 0x3000 { s = Self::run_step_nr(s, inputs::NIL); }
+  fn iterate_entrance(&self, mut s: State) -> State {
+    while (s.y_pos & 0xff00) < 0x3000 { s = self.run_step_nr(s, inputs::NIL); }
     s
   }
 }
 
+// Climb_Y_SpeedData and Climb_Y_MForceData
+pub const CLIMB_Y_SPD_UP: i16 = -0xe0;
+pub const CLIMB_Y_SPD_DOWN: i16 = 0x1ff;
+
+// PlayerCtrlRoutine and CheckPlayerVertical
+pub const BOTTOM_OF_SCREEN_Y_POS: i32 = 0x1d000;
+
+// ChkForLandJumpSpring and JumpspringHandler
+const JUMPSPRING_FORCE: i16 = -0x700;
+const JUMPSPRING_HIGH_FORCE: i16 = -0xc00;
+const JUMPSPRING_BOUNCE_ANIM_CTRL: u8 = 4; // JumpspringAnimCtrl in the frame the player is launched
+
+// Phases of the injury sequence. State::injury_timer counts down the frames in which ForceInjury halts the timers with
+// TimerControl, followed by the InjuryTimer value, which is decremented with the interval timers.
+const INJURY_TIMER: u8 = 8; // ForceInjury
+const INJURY_FREEZE_FRAMES: u8 = 15; // TimerControl 0xfe to 0xf0, player size changes at the end
+const INJURY_BLINK_FRAMES: u8 = 40; // TimerControl 0xef to 0xc8, PlayerCtrlRoutine runs except on the last frame
+
+fn is_injury_freeze_frame(injury_timer: u8) -> bool {
+  injury_timer >= INJURY_TIMER + INJURY_BLINK_FRAMES || injury_timer == INJURY_TIMER
+}
+/// Whether the player is frozen by an injury in the frame following s, ignoring all inputs.
+pub fn is_frozen_after_injury(s: &State) -> bool {
+  s.injury_timer > INJURY_TIMER && is_injury_freeze_frame(s.injury_timer - 1)
+}
+/// Whether the interval timers are decremented in the frame following s, i.e. IntervalTimerControl wraps around. Only
+/// known when the parity tracks IntervalTimerControl (Parity21).
+fn is_interval_timer_frame<O: Options>(s: &State) -> bool {
+  assert!(O::Parity::PARITY == 21, "interval timers are only tracked with Parity21");
+  s.parity == O::Parity::PARITY - 1
+}
+
+/// Whether the player is above the screen or below its bottom, where inputs are ignored and the player doesn't touch
+/// other objects.
+pub fn is_vertically_offscreen(s: &State) -> bool {
+  s.y_pos < 0x10000 || s.y_pos >= BOTTOM_OF_SCREEN_Y_POS
+}
+
+/// Pixel x position of the left edge of the screen. The screen page is reconstructed from the player, who is kept on
+/// screen by the scroll handler; without a tracked scroll position, the player is assumed to be in the middle of it.
+pub fn screen_left_x_pos<O: Options>(s: &State) -> i32 {
+  if !O::ScrollPos::TRACK_SCROLL_POS { return (s.x_pos >> 8) - 0x70; }
+  let mut rel_x_pos = ((s.x_pos >> 8) - s.left_screen_edge_pos as i32) & 0xff;
+  // ChkPOffscr keeps the player within 0..=0xef of the edge, and only the side collision checks and lifts run after it,
+  // moving the player by a few pixels: values from 0xf8 on can only come from being pushed past the left edge
+  if rel_x_pos >= 0xf8 { rel_x_pos -= 0x100; }
+  (s.x_pos >> 8) - rel_x_pos
+}
+
+/// Emulator of the player in the area of block buffer B.
 pub struct SmbEmu<O: Options, B: BlockBuffer> {
+  block_buffer: B,
+  options: PhantomData<O>,
+}
+impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
+  pub fn with_block_buffer(block_buffer: B) -> Self {
+    SmbEmu { block_buffer, options: PhantomData }
+  }
+}
+impl<O: Options, B: BlockBuffer> Emu for SmbEmu<O, B> {
+  fn new() -> Self {
+    Self::with_block_buffer(B::new())
+  }
+  fn run_step(&self, s: State, input: Input) -> (State, EmuResult) {
+    SmbStep::<O, B>::new(&self.block_buffer, s, input).run_step()
+  }
+}
+
+/// A single frame of SmbEmu.
+struct SmbStep<'a, O: Options, B: BlockBuffer + 'a> {
+  block_buffer: &'a B,
   s: State,
   joypad: Input,
   joypad_lr: Dir,
@@ -98,11 +236,11 @@ pub struct SmbEmu<O: Options, B: BlockBuffer> {
   x_scroll: i8, // only for scroll
   side_collision: bool, // only for scroll
   options: PhantomData<O>,
-  block_buffer: PhantomData<B>,
 }
-impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
-  fn new(s: State, joypad: Input) -> Self {
-    SmbEmu {
+impl<'a, O: Options, B: BlockBuffer> SmbStep<'a, O, B> {
+  fn new(block_buffer: &'a B, s: State, joypad: Input) -> Self {
+    SmbStep {
+      block_buffer,
       s,
       joypad,
       joypad_lr: Dir::empty(),
@@ -115,48 +253,157 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
       x_scroll: 0,
       side_collision: false,
       options: PhantomData,
-      block_buffer: PhantomData,
     }
   }
   fn run_step(mut self) -> (State, EmuResult) {
+    let timers_halted = O::DamageHandler::TAKES_DAMAGE && self.s.injury_timer > INJURY_TIMER; // TimerControl set
+    if timers_halted {
+      self.s.injury_timer -= 1;
+      if is_injury_freeze_frame(self.s.injury_timer) {
+        if !O::Parity::HALTED_BY_TIMER_CONTROL { self.s.parity = (self.s.parity + 1) % O::Parity::PARITY; }
+        return (self.s, EmuResult::Success);
+      }
+    } else if O::DamageHandler::TAKES_DAMAGE && self.s.injury_timer > 0 && is_interval_timer_frame::<O>(&self.s) {
+      self.s.injury_timer -= 1;
+    }
+
     self.started_on_ground = self.s.is_on_ground();
-    if O::RunningTimer::USE_RUNNING_TIMER && self.s.running_timer > 0 { self.s.running_timer -= 1; }
+    if O::RunningTimer::USE_RUNNING_TIMER && !timers_halted && self.s.running_timer > 0 { self.s.running_timer -= 1; }
+    if O::VineHandler::CLIMB_VINES && !timers_halted && self.s.climb_side_timer > 0 { self.s.climb_side_timer -= 1; }
+    O::VineHandler::grow_vine(&mut self.s);
 
-    let result = self.player_ctrl_routine();
+    let mut result = self.player_ctrl_routine();
 
     if O::ScrollPos::TRACK_SCROLL_POS && self.side_collision { self.s.side_collision_timer = 0xf; }
-    else if O::ScrollPos::TRACK_SCROLL_POS && self.s.side_collision_timer > 0 { self.s.side_collision_timer -= 1; }
+    else if O::ScrollPos::TRACK_SCROLL_POS && !timers_halted && self.s.side_collision_timer > 0 { self.s.side_collision_timer -= 1; }
+
+    if self.started_jump && O::Swim::is_swimming(&self.s) { self.s.jump_swim_timer = 0x1f; }
+    else if O::Swim::MAY_SWIM && !timers_halted && self.s.jump_swim_timer > 0 { self.s.jump_swim_timer -= 1; }
 
-    if O::Swim::IS_SWIMMING && self.started_jump { self.s.jump_swim_timer = 0x1f; }
-    else if O::Swim::IS_SWIMMING && self.s.jump_swim_timer > 0 { self.s.jump_swim_timer -= 1; }
+    if (O::LiftHandler::LIFT_COUNT > 0 || O::HazardHandler::HAZARDS || O::EnemyHandler::ENEMY_COUNT > 0) && !timers_halted { // objects are frozen by TimerControl
+      let landed_lift = if O::LiftHandler::LIFT_COUNT > 0 && result == EmuResult::Success { self.lift_collision() } else { None };
+      self.s.object_frame += 1;
+      if let Some((i, old_lift)) = landed_lift { self.move_with_lift(i, old_lift); }
+      if O::HazardHandler::HAZARDS { O::HazardHandler::move_hazards(&mut self.s); }
+    }
+
+    if O::JumpspringHandler::JUMPSPRINGS && !timers_halted && self.s.jumpspring_anim_ctrl > 0 { self.jumpspring_handler(); }
+
+    if O::EnemyHandler::ENEMY_COUNT > 0 && result == EmuResult::Success && !timers_halted { result = self.enemy_collision(); }
+    if O::HazardHandler::HAZARDS && result == EmuResult::Success && !timers_halted && self.s.injury_timer == 0 && O::HazardHandler::is_touching_hazard(&self.s) {
+      result = self.injure_player(EmuResult::KilledByHazard);
+    }
 
     if O::YPosFractionalBehavior::CLEAR_Y_POS_FRACTIONALS && self.s.is_on_ground() { self.s.y_pos &= 0xffff00; self.s.v_force_down = O::Platform::V_FORCE_AREA_INIT; }
     if self.s.player_state != PlayerState::JUMPING { self.s.v_force = self.s.v_force_down; } // only needed for JumpSwim, set whenever entered
     if O::YPosFractionalBehavior::CLEAR_Y_POS_FRACTIONALS && self.started_on_ground && self.s.player_state == PlayerState::FALLING { // ran off edge with cleared fractional
       return (self.s, EmuResult::InvalidStateFallingWithClearedYposFractionals);
     }
-    self.s.parity = (self.s.parity + 1) % O::Parity::PARITY;
+    if !timers_halted || !O::Parity::HALTED_BY_TIMER_CONTROL { self.s.parity = (self.s.parity + 1) % O::Parity::PARITY; }
 
     (self.s, result)
   }
+  /// PlayerEnemyCollision with the enemies of O::EnemyHandler: stomping an enemy bounces the player off it, touching
+  /// it otherwise injures the player.
+  fn enemy_collision(&mut self) -> EmuResult {
+    assert!(O::EnemyHandler::ENEMY_COUNT <= 8, "{} enemies don't fit in State::stomped_enemies", O::EnemyHandler::ENEMY_COUNT);
+    if is_vertically_offscreen(&self.s) { return EmuResult::Success; }
+    for i in 0..O::EnemyHandler::ENEMY_COUNT {
+      if self.s.stomped_enemies & (1 << i) != 0 { continue; }
+      let enemy = O::EnemyHandler::get_enemy(&self.s, i);
+      if !enemy.hits_player::<O>(&self.s) { continue; }
+      if enemy.is_stomped(&self.s) { // EnemyStomped
+        self.s.stomped_enemies |= 1 << i;
+        self.s.y_spd = enemy.bounce_y_spd(&self.s);
+      } else if self.s.injury_timer == 0 { // InjurePlayer
+        let result = self.injure_player(EmuResult::KilledByEnemy);
+        if result != EmuResult::Success { return result; }
+      }
+    }
+    EmuResult::Success
+  }
+  /// InjurePlayer: big players shrink and the timers are halted while they blink, small players die. Like the game
+  /// (SetKRout), this puts the player in the jumping state.
+  fn injure_player(&mut self, killed_result: EmuResult) -> EmuResult {
+    if !O::DamageHandler::TAKES_DAMAGE || !O::PlayerSize::is_big(&self.s) { return killed_result; }
+    self.s.injured = true;
+    self.s.injury_timer = INJURY_TIMER + INJURY_FREEZE_FRAMES + INJURY_BLINK_FRAMES;
+    self.s.is_crouching = false;
+    self.s.player_state = PlayerState::JUMPING;
+    EmuResult::Success
+  }
   fn player_ctrl_routine(&mut self) -> EmuResult {
-    if self.s.y_pos < 0x10000 || self.s.y_pos >= 0x1d000 { self.joypad = Input::empty(); };
+    if is_vertically_offscreen(&self.s) { self.joypad = Input::empty(); };
     self.joypad_lr = Dir::from_bits_truncate(self.joypad.bits());
     self.joypad_ud = self.joypad & (inputs::U | inputs::D);
     if self.joypad.contains(Input::DOWN) && self.s.is_on_ground() && !self.joypad_lr.is_empty() {
       self.joypad_lr = Dir::empty();
       self.joypad_ud = Input::empty();
     }
+    let screen_left_x_pos = screen_left_x_pos::<O>(&self.s);
     self.player_movement_subs();
     if self.s.x_spd < 0  { self.s.moving_dir = Dir::LEFT; }
     else if self.s.x_spd >= 0x100 { self.s.moving_dir = Dir::RIGHT; }
 
-    self.scroll_handler();
+    self.scroll_handler(screen_left_x_pos);
 
-    self.player_bg_collision()
+    let result = self.player_bg_collision();
+    if result == EmuResult::Success && self.s.y_pos >= O::KillPlane::kill_y_pos() { return EmuResult::Death; } // fell into a pit
+    result
+  }
+  /// JumpspringHandler: moves the player down with the compressing jumpspring and back up, and launches them when
+  /// JumpspringAnimCtrl reaches 4. It steps in frames with FrameCounter & 3 == 0, which State::parity follows. Pressing
+  /// A from the second step on makes the bounce higher (a new A press is assumed, like for jumps).
+  fn jumpspring_handler(&mut self) -> () {
+    assert!(O::Parity::PARITY % 4 == 0 && !O::Parity::HALTED_BY_TIMER_CONTROL, "jumpsprings need a parity following FrameCounter");
+    let anim_ctrl = self.s.jumpspring_anim_ctrl;
+    self.s.y_pos += if anim_ctrl <= 2 { 0x200 } else { -0x200 };
+    if anim_ctrl >= 2 && self.joypad.contains(Input::A) { self.s.jumpspring_high_bounce = true; }
+    if anim_ctrl == JUMPSPRING_BOUNCE_ANIM_CTRL {
+      self.s.y_spd = (if self.s.jumpspring_high_bounce { JUMPSPRING_HIGH_FORCE } else { JUMPSPRING_FORCE }) + (self.s.y_spd & 0xff);
+      self.s.jumpspring_anim_ctrl = 0;
+      self.s.jumpspring_high_bounce = false;
+    } else if self.s.parity & 3 == 0 {
+      self.s.jumpspring_anim_ctrl += 1;
+    }
+  }
+  /// LargePlatformCollision against all lifts in their current positions, returns the lift the player landed on.
+  fn lift_collision(&mut self) -> Option<(usize, Lift)> {
+    let mut landed_lift = None;
+    if !is_vertically_offscreen(&self.s) { // CheckPlayerVertical
+      for i in 0..O::LiftHandler::LIFT_COUNT {
+        let lift = O::LiftHandler::get_lift(&self.s, i);
+        let lift_hitbox = lift.hitbox();
+        let player_hitbox = Hitbox::player::<O>(&self.s);
+        if !player_hitbox.intersects(&lift_hitbox) { continue; }
+        // ProcLPlatCollisions
+        if (lift_hitbox.y2 - player_hitbox.y1) & 0xff < 4 && self.s.y_spd < 0 { self.s.y_spd = 0x100 + (self.s.y_spd & 0xff); } // hit from below
+        if (player_hitbox.y2 - lift_hitbox.y1) & 0xff < 6 && self.s.y_spd >= 0 {
+          landed_lift = Some((i, lift));
+          self.s.player_state = PlayerState::STANDING;
+        } else if (player_hitbox.x2 - lift_hitbox.x1) & 0xff < 8 { // PlatformSideCollisions
+          self.impede_player_move(Dir::RIGHT);
+        } else if (lift_hitbox.x2 - player_hitbox.x1 - 1) & 0xff < 9 {
+          self.impede_player_move(Dir::LEFT);
+        }
+      }
+    }
+    landed_lift
+  }
+  /// Moves the player standing on lift i from its position old_lift along to its position in the current
+  /// State::object_frame.
+  fn move_with_lift(&mut self, i: usize, old_lift: Lift) -> () {
+    let lift = O::LiftHandler::get_lift(&self.s, i);
+    self.s.x_pos += ((lift.x_pos >> 8) - (old_lift.x_pos >> 8)) << 8; // PositionPlayerOnHPlat, Platform_X_Scroll is not modeled
+    if lift.y_pos >> 16 == 1 { // PositionPlayerOnVPlat
+      self.s.y_pos = ((lift.y_pos & !0xff) - 0x2000) | (self.s.y_pos & 0xff);
+      self.s.y_spd = 0;
+    }
   }
   fn player_movement_subs(&mut self) -> () {
-    if O::PlayerSize::is_big(&self.s) && self.s.is_on_ground() {
+    if !O::PlayerSize::is_big(&self.s) {
+      self.s.is_crouching = false; // CrouchingFlag is cleared every frame while small
+    } else if self.s.is_on_ground() {
       self.s.is_crouching = self.joypad_ud.contains(Input::DOWN);
     }
 
@@ -172,7 +419,7 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
       }
       PlayerState::JUMPING => {
         if self.s.y_spd >= 0 || (!self.joypad.contains(Input::A) && !self.started_jump) { self.s.v_force = self.s.v_force_down; }
-        if O::Swim::IS_SWIMMING {
+        if O::Swim::is_swimming(&self.s) {
           self.get_player_anim_speed();
           if self.s.y_pos < 0x11400 { self.s.v_force = O::Platform::V_FORCE_SWIM_TOO_HIGH; }
           if !self.joypad_lr.is_empty() { self.s.facing_dir = self.joypad_lr; }
@@ -188,17 +435,22 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
         self.move_vertically();
       }
       PlayerState::CLIMBING => {
-        unimplemented!();
+        self.climbing_sub();
       }
     }
   }
   fn player_physics_sub(&mut self) -> () {
+    if self.s.player_state == PlayerState::CLIMBING { // ProcClimb
+      self.s.y_spd = if self.joypad_ud.contains(Input::UP) { CLIMB_Y_SPD_UP } else if self.joypad_ud.contains(Input::DOWN) { CLIMB_Y_SPD_DOWN } else { 0 };
+      return;
+    }
+
     // handle starting jumps
-    if self.joypad.contains(Input::A) && (self.s.is_on_ground() || (O::Swim::IS_SWIMMING && (self.s.jump_swim_timer != 0 || self.s.y_spd >= 0))) {
+    if self.joypad.contains(Input::A) && (!O::JumpspringHandler::JUMPSPRINGS || self.s.jumpspring_anim_ctrl == 0) && (self.s.is_on_ground() || (O::Swim::is_swimming(&self.s) && (self.s.jump_swim_timer != 0 || self.s.y_spd >= 0))) {
       self.s.y_pos &= 0xffff00; // clear fractional yPos
       self.started_jump = true;
       self.s.player_state = PlayerState::JUMPING;
-      if O::Swim::IS_SWIMMING {
+      if O::Swim::is_swimming(&self.s) {
         self.s.v_force = O::Platform::V_FORCE_JUMP_SWIMMING;
         self.s.v_force_down = O::Platform::V_FORCE_FALL_SWIMMING;
         self.s.y_spd = O::Platform::JUMP_VELOCITY_SWIM;
@@ -219,7 +471,7 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
     }
 
     // X_Physics
-    let is_running: bool = !O::Swim::IS_SWIMMING && self.s.is_on_ground() && self.joypad_lr == self.s.moving_dir && self.joypad.contains(Input::B);
+    let is_running: bool = !O::Swim::is_swimming(&self.s) && self.s.is_on_ground() && self.joypad_lr == self.s.moving_dir && self.joypad.contains(Input::B);
     if O::RunningTimer::USE_RUNNING_TIMER && is_running { self.s.running_timer = 0xa; }
 
     if !self.s.is_on_ground() && self.s.x_spd_abs >= O::Platform::X_SPD_ABS_CUTOFFS[3] {
@@ -234,11 +486,11 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
       self.max_speed_right = O::Platform::MAX_X_SPD_WALK;
       self.max_speed_left = -O::Platform::MAX_X_SPD_WALK;
       self.friction = O::Platform::FRICTION_WALK_SLOW;
-    } else if O::Swim::IS_SWIMMING && !self.s.running_speed && self.s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[5] {
+    } else if O::Swim::is_swimming(&self.s) && !self.s.running_speed && self.s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[5] {
       self.max_speed_right = O::Platform::MAX_X_SPD_SWIM;
       self.max_speed_left = -O::Platform::MAX_X_SPD_SWIM;
       self.friction = O::Platform::FRICTION_WALK_SLOW;
-    } else if O::Swim::IS_SWIMMING {
+    } else if O::Swim::is_swimming(&self.s) {
       self.max_speed_right = O::Platform::MAX_X_SPD_SWIM;
       self.max_speed_left = -O::Platform::MAX_X_SPD_SWIM;
       self.friction = O::Platform::FRICTION_WALK_FAST;
@@ -258,6 +510,18 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
 
     if self.s.facing_dir != self.s.moving_dir { self.friction <<= 1; }
   }
+  fn climbing_sub(&mut self) -> () {
+    const CLIMB_ADDER: [i32; 4] = [0xe00, 0x400, -0x400, -0xe00];
+
+    self.s.y_pos += self.s.y_spd as i32;
+    let lr_collision: Dir = self.joypad_lr & self.s.collision_bits;
+    if lr_collision.is_empty() { self.s.climb_side_timer = 0; return; }
+    if self.s.climb_side_timer > 0 { return; }
+    self.s.climb_side_timer = 0x18;
+    let climb_adder_index = (if lr_collision.contains(Dir::RIGHT) { 0 } else { 2 }) + (if self.s.facing_dir == Dir::RIGHT { 0 } else { 1 });
+    self.s.x_pos += CLIMB_ADDER[climb_adder_index]; // move to the other side of the vine, or let go of it
+    self.s.facing_dir = Dir::from_bits_truncate(self.joypad_lr.bits() ^ Dir::LR.bits());
+  }
   fn get_player_anim_speed(&mut self) -> () {
     if self.s.x_spd_abs >= O::Platform::X_SPD_ABS_CUTOFFS[4] { self.s.running_speed = true; }
     else if !Input::A.contains(self.joypad) && Dir::from_bits_truncate(self.joypad.bits()) == self.s.moving_dir { self.s.running_speed = false; }
@@ -290,30 +554,43 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
     self.s.y_spd += self.s.v_force as i16;
     if self.s.y_spd >= O::Platform::MAX_Y_SPD && (self.s.y_spd & 0xff) >= 0x80 { self.s.y_spd = O::Platform::MAX_Y_SPD; }
   }
-  fn scroll_handler(&mut self) -> () {
+  fn scroll_handler(&mut self, screen_left_x_pos: i32) -> () {
     if O::ScrollPos::TRACK_SCROLL_POS {
-      let rel_x_pos = ((self.s.x_pos >> 8) - self.s.left_screen_edge_pos as i32) & 0xff;
-      if rel_x_pos < 0x50 || self.s.side_collision_timer > 0 || self.x_scroll <= 0 { return; }
-      if rel_x_pos < 0x70 && self.x_scroll >= 2 { self.x_scroll -= 1; }
-      self.s.left_screen_edge_pos = self.s.left_screen_edge_pos.wrapping_add(self.x_scroll as u8);
+      let mut screen_left_x_pos = screen_left_x_pos;
+      let rel_x_pos = ((self.s.x_pos >> 8) - screen_left_x_pos) & 0xff;
+      if !O::ScrollLock::is_scroll_locked(screen_left_x_pos) && rel_x_pos >= 0x50 && self.s.side_collision_timer == 0 && self.x_scroll > 0 {
+        if rel_x_pos < 0x70 && self.x_scroll >= 2 { self.x_scroll -= 1; }
+        screen_left_x_pos += self.x_scroll as i32;
+        self.s.left_screen_edge_pos = screen_left_x_pos as u8;
+      }
+
+      // ChkPOffscr: keep the player on screen, stopping them unless they push away from the edge
+      if (self.s.x_pos >> 8) <= screen_left_x_pos {
+        self.s.x_pos = (screen_left_x_pos << 8) | (self.s.x_pos & 0xff);
+        if self.joypad_lr != Dir::RIGHT { self.s.x_spd &= 0xff; }
+      } else if (self.s.x_pos >> 8) >= screen_left_x_pos + 0xf0 {
+        self.s.x_pos = ((screen_left_x_pos + 0xef) << 8) | (self.s.x_pos & 0xff);
+        if self.joypad_lr != Dir::LEFT { self.s.x_spd &= 0xff; }
+      }
     }
   }
   fn block_buffer_collision(&self, block_buffer_adder_offset: usize) -> CollisionResult {
-    let block_buffer_adder_offset = block_buffer_adder_offset + if !O::PlayerSize::is_big(&self.s) || self.s.is_crouching { 0x0e } else if O::Swim::IS_SWIMMING { 0x07 } else { 0 };
+    let block_buffer_adder_offset = block_buffer_adder_offset + if !O::PlayerSize::is_big(&self.s) || self.s.is_crouching { 0x0e } else if O::Swim::is_swimming(&self.s) { 0x07 } else { 0 };
 
     let bx = O::Platform::BLOCK_BUFFER_X_ADDER_DATA[block_buffer_adder_offset] as usize;
     let by = O::Platform::BLOCK_BUFFER_Y_ADDER_DATA[block_buffer_adder_offset] as usize;
     let cx: usize = (self.s.x_pos as usize + bx) >> 12;
     let cy: usize = ((self.s.y_pos as usize + by - 0x2000) >> 12) & 0x0f;
 
-    let mut cv = B::get_block_at(cx, cy);
+    let mut cv = self.block_buffer.get_block_at(cx, cy);
 
     if is_coin(cv) && O::CoinHandler::is_coin_collected(&self.s, cx, cy) { cv = 0; } // ignore collected coins
     if /* is_question_block(cv) && */ O::PowerupHandler::is_activated_powerup_block(&self.s, cx, cy) { cv = 0xc4; } // question block changed to solid block
+    if cv == 0 && O::VineHandler::is_vine_at(&self.s, cx, cy) { cv = 0x26; } // grown vine
     if cv == 0 { CollisionResult::NoCollision } else { CollisionResult::Collision(cv, cx, cy) }
   }
   fn player_bg_collision(&mut self) -> EmuResult {
-    if O::Swim::IS_SWIMMING { self.s.player_state = PlayerState::JUMPING }
+    if O::Swim::is_swimming(&self.s) { self.s.player_state = PlayerState::JUMPING }
     else if self.s.player_state == PlayerState::STANDING || self.s.player_state == PlayerState::CLIMBING { self.s.player_state = PlayerState::FALLING; }
 
     if self.s.y_pos < 0x10000 || self.s.y_pos >= 0x20000 { return EmuResult::Success; } // yPos out of bounds
@@ -326,7 +603,7 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
           O::CoinHandler::collect_coin(&mut self.s, cx, cy);
           return EmuResult::Success; // exit (no feet or side checks)
         } else if self.s.y_spd < 0 && (self.s.y_pos & 0x0f00) >= 0x400 {
-          if is_solid(cv) || O::Swim::IS_SWIMMING {
+          if is_solid(cv) || O::Swim::is_swimming(&self.s) {
             self.s.y_spd = 0x100 + (self.s.y_spd & 0xff); // hit solid block
           } else if O::PlayerSize::is_big(&self.s) && !is_question_block(cv) {
             self.s.y_spd = -0x200 + (self.s.y_spd & 0xff); // shatter brick
@@ -358,6 +635,8 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
         } else if !is_climb(cv) && !is_hidden_block(cv) && self.s.y_spd >= 0 {
           if cv == 0xc5 { // axe hit
             return EmuResult::StateChangeAxe(cx, cy);
+          } else if O::JumpspringHandler::JUMPSPRINGS && self.s.jumpspring_anim_ctrl > 0 {
+            self.s.player_state = PlayerState::STANDING; // standing on the jumpspring while it moves
           } else if (self.s.y_pos & 0x0f00) >= O::Platform::BLOCK_SURFACE_THICKNESS {
             let moving_dir = self.s.moving_dir;
             self.impede_player_move(moving_dir);
@@ -367,6 +646,7 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
             if (!self.started_on_ground || self.joypad_lr.is_empty()) && cv == 0x10 && right_foot_on_vert_pipe && O::VerticalPipeHandler::enter_vertical_pipe(cx, cy) {
               return EmuResult::StateChangeVerticalPipe(cx, cy); // vertical pipe entry
             }
+            if O::JumpspringHandler::JUMPSPRINGS && (cv == 0x67 || cv == 0x68) { self.s.jumpspring_anim_ctrl = 1; } // ChkForLandJumpSpring
             self.s.y_spd = 0; // kill vertical speed
             self.s.player_state = PlayerState::STANDING; // land
           }
@@ -402,12 +682,16 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
           EmuResult::StateChangeFlag(cx, cy)
         } else { // Hit vine
           self.put_player_on_vine(cx);
-          if cv == 0x26 && (self.s.y_pos & 0xff00) < 0x2000 { EmuResult::StateChangeVineAutoclimb(cx, cy) } else { EmuResult::HitVine(cx, cy) }
+          if cv == 0x26 && (self.s.y_pos & 0xff00) < 0x2000 { EmuResult::StateChangeVineAutoclimb(cx, cy) }
+          else if O::VineHandler::CLIMB_VINES { EmuResult::Success }
+          else { EmuResult::HitVine(cx, cy) }
         }
       }
     } else if is_coin(cv) {
       O::CoinHandler::collect_coin(&mut self.s, cx, cy);
       return EmuResult::Success; // grab coin
+    } else if O::JumpspringHandler::JUMPSPRINGS && (cv == 0x67 || cv == 0x68) && self.s.jumpspring_anim_ctrl > 0 {
+      return EmuResult::Success; // ChkJumpspringMetatiles: no side collision with the moving jumpspring
     } else if self.s.is_on_ground() && self.s.facing_dir == Dir::RIGHT && (cv == 0x6c || cv == 0x1f) {
       return EmuResult::StateChangeSidePipe(cx, cy); // sideways pipe entry
     } else {
@@ -421,13 +705,11 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
 
     self.s.player_state = PlayerState::CLIMBING;
     self.s.x_spd = 0;
-    if O::ScrollPos::TRACK_SCROLL_POS && ((self.s.x_pos >> 8) - self.s.left_screen_edge_pos as i32) & 0xff < 16 { self.s.facing_dir = Dir::LEFT; }
+    let screen_left_x_pos = screen_left_x_pos::<O>(&self.s);
+    if O::ScrollPos::TRACK_SCROLL_POS && (self.s.x_pos >> 8) - screen_left_x_pos < 16 { self.s.facing_dir = Dir::LEFT; }
     let x_pos = (((cx as i32) << 4) + CLIMB_X_POS_ADDER[self.s.facing_dir.bits() as usize]) & 0xff;
     let x_page_loc = if cx & 0xf != 0 { self.s.x_pos >> 16 } else {
-      let left_screen_edge_pos: u8 = if O::ScrollPos::TRACK_SCROLL_POS { self.s.left_screen_edge_pos } else { (((self.s.x_pos >> 8) - 0x70) & 0xff) as u8 }; // assume middle of screen if scroll is not tracked
-      let x_pos_byte: u8 = ((self.s.x_pos >> 8) & 0xff) as u8;
-      let left_screen_page_loc = if left_screen_edge_pos <= x_pos_byte { self.s.x_pos >> 16 } else { (self.s.x_pos >> 16) - 1 };
-      let right_screen_page_loc = if left_screen_edge_pos == 0 { left_screen_page_loc } else { left_screen_page_loc + 1 };
+      let right_screen_page_loc = (screen_left_x_pos + 0xff) >> 8;
       (right_screen_page_loc + CLIMB_PAGE_LOC_ADDER[self.s.facing_dir.bits() as usize]) & 0xff
     };
     self.s.x_pos = (self.s.x_pos & 0xff) | (x_pos << 8) | (x_page_loc << 16);
@@ -445,12 +727,6 @@ impl<O: Options, B: BlockBuffer> SmbEmu<O, B> {
     self.s.collision_bits -= moving_dir;
   }
 }
-impl<O: Options, B: BlockBuffer> Emu for SmbEmu<O, B> {
-    fn run_step(s: State, input: Input) -> (State, EmuResult) {
-        SmbEmu::<O, B>::new(s, input).run_step()
-    }
-}
-
 enum CollisionResult {
     NoCollision,
     Collision(u8, usize, usize)
@@ -465,5 +741,732 @@ pub enum EmuResult {
   StateChangeFlag(usize, usize),
   StateChangeVineAutoclimb(usize, usize),
   HitVine(usize, usize),
+  KilledByEnemy,
+  KilledByHazard,
+  Death,
   InvalidStateFallingWithClearedYposFractionals,
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use blockbuffer::world1::BB11;
+  use state::Dist;
+  use super::*;
+  use blockbuffer::NoCollisions;
+  use bowser::{Bowser, BowserPath, PseudoRandom};
+  use hazard::{FIREBAR_FAST_SPIN_SPEED, Firebar};
+  use enemy::{Enemy, EnemyKind, SMALL_ENEMY_BOUNDING_BOX};
+  use lift::{ConstantLift, LARGE_LIFT, LIFT_DOWN_Y_SPD, XMovingLift, lift_path};
+  use typenum::{U192, U209};
+
+  /// A Spiny standing on the ground of 1-1, which hurts the player on any contact.
+  const SPINY: Enemy = Enemy { x_pos: 0x30, y_pos: 0xb0, kind: EnemyKind::Spiny, bounding_box: SMALL_ENEMY_BOUNDING_BOX };
+
+  #[test]
+  fn parse_rle_reads_print_rle_format() {
+    assert_eq!(parse_rle("[2x B|R, 1x NIL, 1x A | DOWN] (len: 4)"), Ok(vec![inputs::B | inputs::R, inputs::B | inputs::R, inputs::NIL, inputs::A | inputs::D]));
+    assert_eq!(parse_rle(" [1x SELECT|START, 1x UP] "), Ok(vec![Input::SELECT | Input::START, inputs::U]));
+    assert_eq!(parse_rle("[]"), Ok(vec![]));
+  }
+
+  #[test]
+  fn parse_rle_reports_errors() {
+    assert_eq!(parse_rle("2x R"), Err(RleParseError::MissingBrackets));
+    assert_eq!(parse_rle("[2x R"), Err(RleParseError::MissingBrackets));
+    assert_eq!(parse_rle("[2 R]"), Err(RleParseError::MalformedRun("2 R".to_string())));
+    assert_eq!(parse_rle("[twox R]"), Err(RleParseError::InvalidCount("two".to_string())));
+    assert_eq!(parse_rle("[2x R|X]"), Err(RleParseError::InvalidButton("X".to_string())));
+    assert_eq!(parse_rle("[2x R] (len: two)"), Err(RleParseError::MalformedLength("(len: two)".to_string())));
+    assert_eq!(parse_rle("[2x R, 1x A] (len: 2)"), Err(RleParseError::LengthMismatch { expected: 2, actual: 3 }));
+  }
+  type InjuryEmu = SmbEmu<Injury, BB11>;
+  enum Injury {}
+  impl Options for Injury {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = BigUntilInjured;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = Parity21;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type DamageHandler = TakeDamage;
+    type EnemyHandler = Self;
+  }
+  impl EnemyHandler for Injury {
+    const ENEMY_COUNT: usize = 1;
+    const ENEMY_FRAME_BITS: usize = 0;
+    fn get_enemy(_: &State, _: usize) -> Enemy { SPINY }
+  }
+
+  /// Big player standing on the ground of 1-1 at x_pos, next to the Spiny for x_pos 0x2800.
+  fn standing_at(x_pos: i32, parity: u8) -> State {
+    let mut s = ::transition::entrance_state::<Injury>(x_pos, 0x1b000, PlayerState::STANDING);
+    s.parity = parity;
+    s
+  }
+
+  #[test]
+  fn stationary_enemy_contact() {
+    assert!(SPINY.hits_player::<Injury>(&standing_at(0x2800, 0)));
+    assert!(!SPINY.hits_player::<Injury>(&standing_at(0x1000, 0)));
+    assert!(!SPINY.hits_player::<Injury>(&standing_at(0x4000, 0)));
+  }
+
+  #[test]
+  fn injury_shrinks_player() {
+    let (s, result) = InjuryEmu::new().run_step(standing_at(0x2800, 0), inputs::NIL);
+    assert_eq!(result, EmuResult::Success);
+    assert!(s.injured && !BigUntilInjured::is_big(&s));
+    assert_eq!(s.player_state, PlayerState::JUMPING); // SetKRout
+    assert_eq!(s.injury_timer, INJURY_TIMER + INJURY_FREEZE_FRAMES + INJURY_BLINK_FRAMES);
+
+    let (s, result) = InjuryEmu::new().run_step(standing_at(0x1000, 0), inputs::NIL);
+    assert_eq!(result, EmuResult::Success);
+    assert!(!s.injured && s.injury_timer == 0);
+  }
+
+  #[test]
+  fn injury_halts_timers() {
+    let (mut s, _) = InjuryEmu::new().run_step(standing_at(0x2800, 0), inputs::NIL);
+    let parity = s.parity;
+    for frame in 0..INJURY_FREEZE_FRAMES + INJURY_BLINK_FRAMES {
+      let frozen = frame < INJURY_FREEZE_FRAMES || frame == INJURY_FREEZE_FRAMES + INJURY_BLINK_FRAMES - 1;
+      assert_eq!(is_frozen_after_injury(&s), frozen, "frame {}", frame);
+      let (next_s, result) = InjuryEmu::new().run_step(s.clone(), inputs::R);
+      assert_eq!(result, EmuResult::Success);
+      if frozen { assert_eq!(next_s.x_pos, s.x_pos, "frame {}", frame); }
+      assert_eq!(next_s.parity, parity, "frame {}", frame);
+      s = next_s;
+    }
+    assert_eq!(s.injury_timer, INJURY_TIMER);
+    assert!(!is_frozen_after_injury(&s));
+  }
+
+  #[test]
+  fn injury_invincibility_ends_with_interval_timer() {
+    for parity in 0..21 {
+      let (mut s, _) = InjuryEmu::new().run_step(standing_at(0x2800, parity), inputs::NIL);
+      let interval_timer_control = (0x14 - s.parity) as Dist;
+      let mut frames = 0;
+      loop {
+        let (next_s, result) = InjuryEmu::new().run_step(s, inputs::NIL);
+        s = next_s;
+        frames += 1;
+        if result != EmuResult::Success {
+          assert_eq!(result, EmuResult::KilledByEnemy);
+          break;
+        }
+        assert!(frames < 0x100, "never hit again");
+      }
+      // InjuryTimer 8 is decremented when IntervalTimerControl wraps: once after interval_timer_control + 1 frames and
+      // then every 21 frames, once the timers are no longer halted.
+      assert_eq!(frames, (INJURY_FREEZE_FRAMES + INJURY_BLINK_FRAMES) as Dist + interval_timer_control + 1 + 7 * 21, "parity {}", parity);
+    }
+  }
+
+  enum Contact {}
+  impl Options for Contact {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Big;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = Parity21;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type EnemyHandler = Injury;
+  }
+
+  #[test]
+  fn running_through_enemy_takes_damage_once() {
+    let inputs = [inputs::B | inputs::R; 200];
+    let trace = ::replay::trace(&InjuryEmu::new(), standing_at(0x1000, 0), &inputs);
+    assert_eq!(trace.len(), inputs.len());
+    assert!(trace.iter().all(|&(_, ref result)| *result == EmuResult::Success));
+    let injury_frames: Vec<usize> = (1..trace.len()).filter(|&i| trace[i].0.injury_timer > trace[i - 1].0.injury_timer).collect();
+    assert_eq!(injury_frames.len(), 1);
+    let hit_s = &trace[injury_frames[0]].0;
+    assert!(SPINY.hits_player::<Injury>(hit_s) && hit_s.injured);
+    // frozen while shrinking, then running on past the Spiny while blinking
+    let frozen_s = &trace[injury_frames[0] + INJURY_FREEZE_FRAMES as usize].0;
+    assert_eq!(frozen_s.x_pos, hit_s.x_pos);
+    let (last_s, _) = trace.last().unwrap();
+    assert!(last_s.injured && !BigUntilInjured::is_big(last_s));
+    assert!(last_s.x_pos > 0x4000, "player stuck at {:#x}", last_s.x_pos);
+
+    let trace = ::replay::trace(&SmbEmu::<Contact, BB11>::new(), standing_at(0x1000, 0), &inputs);
+    assert_eq!(trace.len(), injury_frames[0] + 1);
+    assert_eq!(trace.last().unwrap().1, EmuResult::KilledByEnemy);
+  }
+
+  enum Climbing {}
+  impl Options for Climbing {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+
+  fn on_vine(facing_dir: Dir) -> State {
+    let mut s = ::transition::entrance_state::<Climbing>(0x3800, 0x18000, PlayerState::CLIMBING);
+    s.facing_dir = facing_dir;
+    s
+  }
+  fn climbing_sub(s: State, joypad_lr: Dir) -> State {
+    let mut emu = SmbStep::<Climbing, NoCollisions>::new(&NoCollisions, s, Input::from_bits_truncate(joypad_lr.bits()));
+    emu.joypad_lr = joypad_lr;
+    emu.climbing_sub();
+    emu.s
+  }
+
+  #[test]
+  fn climbing_moves_vertically() {
+    let mut s = on_vine(Dir::RIGHT);
+    s.y_spd = CLIMB_Y_SPD_UP;
+    let s = climbing_sub(s, Dir::empty());
+    assert_eq!((s.x_pos, s.y_pos), (0x3800, 0x18000 + CLIMB_Y_SPD_UP as i32));
+    let mut s = on_vine(Dir::LEFT);
+    s.y_spd = CLIMB_Y_SPD_DOWN;
+    let s = climbing_sub(s, Dir::empty());
+    assert_eq!((s.x_pos, s.y_pos), (0x3800, 0x18000 + CLIMB_Y_SPD_DOWN as i32));
+  }
+
+  #[test]
+  fn climbing_switches_sides_and_lets_go() {
+    // pressing towards the vine moves the player to its other side
+    let s = climbing_sub(on_vine(Dir::RIGHT), Dir::RIGHT);
+    assert_eq!((s.x_pos, s.facing_dir, s.climb_side_timer), (0x3800 + 0xe00, Dir::LEFT, 0x18));
+    // nothing happens until climb_side_timer ran out
+    assert_eq!(climbing_sub(s.clone(), Dir::RIGHT), s);
+    // pressing away from the vine lets go of it
+    let mut s = s;
+    s.climb_side_timer = 0;
+    let s = climbing_sub(s, Dir::RIGHT);
+    assert_eq!((s.x_pos, s.facing_dir), (0x3800 + 0xe00 + 0x400, Dir::LEFT));
+
+    let s = climbing_sub(on_vine(Dir::LEFT), Dir::LEFT);
+    assert_eq!((s.x_pos, s.facing_dir), (0x3800 - 0xe00, Dir::RIGHT));
+    let s = climbing_sub(on_vine(Dir::RIGHT), Dir::LEFT);
+    assert_eq!((s.x_pos, s.facing_dir), (0x3800 - 0x400, Dir::RIGHT));
+  }
+
+  #[test]
+  fn climbing_into_wall_resets_side_timer() {
+    let mut s = on_vine(Dir::RIGHT);
+    s.collision_bits = Dir::LEFT;
+    s.climb_side_timer = 5;
+    let s = climbing_sub(s, Dir::RIGHT);
+    assert_eq!((s.x_pos, s.facing_dir, s.climb_side_timer), (0x3800, Dir::RIGHT, 0));
+  }
+
+  enum Scrolling {}
+  impl Options for Scrolling {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = WithScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+  enum ScrollLocked {}
+  impl Options for ScrollLocked {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = WithScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type ScrollLock = ScrollLockAtPage<::typenum::U1>;
+  }
+  /// Ground with a wall in block column 0x10.
+  struct WallAt10;
+  impl BlockBuffer for WallAt10 {
+    fn new() -> Self { WallAt10 }
+    fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 { if block_y >= 13 || block_x == 0x10 { 0x54 } else { 0 } }
+  }
+
+  /// Small player running right on the ground of 1-1, left_screen_edge_pos 0x60 pixels left of them.
+  fn running_at<O: Options>(x_pos: i32) -> State {
+    let mut s = ::transition::entrance_state::<O>(x_pos, 0x1b000, PlayerState::STANDING);
+    s.x_spd = 0x2800;
+    s.set_x_spd_abs::<O::Platform>(0x28);
+    s.moving_dir = Dir::RIGHT;
+    s.left_screen_edge_pos = ((x_pos >> 8) - 0x60) as u8;
+    s
+  }
+
+  #[test]
+  fn screen_scrolls_with_player() {
+    let s = running_at::<Scrolling>(0x16000);
+    let (next_s, _) = SmbEmu::<Scrolling, BB11>::new().run_step(s.clone(), inputs::R);
+    assert!(next_s.x_pos > s.x_pos);
+    assert!(screen_left_x_pos::<Scrolling>(&next_s) > screen_left_x_pos::<Scrolling>(&s));
+  }
+
+  #[test]
+  fn scroll_lock_stops_screen() {
+    let s = running_at::<ScrollLocked>(0x16000);
+    let (next_s, _) = SmbEmu::<ScrollLocked, BB11>::new().run_step(s.clone(), inputs::R);
+    assert!(next_s.x_pos > s.x_pos);
+    assert_eq!(screen_left_x_pos::<ScrollLocked>(&next_s), 0x100);
+
+    // the locked screen stops the player at its right edge
+    let mut s = running_at::<ScrollLocked>(0x1e000);
+    s.left_screen_edge_pos = 0;
+    for _ in 0..0x10 { s = SmbEmu::<ScrollLocked, BB11>::new().run_step(s, inputs::R).0; }
+    assert_eq!(s.x_pos >> 8, 0x100 + 0xef);
+    assert_eq!(screen_left_x_pos::<ScrollLocked>(&s), 0x100);
+  }
+
+  #[test]
+  fn wall_pushes_player_past_left_screen_edge() {
+    // after ChkPOffscr, the side collision checks push the player by a pixel each
+    let mut s = ::transition::entrance_state::<Scrolling>(0xf500, 0x1b000, PlayerState::STANDING);
+    s.left_screen_edge_pos = 0xf5;
+    for _ in 0..4 {
+      s = SmbEmu::<Scrolling, WallAt10>::new().run_step(s, inputs::R).0;
+      assert!((s.x_pos >> 8) < 0xf5 && (s.x_pos >> 8) >= 0xf5 - 2);
+      // the screen is still reconstructed from the player left of it
+      assert_eq!(screen_left_x_pos::<Scrolling>(&s), 0xf5);
+    }
+  }
+
+  /// A lift moving left and right, and one moving down, starting at FrameCounter 0.
+  enum Lifts {}
+  impl Options for Lifts {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type LiftHandler = Self;
+  }
+  impl LiftHandler for Lifts {
+    const LIFT_COUNT: usize = 2;
+    const LIFT_FRAME_BITS: usize = 8;
+    fn get_lift(s: &State, lift: usize) -> Lift {
+      let frames = s.object_frame as usize;
+      let path = if lift == 0 { lift_path(XMovingLift { x_pos: 0x4000, y_pos: 0x19000, primary_counter: 0, secondary_counter: 0, bounding_box: LARGE_LIFT }, 0, frames) }
+      else { lift_path(ConstantLift { x_pos: 0x8000, y_pos: 0x18000, y_spd: LIFT_DOWN_Y_SPD, bounding_box: LARGE_LIFT }, 0, frames) };
+      path[frames]
+    }
+  }
+  type LiftsEmu = SmbEmu<Lifts, NoCollisions>;
+
+  #[test]
+  fn player_lands_on_lift_and_rides_it() {
+    let mut s = ::transition::entrance_state::<Lifts>(0x8400, 0x15000, PlayerState::FALLING);
+    let mut frames = 0;
+    while s.player_state != PlayerState::STANDING {
+      s = LiftsEmu::new().run_step(s, inputs::NIL).0;
+      frames += 1;
+      assert!(frames < 0x20, "never landed");
+    }
+    for frame in 0..0x40 {
+      let lift = Lifts::get_lift(&s, 1);
+      assert_eq!(s.player_state, PlayerState::STANDING, "frame {}", frame);
+      assert_eq!(s.y_pos >> 8, (lift.y_pos >> 8) - 0x20, "frame {}", frame);
+      let (next_s, result) = LiftsEmu::new().run_step(s, inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+    }
+  }
+
+  #[test]
+  fn lift_carries_player_horizontally() {
+    let mut s = ::transition::entrance_state::<Lifts>(0x4800, 0x17000, PlayerState::STANDING);
+    for frame in 0..0x40 {
+      let lift = Lifts::get_lift(&s, 0);
+      let (next_s, result) = LiftsEmu::new().run_step(s.clone(), inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      let next_lift = Lifts::get_lift(&next_s, 0);
+      assert_eq!((next_s.x_pos >> 8) - (s.x_pos >> 8), (next_lift.x_pos >> 8) - (lift.x_pos >> 8), "frame {}", frame);
+      assert_eq!(next_s.y_pos >> 8, 0x170, "frame {}", frame);
+      s = next_s;
+    }
+    assert!(s.x_pos >> 8 < 0x48, "lift never moved");
+  }
+
+  #[test]
+  fn lift_side_impedes_player() {
+    let mut s = ::transition::entrance_state::<Lifts>(0x3500, 0x17c00, PlayerState::FALLING);
+    s.x_spd = 0x1800;
+    s.moving_dir = Dir::RIGHT;
+    let (s, result) = LiftsEmu::new().run_step(s, inputs::R);
+    assert_eq!(result, EmuResult::Success);
+    assert_eq!(s.player_state, PlayerState::FALLING);
+    assert!(s.x_spd < 0x100);
+  }
+
+  #[test]
+  fn small_player_is_not_crouching() {
+    let mut s = standing_at(0x1000, 0);
+    s.injured = true;
+    s.is_crouching = true;
+    let (s, result) = InjuryEmu::new().run_step(s, inputs::D);
+    assert_eq!(result, EmuResult::Success);
+    assert!(!s.is_crouching);
+  }
+
+  /// Ground with a ceiling block in block columns 4 and 5, 16 pixels above a crouching big player's head.
+  struct CeilingAt4;
+  impl BlockBuffer for CeilingAt4 {
+    fn new() -> Self { CeilingAt4 }
+    fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 {
+      if block_y >= 11 || block_y == 9 && (block_x == 4 || block_x == 5) { 0x54 } else { 0 }
+    }
+  }
+  type CeilingEmu = SmbEmu<Injury, CeilingAt4>;
+
+  /// Big player crouching under the ceiling, with its left side in block column 4.
+  fn crouching_under_ceiling(x_spd: i16) -> State {
+    let mut s = standing_at(0x4800, 0);
+    s.is_crouching = true;
+    s.x_spd = x_spd;
+    s
+  }
+
+  #[test]
+  fn uncrouching_under_ceiling_pushes_player_out() {
+    let (s, result) = CeilingEmu::new().run_step(crouching_under_ceiling(0), inputs::D);
+    assert_eq!(result, EmuResult::Success);
+    assert!(s.is_crouching && s.x_pos == 0x4800);
+
+    // the upper side check of the tall hitbox hits the ceiling, pushing the player right one pixel per frame until
+    // its left side is out of the block
+    let mut s = crouching_under_ceiling(0);
+    for frame in 0..0x20 {
+      let (next_s, result) = CeilingEmu::new().run_step(s.clone(), inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      assert!(!next_s.is_crouching);
+      let pushed = (s.x_pos >> 8) + 2 < 0x60;
+      assert_eq!(next_s.x_pos, s.x_pos + if pushed { 0x100 } else { 0 }, "frame {}", frame);
+      assert_eq!(next_s.collision_bits.contains(Dir::LEFT), !pushed, "frame {}", frame);
+      s = next_s;
+    }
+    assert_eq!(s.x_pos, 0x5e00);
+  }
+
+  #[test]
+  fn player_moving_away_from_ceiling_is_not_pushed() {
+    // only the first side with a collision is handled, and the left side doesn't push a player moving right
+    let (s, result) = CeilingEmu::new().run_step(crouching_under_ceiling(0x1000), inputs::R);
+    assert_eq!(result, EmuResult::Success);
+    assert!(s.x_spd >= 0x100 && s.x_pos > 0x4800);
+    assert!(!s.collision_bits.contains(Dir::LEFT));
+  }
+
+  /// Dies 16 pixels above the bottom of the screen.
+  enum Pit {}
+  impl Options for Pit {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type KillPlane = KillPlaneAt<U192>;
+  }
+
+  #[test]
+  fn player_dies_at_kill_plane() {
+    let mut s = ::transition::entrance_state::<Pit>(0x4000, 0x1b000, PlayerState::FALLING);
+    loop {
+      let (next_s, result) = SmbEmu::<Pit, NoCollisions>::new().run_step(s, inputs::NIL);
+      if result == EmuResult::Death { assert!(next_s.y_pos >= 0x1c000); break; }
+      assert_eq!(result, EmuResult::Success);
+      assert!(next_s.y_pos < 0x1c000);
+      s = next_s;
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "below the bottom of the screen")]
+  fn kill_plane_fits_compressed_state() {
+    <KillPlaneAt<U209> as KillPlane>::kill_y_pos();
+  }
+
+  /// A short firebar pointing up from a pivot at pixel (0x48, 0x90).
+  enum Burning {}
+  impl Options for Burning {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type HazardHandler = Self;
+  }
+  impl HazardHandler for Burning {
+    const HAZARDS: bool = true;
+    const HAZARD_FRAME_BITS: usize = 0;
+    const PIRANHA_PLANT_BITS: usize = 0;
+    const BOWSER_BITS: usize = 0;
+    fn is_touching_hazard(s: &State) -> bool {
+      Firebar { x_pos: 0x48, y_pos: 0x90, long: false, spin_speed: FIREBAR_FAST_SPIN_SPEED, counter_clockwise: false, spin_state: 0 }.hits_player::<Self>(s, 0)
+    }
+    fn move_hazards(_: &mut State) -> () {}
+  }
+  type BurningEmu = SmbEmu<Burning, NoCollisions>;
+
+  #[test]
+  fn firebar_kills_small_player() {
+    let mut s = ::transition::entrance_state::<Burning>(0x4400, 0x14000, PlayerState::FALLING);
+    let mut frames = 0;
+    loop {
+      let (next_s, result) = BurningEmu::new().run_step(s, inputs::NIL);
+      if result == EmuResult::KilledByHazard { break; }
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+      frames += 1;
+      assert!(frames < 0x20, "never touched the firebar");
+    }
+    // the fireballs are 8 pixels wide
+    let mut s = ::transition::entrance_state::<Burning>(0x5400, 0x14000, PlayerState::FALLING);
+    for _ in 0..0x20 {
+      let (next_s, result) = BurningEmu::new().run_step(s, inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+    }
+  }
+
+  /// A Goomba standing on the ground of CeilingAt4, at pixel (0x80, 0xb8).
+  enum Stomping {}
+  impl Options for Stomping {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type EnemyHandler = Self;
+  }
+  impl EnemyHandler for Stomping {
+    const ENEMY_COUNT: usize = 1;
+    const ENEMY_FRAME_BITS: usize = 0;
+    fn get_enemy(_: &State, _: usize) -> Enemy {
+      Enemy { x_pos: 0x80, y_pos: 0xb8, kind: EnemyKind::Goomba, bounding_box: SMALL_ENEMY_BOUNDING_BOX }
+    }
+  }
+  /// 9 Goombas, one more than State::stomped_enemies has bits for.
+  enum Crowded {}
+  impl Options for Crowded {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type EnemyHandler = Self;
+  }
+  impl EnemyHandler for Crowded {
+    const ENEMY_COUNT: usize = 9;
+    const ENEMY_FRAME_BITS: usize = 0;
+    fn get_enemy(s: &State, i: usize) -> Enemy { Stomping::get_enemy(s, i) }
+  }
+  type StompingEmu = SmbEmu<Stomping, CeilingAt4>;
+
+  #[test]
+  fn falling_player_stomps_enemy_once() {
+    let mut s = ::transition::entrance_state::<Stomping>(0x8000, 0x19800, PlayerState::FALLING);
+    s.y_spd = 0x180;
+    for frames in 0.. {
+      let (next_s, result) = StompingEmu::new().run_step(s, inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+      if s.stomped_enemies != 0 { break; }
+      assert!(frames < 0x10, "never touched the enemy");
+    }
+    assert_eq!(s.stomped_enemies, 1);
+    assert_eq!(s.y_spd & !0xff, -0x400);
+    // stomped enemies are harmless
+    for _ in 0..0x40 {
+      let (next_s, result) = StompingEmu::new().run_step(s, inputs::NIL);
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+    }
+    assert!(s.is_on_ground());
+  }
+
+  #[test]
+  fn walking_into_enemy_kills_small_player() {
+    let mut s = ::transition::entrance_state::<Stomping>(0x6000, 0x1b000, PlayerState::STANDING);
+    for frames in 0.. {
+      let (next_s, result) = StompingEmu::new().run_step(s, inputs::R);
+      if result == EmuResult::KilledByEnemy { break; }
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+      assert!(frames < 0x40, "never touched the enemy");
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "9 enemies don't fit")]
+  fn stomped_enemies_fit_in_state() {
+    let s = ::transition::entrance_state::<Crowded>(0x6000, 0x19800, PlayerState::STANDING);
+    SmbEmu::<Crowded, NoCollisions>::new().run_step(s, inputs::NIL);
+  }
+
+  lazy_static! {
+    static ref BOWSER_PATH: BowserPath = Bowser::new(0x80, 0x80, 1, 0).path(PseudoRandom([0xa5, 0, 0, 0, 0, 0, 0]), 0, 0x100);
+  }
+  /// Bowser starting at pixel (0x80, 0x80) on a bridge.
+  enum Fighting {}
+  impl Options for Fighting {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type HazardHandler = Self;
+  }
+  impl HazardHandler for Fighting {
+    const HAZARDS: bool = true;
+    const HAZARD_FRAME_BITS: usize = 8;
+    const PIRANHA_PLANT_BITS: usize = 0;
+    const BOWSER_BITS: usize = 9;
+    fn is_touching_hazard(s: &State) -> bool { BOWSER_PATH.hits_player::<Self>(s) }
+    fn move_hazards(s: &mut State) -> () { BOWSER_PATH.update_pass_frame(s) }
+  }
+  /// Bridge in block rows 8 and below, with the axe in block column 0xe above it.
+  struct BowserBridge;
+  impl BlockBuffer for BowserBridge {
+    fn new() -> Self { BowserBridge }
+    fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 {
+      if block_y >= 8 { 0x89 } else if block_x == 0xe && block_y == 6 { 0xc5 } else { 0 }
+    }
+  }
+  type FightingEmu = SmbEmu<Fighting, BowserBridge>;
+
+  #[test]
+  fn bowser_kills_small_player() {
+    let mut s = ::transition::entrance_state::<Fighting>(0x4000, 0x18000, PlayerState::STANDING);
+    for frames in 0.. {
+      let (next_s, result) = FightingEmu::new().run_step(s, inputs::R);
+      if result == EmuResult::KilledByHazard { break; }
+      assert_eq!(result, EmuResult::Success);
+      assert!(!next_s.bowser_passed, "passed Bowser without touching him");
+      s = next_s;
+      assert!(frames < 0x80, "never touched Bowser");
+    }
+  }
+
+  #[test]
+  fn player_lands_on_axe() {
+    let mut s = ::transition::entrance_state::<Fighting>(0xe400, 0x14000, PlayerState::FALLING);
+    for frames in 0.. {
+      let (next_s, result) = FightingEmu::new().run_step(s, inputs::NIL);
+      if let EmuResult::StateChangeAxe(cx, cy) = result { assert_eq!((cx, cy), (0xe, 6)); break; }
+      assert_eq!(result, EmuResult::Success);
+      s = next_s;
+      assert!(frames < 0x20, "never landed on the axe");
+    }
+  }
+
+  enum Springing {}
+  impl Options for Springing {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = Parity4;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type JumpspringHandler = Jumpsprings;
+  }
+  /// Ground with a jumpspring in block column 5.
+  struct JumpspringAt5;
+  impl BlockBuffer for JumpspringAt5 {
+    fn new() -> Self { JumpspringAt5 }
+    fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 {
+      if block_y >= 11 { 0x54 } else if block_x == 5 && block_y == 9 { 0x67 } else if block_x == 5 && block_y == 10 { 0x68 } else { 0 }
+    }
+  }
+  type SpringingEmu = SmbEmu<Springing, JumpspringAt5>;
+
+  /// Runs from a player landing on the jumpspring in a frame with FrameCounter & 3 == parity until the bounce, holding
+  /// input from the frame after landing. Returns the frames from landing to the bounce and the state after it.
+  fn bounce(parity: u8, input: Input) -> (Dist, State) {
+    // landing on the jumpspring in the first frame
+    let mut s = ::transition::entrance_state::<Springing>(0x5000, 0x18e38, PlayerState::FALLING);
+    s.y_spd = 0x230;
+    s.parity = parity;
+    let (s, _) = SpringingEmu::new().run_step(s, inputs::NIL);
+    assert!(s.jumpspring_anim_ctrl > 0, "landing");
+    let mut s = s;
+    let mut frames = 1;
+    while s.jumpspring_anim_ctrl > 0 {
+      assert_eq!(s.player_state, PlayerState::STANDING);
+      s = SpringingEmu::new().run_step(s, input).0;
+      frames += 1;
+      assert!(frames < 0x20, "never bounced");
+    }
+    (frames, s)
+  }
+
+  #[test]
+  fn jumpspring_steps_with_frame_counter() {
+    for parity in 0..4 {
+      let (frames, s) = bounce(parity, inputs::NIL);
+      // JumpspringAnimCtrl 1 lasts until FrameCounter & 3 == 0, 2 and 3 last 4 frames each, the bounce is at 4
+      let anim_ctrl_1_frames = ((4 - parity as i32) & 3) + 1;
+      assert_eq!(frames, anim_ctrl_1_frames as Dist + 8 + 1, "parity {}", parity);
+      assert_eq!(s.y_spd >> 8, JUMPSPRING_FORCE >> 8, "parity {}", parity);
+      // 2 pixels down in each frame of JumpspringAnimCtrl 1 and 2, up in each frame of 3 and 4
+      assert_eq!(s.y_pos >> 8, 0x190 + 2 * (anim_ctrl_1_frames - 1), "parity {}", parity);
+    }
+  }
+
+  #[test]
+  fn jumpspring_bounces_higher_with_a() {
+    let (_, s) = bounce(1, inputs::A);
+    assert_eq!(s.y_spd >> 8, JUMPSPRING_HIGH_FORCE >> 8);
+  }
+}
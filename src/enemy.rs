@@ -0,0 +1,53 @@
+use hitbox::Hitbox;
+use options::Options;
+use state::State;
+
+// BoundBoxCtrlData entries of enemies
+pub const ENEMY_BOUNDING_BOX: (i32, i32, i32, i32) = (0x02, 0x09, 0x0e, 0x15); // SetBBox, e.g. Koopa Troopas
+pub const SMALL_ENEMY_BOUNDING_BOX: (i32, i32, i32, i32) = (0x03, 0x0e, 0x0d, 0x14); // SmallBBox, e.g. Goombas
+// SBnce and EnemyStompedPts, only Player_Y_Speed is set
+const STOMP_BOUNCE_Y_SPD: i16 = -0x400;
+const STOMP_BOUNCE_Y_SPD_OTHER: i16 = -0x300;
+// ChkInj
+const STOMP_ABOVE_Y_DIST: i32 = 0x0c;
+
+/// How an enemy reacts to being stomped (EnemyStomped).
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum EnemyKind {
+  Goomba, // flattened
+  KoopaTroopa, // turns into a shell, which is not kicked when touched again
+  Spiny, // can't be stomped
+  Other, // Bloober, cheep-cheeps, Bullet Bills, Hammer Bros and Lakitu, stomped even when moving up
+}
+
+/// An enemy in a single frame at pixel position (x_pos, y_pos), with x_pos including the page and y_pos relative to the
+/// top of the screen.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Enemy {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub kind: EnemyKind,
+  pub bounding_box: (i32, i32, i32, i32),
+}
+impl Enemy {
+  pub fn hitbox(&self) -> Hitbox {
+    Hitbox::new(self.x_pos, 0x100 + self.y_pos, self.bounding_box)
+  }
+  pub fn hits_player<O: Options>(&self, s: &State) -> bool {
+    Hitbox::player::<O>(s).intersects(&self.hitbox())
+  }
+  /// ChkForPlayerInjury: whether touching the enemy stomps it instead of hurting the player. Enemies are stomped when
+  /// the player moves down by at least a pixel per frame, and some also when the player is above them.
+  pub fn is_stomped(&self, s: &State) -> bool {
+    match self.kind {
+      EnemyKind::Spiny => false,
+      EnemyKind::Goomba | EnemyKind::KoopaTroopa => s.y_spd >= 0x100,
+      EnemyKind::Other => s.y_spd >= 0x100 || (((s.y_pos >> 8) + STOMP_ABOVE_Y_DIST) & 0xff) < self.y_pos,
+    }
+  }
+  /// Vertical speed of the player after stomping the enemy, keeping the fractional part.
+  pub fn bounce_y_spd(&self, s: &State) -> i16 {
+    let y_spd = if self.kind == EnemyKind::Other { STOMP_BOUNCE_Y_SPD_OTHER } else { STOMP_BOUNCE_Y_SPD };
+    y_spd + (s.y_spd & 0xff)
+  }
+}
@@ -0,0 +1,214 @@
+use enemy::EnemyKind;
+use rom::Rom;
+
+const END_OF_ENEMY_DATA: u8 = 0xff;
+const AREA_CHANGE_ROW: u8 = 0x0e;
+const PAGE_CONTROL_ROW: u8 = 0x0f;
+// Enemy groups (HandleGroupEnemies), spawned next to each other at the right edge of the screen
+const FIRST_GROUP_ID: u8 = 0x37;
+const END_GROUP_ID: u8 = 0x3f;
+const GROUP_Y_POS: i32 = 0xb0;
+const GROUP_Y_POS_HIGH: i32 = 0x70;
+
+/// An enemy object of the enemy data of an area, at pixel position (x_pos, y_pos) with x_pos including the page.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct EnemyObject {
+  pub page: u8,
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub id: u8,
+  pub hard_mode: bool, // only appears with SecondaryHardMode, i.e. from world 5 on or in the second quest
+}
+impl EnemyObject {
+  /// Enemy ID the object spawns as, goombas turn into buzzy beetles with PrimaryHardMode (second quest).
+  pub fn enemy_id(&self, primary_hard_mode: bool) -> u8 {
+    if self.id == 0x06 && primary_hard_mode { 0x02 } else { self.id }
+  }
+  pub fn appears(&self, secondary_hard_mode: bool) -> bool {
+    !self.hard_mode || secondary_hard_mode
+  }
+  /// Enemy ID, number of enemies and y position of an enemy group object.
+  pub fn group(&self, primary_hard_mode: bool) -> Option<(u8, usize, i32)> {
+    if self.id < FIRST_GROUP_ID || self.id >= END_GROUP_ID { return None; }
+    let group = self.id - FIRST_GROUP_ID;
+    let id = if group >= 4 { 0x00 } else if primary_hard_mode { 0x02 } else { 0x06 };
+    Some((id, 2 + (group & 0x1) as usize, if group & 0x2 != 0 { GROUP_Y_POS_HIGH } else { GROUP_Y_POS }))
+  }
+  /// How the enemy reacts to being stomped, for enemies the player can collide with.
+  pub fn kind(&self, primary_hard_mode: bool) -> Option<EnemyKind> {
+    match self.enemy_id(primary_hard_mode) {
+      0x06 => Some(EnemyKind::Goomba),
+      0x00 ..= 0x04 | 0x0e ..= 0x10 => Some(EnemyKind::KoopaTroopa), // including buzzy beetles and paratroopas
+      0x12 => Some(EnemyKind::Spiny),
+      0x05 | 0x07 | 0x08 | 0x0a | 0x0b | 0x11 => Some(EnemyKind::Other),
+      _ => None,
+    }
+  }
+}
+
+/// Area change object (row $0e): a pipe or vine of the area leads to the area with the given area pointer, entered
+/// at entrance_page. It only applies in world_number, which allows areas to be shared between worlds.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct AreaChange {
+  pub page: u8,
+  pub area_pointer: u8,
+  pub world_number: u8,
+  pub entrance_page: u8,
+}
+
+/// Decoded enemy data of an area, in the order of the data.
+#[derive(Clone,Debug,Default,Eq,PartialEq)]
+pub struct AreaEnemies {
+  pub enemies: Vec<EnemyObject>,
+  pub area_changes: Vec<AreaChange>,
+}
+
+/// Decodes the enemy data of the area with the given area pointer, following the page handling of ProcessEnemyData.
+pub fn decode_enemies(rom: &Rom, area_pointer: u8) -> AreaEnemies {
+  decode_enemy_data(rom.bytes_from(rom.enemy_data_addr(area_pointer)))
+}
+/// Decodes enemy data ending with $ff.
+fn decode_enemy_data(enemy_data: &[u8]) -> AreaEnemies {
+  let data = |offset: usize| enemy_data[offset];
+  let mut result = AreaEnemies::default();
+  let mut offset = 0;
+  let mut page: u8 = 0;
+  let mut page_sel = false;
+  while data(offset) != END_OF_ENEMY_DATA {
+    let (byte0, byte1) = (data(offset), data(offset + 1));
+    if byte1 & 0x80 != 0 && !page_sel {
+      page_sel = true;
+      page = page.wrapping_add(1);
+    }
+    let row = byte0 & 0xf;
+    if row == PAGE_CONTROL_ROW && !page_sel {
+      page = byte1 & 0x3f;
+      page_sel = true; // the page flag of the next object is ignored
+      offset += 2;
+      continue;
+    }
+    if row == AREA_CHANGE_ROW {
+      let byte2 = data(offset + 2);
+      result.area_changes.push(AreaChange { page, area_pointer: byte1 & 0x7f, world_number: byte2 >> 5, entrance_page: byte2 & 0x1f });
+      offset += 3;
+    } else {
+      result.enemies.push(EnemyObject {
+        page,
+        x_pos: (page as i32) << 8 | (byte0 & 0xf0) as i32,
+        y_pos: (row as i32) << 4,
+        id: byte1 & 0x3f,
+        hard_mode: byte1 & 0x40 != 0,
+      });
+      offset += 2;
+    }
+    page_sel = false;
+  }
+  result
+}
+
+/// Name of an enemy ID, for dumps.
+pub fn enemy_name(id: u8) -> &'static str {
+  match id {
+    0x00 => "Green Koopa Troopa",
+    0x01 => "Red Koopa Troopa (walks off floors)",
+    0x02 => "Buzzy Beetle",
+    0x03 => "Red Koopa Troopa",
+    0x04 => "Green Koopa Troopa (stationary)",
+    0x05 => "Hammer Bro",
+    0x06 => "Goomba",
+    0x07 => "Bloober",
+    0x08 => "Bullet Bill",
+    0x0a => "Grey Cheep-Cheep",
+    0x0b => "Red Cheep-Cheep",
+    0x0c => "Podoboo",
+    0x0d => "Piranha Plant",
+    0x0e => "Green Paratroopa (jumping)",
+    0x0f => "Red Paratroopa",
+    0x10 => "Green Paratroopa (flying)",
+    0x11 => "Lakitu",
+    0x12 => "Spiny",
+    0x14 => "Flying Cheep-Cheep frenzy",
+    0x15 => "Bowser flame frenzy",
+    0x16 => "Fireworks",
+    0x17 => "Bullet Bill or Cheep-Cheep frenzy",
+    0x18 => "Stop frenzy",
+    0x1b ..= 0x1e => "Short firebar",
+    0x1f => "Long firebar",
+    0x24 => "Balance lift",
+    0x25 => "Vertical lift",
+    0x26 => "Large lift up",
+    0x27 => "Large lift down",
+    0x28 | 0x2a => "Horizontal lift",
+    0x29 => "Falling lift",
+    0x2b => "Small lift up",
+    0x2c => "Small lift down",
+    0x2d => "Bowser",
+    0x2e => "Power-up",
+    0x2f => "Vine",
+    0x32 => "Jumpspring",
+    0x33 => "Bullet Bill cannon",
+    0x35 => "Retainer",
+    0x37 ..= 0x3a => "Goomba group",
+    0x3b ..= 0x3e => "Koopa Troopa group",
+    _ => "unknown",
+  }
+}
+
+/// Prints the enemy data of an area of a world (both 0-based) in the ROM file, to compare against an emulator. The
+/// enemies spawned depend on the quest: the second quest uses PrimaryHardMode, and SecondaryHardMode from world 5 on.
+pub fn print_enemies(rom_path: &str, world_number: usize, area_number: usize, second_quest: bool) {
+  let rom = Rom::load(rom_path);
+  let area_pointer = rom.area_pointer(world_number, area_number);
+  let area_enemies = decode_enemies(&rom, area_pointer);
+  let (primary_hard_mode, secondary_hard_mode) = (second_quest, second_quest || world_number >= 4);
+  println!("Enemy data of area {} of world {} (area pointer {:#04x}):", area_number, world_number + 1, area_pointer);
+  for e in area_enemies.enemies.iter() {
+    let spawn = if !e.appears(secondary_hard_mode) {
+      "not spawned".to_string()
+    } else if let Some((id, count, y_pos)) = e.group(primary_hard_mode) {
+      format!("{} x {} at y_pos {:#04x}", count, enemy_name(id), y_pos)
+    } else {
+      let id = e.enemy_id(primary_hard_mode);
+      format!("{}{}", enemy_name(id), e.kind(primary_hard_mode).map_or(String::new(), |kind| format!(", stomped as {:?}", kind)))
+    };
+    println!("page {:#04x} x_pos {:#06x} y_pos {:#04x}: {:#04x}{}: {}", e.page, e.x_pos, e.y_pos, e.id, if e.hard_mode { " (hard mode)" } else { "" }, spawn);
+  }
+  for c in area_enemies.area_changes.iter() {
+    println!("page {:#04x}: area change to area pointer {:#04x}, entrance page {:#04x}, in world {}", c.page, c.area_pointer, c.entrance_page, c.world_number + 1);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn decodes_pages_and_area_changes() {
+    let area_enemies = decode_enemy_data(&[
+        0x76, 0x06, // Goomba
+        0x1b, 0x86, // Goomba on the next page
+        0x0f, 0x05, // page control
+        0x37, 0xc0, // hard mode Koopa Troopa, the page flag is ignored after the page control
+        0x2e, 0x25, 0x41, // area change
+        0x4b, 0x92, // Spiny on the next page
+        0xff]);
+    assert_eq!(area_enemies.enemies, vec![
+        EnemyObject { page: 0, x_pos: 0x070, y_pos: 0x60, id: 0x06, hard_mode: false },
+        EnemyObject { page: 1, x_pos: 0x110, y_pos: 0xb0, id: 0x06, hard_mode: false },
+        EnemyObject { page: 5, x_pos: 0x530, y_pos: 0x70, id: 0x00, hard_mode: true },
+        EnemyObject { page: 6, x_pos: 0x640, y_pos: 0xb0, id: 0x12, hard_mode: false }]);
+    assert_eq!(area_enemies.area_changes, vec![AreaChange { page: 5, area_pointer: 0x25, world_number: 2, entrance_page: 1 }]);
+    assert!(!area_enemies.enemies[2].appears(false));
+    assert!(area_enemies.enemies[2].appears(true));
+  }
+
+  #[test]
+  fn decodes_enemy_groups() {
+    let group = |id| EnemyObject { page: 0, x_pos: 0, y_pos: 0, id, hard_mode: false };
+    assert_eq!(group(0x38).group(false), Some((0x06, 3, GROUP_Y_POS)));
+    assert_eq!(group(0x39).group(true), Some((0x02, 2, GROUP_Y_POS_HIGH)));
+    assert_eq!(group(0x3e).group(false), Some((0x00, 3, GROUP_Y_POS_HIGH)));
+    assert_eq!(group(0x3f).group(false), None);
+    assert_eq!(group(0x06).kind(true), Some(EnemyKind::KoopaTroopa)); // buzzy beetle
+  }
+}
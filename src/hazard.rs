@@ -0,0 +1,284 @@
+use bowser::PseudoRandom;
+use enemy::SMALL_ENEMY_BOUNDING_BOX;
+use hitbox::Hitbox;
+use options::{Options, PlayerSize};
+use state::State;
+
+// FirebarSpinSpdData
+pub const FIREBAR_SLOW_SPIN_SPEED: u8 = 0x28;
+pub const FIREBAR_FAST_SPIN_SPEED: u8 = 0x38;
+// FirebarPosLookupTbl, one row per fireball outside of the pivot
+const FIREBAR_POS_LOOKUP: [[i32; 9]; 11] = [
+    [0x00, 0x01, 0x03, 0x04, 0x05, 0x06, 0x07, 0x07, 0x08],
+    [0x00, 0x03, 0x06, 0x09, 0x0b, 0x0d, 0x0e, 0x0f, 0x10],
+    [0x00, 0x04, 0x09, 0x0d, 0x10, 0x13, 0x16, 0x17, 0x18],
+    [0x00, 0x06, 0x0c, 0x12, 0x16, 0x1a, 0x1d, 0x1f, 0x20],
+    [0x00, 0x07, 0x0f, 0x16, 0x1c, 0x21, 0x25, 0x27, 0x28],
+    [0x00, 0x09, 0x12, 0x1b, 0x21, 0x27, 0x2c, 0x2f, 0x30],
+    [0x00, 0x0b, 0x15, 0x1f, 0x27, 0x2e, 0x33, 0x37, 0x38],
+    [0x00, 0x0c, 0x18, 0x24, 0x2d, 0x35, 0x3b, 0x3e, 0x40],
+    [0x00, 0x0e, 0x1b, 0x28, 0x32, 0x3b, 0x42, 0x46, 0x48],
+    [0x00, 0x0f, 0x1f, 0x2d, 0x38, 0x42, 0x4a, 0x4e, 0x50],
+    [0x00, 0x11, 0x22, 0x31, 0x3e, 0x49, 0x51, 0x56, 0x58]];
+const FIREBAR_MIRROR_DATA: [u8; 4] = [0x01, 0x03, 0x02, 0x00];
+const FIREBAR_Y_POS: [i32; 2] = [0x0c, 0x18];
+// InitPodoboo and MovePodoboo
+const PODOBOO_START_Y_POS: i32 = 0x20200;
+const PODOBOO_Y_SPD: u16 = 0xf900;
+const PODOBOO_MIN_INTERVALS: u8 = 0x06;
+// IntervalTimerControl, the interval timers are decremented when it wraps
+const INTERVAL_TIMER_CONTROL_MAX: u8 = 0x14;
+// MoveJ_EnemyVertically
+const PODOBOO_DOWN_FORCE: i16 = 0x1c;
+const PODOBOO_MAX_Y_SPD: i16 = 0x03;
+// InitPiranhaPlant and MovePiranhaPlant
+const PIRANHA_PLANT_HEIGHT: u8 = 0x18;
+const PIRANHA_PLANT_PLAYER_DIST: i32 = 0x21;
+const PIRANHA_PLANT_TIMER: u8 = 0x40;
+
+/// Rotating firebar (ProcFirebar) with its pivot at pixel position (x_pos, y_pos).
+pub struct Firebar {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub long: bool,
+  pub spin_speed: u8,
+  pub counter_clockwise: bool,
+  pub spin_state: u16, // FirebarSpinState_High and FirebarSpinState_Low at the start states
+}
+impl Firebar {
+  /// FirebarSpinState_High in every frame, starting with the start states.
+  pub fn spin_states(&self, frames: usize) -> Vec<u8> {
+    let mut spin_state = self.spin_state;
+    let mut spin_states = vec![(spin_state >> 8) as u8];
+    for _ in 0..frames {
+      spin_state = if self.counter_clockwise { spin_state.wrapping_sub(self.spin_speed as u16) } else { spin_state.wrapping_add(self.spin_speed as u16) };
+      let mut spin_state_high = (spin_state >> 8) as u8 & 0x1f;
+      if self.long && (spin_state_high == 0x08 || spin_state_high == 0x18) { spin_state_high += 1; } // skipped by long firebars
+      spin_state = ((spin_state_high as u16) << 8) | (spin_state & 0xff);
+      spin_states.push(spin_state_high);
+    }
+    spin_states
+  }
+  /// FirebarCollision of any of the fireballs with the player.
+  pub fn hits_player<O: Options>(&self, s: &State, spin_state: u8) -> bool {
+    let fireballs = if self.long { 12 } else { 6 };
+    (0..fireballs).any(|fireball| {
+      let (x, y) = self.get_fireball_pos(spin_state, fireball);
+      fireball_hits_player::<O>(s, x, y)
+    })
+  }
+  /// GetFirebarPosition: pixel position of a fireball, 0 being the one on the pivot.
+  fn get_fireball_pos(&self, spin_state: u8, fireball: usize) -> (i32, i32) {
+    if fireball == 0 { return (self.x_pos, self.y_pos); }
+    let oscillate = |v: u8| { let v = v & 0x0f; (if v >= 9 { (v ^ 0x0f) + 1 } else { v }) as usize };
+    let x_adder = FIREBAR_POS_LOOKUP[fireball - 1][oscillate(spin_state)];
+    let y_adder = FIREBAR_POS_LOOKUP[fireball - 1][oscillate(spin_state + 8)];
+    let mirror = FIREBAR_MIRROR_DATA[(spin_state >> 3) as usize & 3];
+    (self.x_pos + if mirror & 1 != 0 { x_adder } else { -x_adder }, self.y_pos + if mirror & 2 != 0 { y_adder } else { -y_adder })
+  }
+}
+
+/// FirebarCollision: checks the fireball against up to three points along the player's height.
+fn fireball_hits_player<O: Options>(s: &State, x: i32, y: i32) -> bool {
+  if s.y_pos >> 16 != 1 { return false; }
+  if ((s.x_pos >> 8) + 4 - x).abs() >= 8 { return false; }
+  let player_y = (s.y_pos >> 8) & 0xff;
+  if !O::PlayerSize::is_big(s) || s.is_crouching {
+    (player_y + FIREBAR_Y_POS[1] - y).abs() < 8
+  } else {
+    (player_y - y).abs() < 8 || FIREBAR_Y_POS.iter().any(|y_adder| (player_y + y_adder - y).abs() < 8)
+  }
+}
+
+/// Vertical positions of a Podoboo in every frame, starting with the start states at (y_pos, y_spd). It jumps out of the
+/// lava in the given frames, with the Enemy_Y_MoveForce taken from the pseudo random number generator by MovePodoboo.
+pub fn podoboo_path(mut y_pos: i32, mut y_spd: i16, jumps: &[(usize, u8)], frames: usize) -> Vec<i32> {
+  let mut path = vec![y_pos];
+  for frame in 1..frames + 1 {
+    if let Some(&(_, y_move_force)) = jumps.iter().find(|&&(jump_frame, _)| jump_frame == frame) {
+      y_pos = PODOBOO_START_Y_POS;
+      y_spd = (PODOBOO_Y_SPD | (y_move_force | 0x80) as u16) as i16;
+    }
+    y_pos += y_spd as i32;
+    y_spd += PODOBOO_DOWN_FORCE;
+    if (y_spd >> 8) >= PODOBOO_MAX_Y_SPD && (y_spd & 0xff) >= 0x80 { y_spd = PODOBOO_MAX_Y_SPD << 8; }
+    path.push(y_pos);
+  }
+  path
+}
+
+/// Frames in which the Podoboo in the given enemy slot jumps (MovePodoboo), with the Enemy_Y_MoveForce of the jump.
+/// prng, interval_timer_control and interval_timer are PseudoRandomBitReg, IntervalTimerControl and the
+/// EnemyIntervalTimer of the Podoboo in the start states. It jumps when the timer ran out, which is then set to at least
+/// PODOBOO_MIN_INTERVALS intervals of 21 frames.
+pub fn podoboo_jumps(mut prng: PseudoRandom, mut interval_timer_control: u8, mut interval_timer: u8, slot: usize, frames: usize) -> Vec<(usize, u8)> {
+  let mut jumps = vec![];
+  for frame in 1..frames + 1 {
+    if interval_timer_control == 0 { // DecTimers
+      interval_timer_control = INTERVAL_TIMER_CONTROL_MAX;
+      interval_timer = interval_timer.saturating_sub(1);
+    } else {
+      interval_timer_control -= 1;
+    }
+    prng.next_frame();
+    if interval_timer == 0 {
+      let y_move_force = prng.get(slot + 1) | 0x80;
+      interval_timer = (y_move_force & 0x0f) | PODOBOO_MIN_INTERVALS;
+      jumps.push((frame, y_move_force));
+    }
+  }
+  jumps
+}
+
+/// PlayerEnemyCollision with a Podoboo at (x_pos, y_pos), positioned like the player.
+pub fn podoboo_hits_player<O: Options>(s: &State, x_pos: i32, y_pos: i32) -> bool {
+  Hitbox::player::<O>(s).intersects(&Hitbox::new(x_pos >> 8, y_pos >> 8, SMALL_ENEMY_BOUNDING_BOX))
+}
+
+/// Firebars and Podoboos which move independently of the player, positioned by State::object_frame.
+pub struct HazardPaths {
+  pub frames: usize, // frames the paths cover after the start states
+  pub firebars: Vec<(Firebar, Vec<u8>)>, // with FirebarSpinState_High in every frame
+  pub podoboos: Vec<(i32, Vec<i32>)>, // x_pos positioned like the player, with y_pos in every frame
+}
+impl HazardPaths {
+  /// Whether any of the hazards touches the player in the current frame.
+  pub fn hits_player<O: Options>(&self, s: &State) -> bool {
+    let frame = s.object_frame as usize;
+    self.firebars.iter().any(|&(ref firebar, ref spin_states)| firebar.hits_player::<O>(s, spin_states[frame]))
+        || self.podoboos.iter().any(|&(x_pos, ref path)| podoboo_hits_player::<O>(s, x_pos, path[frame]))
+  }
+}
+
+/// Piranha plant in a vertical pipe at pixel position (x_pos, y_pos), the position VerticalPipe spawns it at
+/// (PiranhaPlantDownYPos). Its movement is kept in State::piranha_plant_y_pos (pixels above y_pos),
+/// State::piranha_plant_timer, State::piranha_plant_rising and State::piranha_plant_moving, which are all 0 for a
+/// newly spawned plant.
+pub struct PiranhaPlant {
+  pub x_pos: i32,
+  pub y_pos: i32,
+}
+impl PiranhaPlant {
+  /// MovePiranhaPlant, with the FrameCounter value of the current frame. It rests in the pipe or at the top for
+  /// PIRANHA_PLANT_TIMER frames, moves one pixel every other frame, and only emerges if the player is not close to it.
+  pub fn move_plant(&self, s: &mut State, frame_counter: u8) -> () {
+    if s.piranha_plant_timer > 0 { s.piranha_plant_timer -= 1; return; } // EnemyFrameTimer
+    if !s.piranha_plant_moving {
+      if !s.piranha_plant_rising {
+        let mut dist = (self.x_pos - (s.x_pos >> 8)) & 0xff; // PlayerEnemyDiff
+        if self.x_pos < (s.x_pos >> 8) { dist = (0x100 - dist) & 0xff; }
+        if dist < PIRANHA_PLANT_PLAYER_DIST { return; } // ChkPlayerNearPipe
+      }
+      s.piranha_plant_rising = !s.piranha_plant_rising; // ReversePlantSpeed
+      s.piranha_plant_moving = true;
+    }
+    if frame_counter & 1 == 0 { return; } // RiseFallPiranhaPlant
+    if s.piranha_plant_rising {
+      assert!(s.piranha_plant_y_pos < PIRANHA_PLANT_HEIGHT, "piranha plant rising above its top");
+      s.piranha_plant_y_pos += 1;
+    } else {
+      assert!(s.piranha_plant_y_pos > 0, "piranha plant falling into its pipe");
+      s.piranha_plant_y_pos -= 1;
+    }
+    if s.piranha_plant_y_pos == if s.piranha_plant_rising { PIRANHA_PLANT_HEIGHT } else { 0 } {
+      s.piranha_plant_moving = false;
+      s.piranha_plant_timer = PIRANHA_PLANT_TIMER;
+    }
+  }
+  /// PlayerEnemyCollision with the plant. While fully in the pipe it can only be touched by clipping into the pipe.
+  pub fn hits_player<O: Options>(&self, s: &State) -> bool {
+    Hitbox::player::<O>(s).intersects(&Hitbox::new(self.x_pos, 0x100 + self.y_pos - s.piranha_plant_y_pos as i32, SMALL_ENEMY_BOUNDING_BOX))
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use options::*;
+  use state::PlayerState;
+
+  enum Hazards {}
+  impl Options for Hazards {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+
+  fn firebar(long: bool) -> Firebar {
+    Firebar { x_pos: 0x180, y_pos: 0x80, long, spin_speed: FIREBAR_FAST_SPIN_SPEED, counter_clockwise: false, spin_state: 0 }
+  }
+
+  #[test]
+  fn firebar_spins_with_speed() {
+    let spin_states = firebar(false).spin_states(0x100);
+    assert_eq!(spin_states.len(), 0x101);
+    assert_eq!(spin_states[8], ((8 * FIREBAR_FAST_SPIN_SPEED as u16) >> 8) as u8);
+    assert!(spin_states.iter().all(|&spin_state| spin_state < 0x20));
+    // long firebars skip the horizontal positions
+    assert!(firebar(true).spin_states(0x100).iter().all(|&spin_state| spin_state != 0x08 && spin_state != 0x18));
+  }
+
+  #[test]
+  fn firebar_hits_player_at_pivot_and_end() {
+    // small player whose lower fireball check point is at the pivot
+    let s = ::transition::entrance_state::<Hazards>(0x17c00, 0x16800, PlayerState::FALLING);
+    assert!((0..0x20).all(|spin_state| firebar(false).hits_player::<Hazards>(&s, spin_state)));
+    let s = ::transition::entrance_state::<Hazards>(0x10000, 0x16800, PlayerState::FALLING);
+    assert!(!(0..0x20).any(|spin_state| firebar(true).hits_player::<Hazards>(&s, spin_state)));
+    // spin state 0 points up, reaching 0x50 pixels for a long firebar
+    let s = ::transition::entrance_state::<Hazards>(0x17c00, 0x11800, PlayerState::FALLING);
+    assert!(firebar(true).hits_player::<Hazards>(&s, 0));
+    assert!(!firebar(false).hits_player::<Hazards>(&s, 0));
+  }
+
+  #[test]
+  fn podoboo_jumps_with_interval_timer() {
+    let prng = PseudoRandom([0xa5, 0, 0, 0, 0, 0, 0]);
+    let jumps = podoboo_jumps(prng, 0, 1, 2, 0x400);
+    assert_eq!(jumps[0].0, 1);
+    for (&(frame, y_move_force), &(next_frame, _)) in jumps.iter().zip(jumps.iter().skip(1)) {
+      assert!(y_move_force & 0x80 != 0);
+      assert_eq!(next_frame - frame, ((y_move_force & 0x0f) | PODOBOO_MIN_INTERVALS) as usize * 21);
+    }
+  }
+
+  #[test]
+  fn podoboo_jumps_out_of_lava() {
+    let path = podoboo_path(PODOBOO_START_Y_POS, 0, &[(1, 0x80)], 0x100);
+    let top = *path.iter().min().unwrap();
+    assert!(top < 0x1d000 && top > 0x10000, "top at {:x}", top);
+    assert!(path[0x100] > PODOBOO_START_Y_POS);
+  }
+
+  #[test]
+  fn piranha_plant_rises_unless_player_is_near() {
+    let plant = PiranhaPlant { x_pos: 0x180, y_pos: 0x90 };
+    let mut s = ::transition::entrance_state::<Hazards>(0x10000, 0x17000, PlayerState::STANDING);
+    for frame_counter in 0..2 * PIRANHA_PLANT_HEIGHT {
+      assert!(s.piranha_plant_rising && s.piranha_plant_moving || frame_counter == 0);
+      plant.move_plant(&mut s, frame_counter);
+    }
+    assert_eq!((s.piranha_plant_y_pos, s.piranha_plant_timer, s.piranha_plant_moving), (PIRANHA_PLANT_HEIGHT, PIRANHA_PLANT_TIMER, false));
+    assert!(plant.hits_player::<Hazards>(&State { x_pos: 0x17800, y_pos: 0x17000, ..s.clone() }));
+
+    let mut s = ::transition::entrance_state::<Hazards>(0x17000, 0x17000, PlayerState::STANDING);
+    for frame_counter in 0..0x80 { plant.move_plant(&mut s, frame_counter); }
+    assert_eq!((s.piranha_plant_y_pos, s.piranha_plant_moving), (0, false));
+    assert!(!plant.hits_player::<Hazards>(&State { x_pos: 0x17800, y_pos: 0x17000, ..s.clone() }));
+  }
+
+  #[test]
+  #[should_panic(expected = "falling into its pipe")]
+  fn piranha_plant_rejects_falling_below_pipe() {
+    let mut s = ::transition::entrance_state::<Hazards>(0x10000, 0x17000, PlayerState::STANDING);
+    s.piranha_plant_moving = true;
+    PiranhaPlant { x_pos: 0x180, y_pos: 0x90 }.move_plant(&mut s, 1);
+  }
+}
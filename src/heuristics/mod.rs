@@ -8,9 +8,16 @@ use std::cmp::{max, min};
 use std::marker::PhantomData;
 
 pub trait SearchGoal {
-  fn new() -> Self;
+  /// Parameters of new, e.g. the target of a goal that is searched for repeatedly. The goals of search cases take none.
+  type Params = ();
+  fn new(Self::Params) -> Self;
   fn distance_to_goal_heuristic(&self, s: &mut State, steps_already_taken: Dist) -> Option<Dist>;
   fn is_goal_state(&self, s: &State, emu_result: &EmuResult) -> bool;
+  /// Frames from the goal state s, reached after the given number of steps, until the goal is complete, e.g. until the
+  /// next level loads after the flag contact. They count towards the length of the solution.
+  /// The search only keeps the earliest arrival at a state, so if they depend on the steps, a later arrival finishing
+  /// earlier is missed and the solution found is not guaranteed to be the shortest.
+  fn frames_after_goal(&self, _: &State, _: Dist) -> Dist { 0 }
 
   fn track_metric(&mut self, _: &State) -> () {}
   fn report_metrics(&self) -> () {}
@@ -19,7 +26,7 @@ pub trait SearchGoal {
 #[allow(dead_code)]
 pub struct ImpossibleSearchGoal;
 impl SearchGoal for ImpossibleSearchGoal {
-  fn new() -> Self { return Self {}; }
+  fn new(_: ()) -> Self { return Self {}; }
   fn distance_to_goal_heuristic(&self, _: &mut State, _: Dist) -> Option<Dist> { Some(0) }
   fn is_goal_state(&self, _: &State, _: &EmuResult) -> bool { false }
 }
@@ -30,7 +37,7 @@ pub struct MaxXPosMetric<P: Platform> {
   _platform: PhantomData<P>,
 }
 impl<P: Platform> SearchGoal for MaxXPosMetric<P> {
-  fn new() -> Self { MaxXPosMetric { max_x_pos: ::std::i32::MIN, _platform: PhantomData } }
+  fn new(_: ()) -> Self { MaxXPosMetric { max_x_pos: ::std::i32::MIN, _platform: PhantomData } }
   fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
     Some(min_x_pos_heuristic::<P>(s, self.max_x_pos + 0x10))
   }
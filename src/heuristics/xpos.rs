@@ -15,7 +15,7 @@ impl XPosHeuristic {
     let mut h = XPosHeuristic {
       max_distance: HashMap::<XPosState, Vec<i32>>::new(),
       min_distance: HashMap::<XPosState, Vec<i32>>::new(),
-      max_x_distance: ((if O::Swim::IS_SWIMMING { O::Platform::MAX_X_SPD_WALK } else { O::Platform::MAX_X_SPD_RUN } >> 8) << 4) as i32,
+      max_x_distance: ((if O::Swim::ALWAYS_SWIMMING { O::Platform::MAX_X_SPD_WALK } else { O::Platform::MAX_X_SPD_RUN } >> 8) << 4) as i32,
     };
     let state_map = Self::build_state_map::<O>(initial_states.iter().map(|s| Self::to_x_pos_state(s)).collect());
     println!("total number of XPosState: {}; total size of state map: {}", state_map.len(), state_map.values().map(|v| v.len()).sum::<usize>());
@@ -211,12 +211,15 @@ impl<O: Options> XPosEmu<O> {
       vec![(O::Platform::FRICTION_WALK_FAST, O::Platform::MAX_X_SPD_WALK)]
     } else if !s.is_on_ground {
       vec![(O::Platform::FRICTION_WALK_SLOW, O::Platform::MAX_X_SPD_WALK)]
-    } else if O::Swim::IS_SWIMMING && !s.running_speed && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[5] {
+    } else if O::Swim::ALWAYS_SWIMMING && !s.running_speed && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[5] {
       vec![(O::Platform::FRICTION_WALK_SLOW, O::Platform::MAX_X_SPD_SWIM)]
-    } else if O::Swim::IS_SWIMMING {
+    } else if O::Swim::ALWAYS_SWIMMING {
       vec![(O::Platform::FRICTION_WALK_FAST, O::Platform::MAX_X_SPD_SWIM)]
     } else {
       let mut inner_result = Vec::new();
+      if O::Swim::MAY_SWIM { // may be in a water area
+        inner_result.push((if !s.running_speed && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[5] { O::Platform::FRICTION_WALK_SLOW } else { O::Platform::FRICTION_WALK_FAST }, O::Platform::MAX_X_SPD_SWIM));
+      }
       if joypad_lr == s.moving_dir {
         inner_result.push((O::Platform::FRICTION_RUN, O::Platform::MAX_X_SPD_RUN));
       }
@@ -235,11 +238,12 @@ impl<O: Options> XPosEmu<O> {
   }
   fn move_subs(s: &XPosState, lr: Dir, joypad_lr: Dir, friction: i16, max_speed: i16) -> Vec<XPosState> {
     let mut result: Vec<XPosState> = vec![s.clone()];
-    if s.is_on_ground || O::Swim::IS_SWIMMING {
+    if s.is_on_ground || O::Swim::MAY_SWIM {
       result = Self::get_player_anim_speed(s.clone(), lr);
       if !joypad_lr.is_empty() {
         for s in &mut result { s.facing_dir = joypad_lr; }
       }
+      if !s.is_on_ground && !O::Swim::ALWAYS_SWIMMING { result.push(s.clone()); } // may be on land
     }
     if s.is_on_ground || !joypad_lr.is_empty() {
       result = result.into_iter().flat_map(|s| { Self::impose_friction(s, joypad_lr, friction, max_speed) }).collect();
@@ -1,4 +1,4 @@
-use options::{Options, Platform, PlayerSize, Swim, YPosFractionalBehavior};
+use options::{EnemyHandler, Options, Platform, PlayerSize, Swim, YPosFractionalBehavior};
 use state::{Dist, State};
 use std::cmp::{max,min};
 use std::collections::{HashMap, HashSet};
@@ -145,7 +145,6 @@ impl ::std::fmt::Display for YPosState {
   }
 }
 
-const WITH_ENEMY_COLLISIONS: bool = false;
 enum Void {}
 pub struct YPosEmu<O: Options> {
   _options: PhantomData<O>,
@@ -173,11 +172,11 @@ impl<O: Options> YPosEmu<O> {
       }
 
       // handle starting jumps
-      if s.is_on_ground || O::Swim::IS_SWIMMING {
+      if s.is_on_ground || O::Swim::MAY_SWIM {
         let mut start_jump = s.clone();
         start_jump.y_pos &= 0xffff00; // clear fractional yPos
         start_jump.is_on_ground = false;
-        if O::Swim::IS_SWIMMING {
+        if O::Swim::MAY_SWIM {
           let mut start_jump_swim = start_jump.clone();
           start_jump_swim.v_force = O::Platform::V_FORCE_JUMP_SWIMMING;
           start_jump_swim.v_force_down = O::Platform::V_FORCE_FALL_SWIMMING;
@@ -188,7 +187,8 @@ impl<O: Options> YPosEmu<O> {
           start_jump_swim_too_high.v_force_down = O::Platform::V_FORCE_FALL_SWIMMING;
           start_jump_swim_too_high.y_spd = O::Platform::JUMP_VELOCITY_SWIM & 0xff; // kill upward momentum if swimming too high
           result.insert(start_jump_swim_too_high);
-        } else {
+        }
+        if s.is_on_ground && !O::Swim::ALWAYS_SWIMMING {
           let mut start_jump_run = start_jump.clone();
           start_jump_run.v_force = O::Platform::V_FORCE_JUMP_RUNNING;
           start_jump_run.v_force_down = O::Platform::V_FORCE_FALL_RUNNING;
@@ -212,7 +212,7 @@ impl<O: Options> YPosEmu<O> {
 
     // MoveSubs
     let states: HashSet<YPosState> = states.into_iter().flat_map(|s| {
-      if !s.is_on_ground && O::Swim::IS_SWIMMING {
+      if !s.is_on_ground && O::Swim::MAY_SWIM {
         let mut swim_too_high = s.clone();
         swim_too_high.v_force = O::Platform::V_FORCE_SWIM_TOO_HIGH;
         vec![s, swim_too_high]
@@ -237,7 +237,7 @@ impl<O: Options> YPosEmu<O> {
 
       let mut hit_solid_block = s.clone();
       hit_solid_block.y_spd = 0x100 + (hit_solid_block.y_spd & 0xff); // hit solid block
-      if O::Swim::IS_SWIMMING { return vec![s, hit_solid_block]; }
+      if O::Swim::ALWAYS_SWIMMING { return vec![s, hit_solid_block]; }
 
       let mut bump_block = s.clone();
       bump_block.y_spd &= 0xff; // bump block
@@ -261,23 +261,15 @@ impl<O: Options> YPosEmu<O> {
   }
 
   fn player_enemy_collision(states: HashSet<YPosState>) -> HashSet<YPosState> {
-    if !WITH_ENEMY_COLLISIONS { return states; }
-    // bounce on enemy
-    // let states: HashSet<YPosState> = states.into_iter().flat_map(|s| {
-    //   if s.y_spd >= 0x100 {
-    //     let mut bounce_enemy = s.clone();
-    //     bounce_enemy.y_spd = -0x300 + (bounce_enemy.y_spd & 0xff); // bounce on enemy
-    //     vec![s, bounce_enemy]
-    //   } else { vec![s] }
-    // }).collect();
-
-    // bounce on shell
+    if O::EnemyHandler::ENEMY_COUNT == 0 { return states; }
+    // EnemyStomped: any enemy may be stomped when moving down, and some also when moving up
     states.into_iter().flat_map(|s| {
-      if s.y_spd >= 0x100 {
-        let mut bounce_shell = s.clone();
-        bounce_shell.y_spd = -0x400 + (bounce_shell.y_spd & 0xff); // bounce on shell
-        vec![s, bounce_shell]
-      } else { vec![s] }
+      let mut bounce_other = s.clone();
+      bounce_other.y_spd = -0x300 + (bounce_other.y_spd & 0xff); // bounce on Bloober, cheep-cheep, etc.
+      if s.y_spd < 0x100 { return vec![s, bounce_other]; }
+      let mut bounce_enemy = s.clone();
+      bounce_enemy.y_spd = -0x400 + (bounce_enemy.y_spd & 0xff); // bounce on Goomba or Koopa Troopa
+      vec![s, bounce_other, bounce_enemy]
     }).collect()
   }
 }
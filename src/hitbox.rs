@@ -0,0 +1,28 @@
+use options::{Options, PlayerSize};
+use state::State;
+
+// BoundBoxCtrlData entries (x1, y1, x2, y2) relative to the object position
+const PLAYER_BIG: (i32, i32, i32, i32) = (0x02, 0x08, 0x0e, 0x20);
+const PLAYER_SMALL: (i32, i32, i32, i32) = (0x03, 0x14, 0x0d, 0x20);
+
+/// Bounding box in absolute pixel coordinates (x_pos >> 8, y_pos >> 8), inclusive on all sides.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Hitbox {
+  pub x1: i32,
+  pub y1: i32,
+  pub x2: i32,
+  pub y2: i32,
+}
+impl Hitbox {
+  pub fn new(x: i32, y: i32, offsets: (i32, i32, i32, i32)) -> Hitbox {
+    Hitbox { x1: x + offsets.0, y1: y + offsets.1, x2: x + offsets.2, y2: y + offsets.3 }
+  }
+  /// Bounding box of the player, which shrinks when small or crouching.
+  pub fn player<O: Options>(s: &State) -> Hitbox {
+    let offsets = if !O::PlayerSize::is_big(s) || s.is_crouching { PLAYER_SMALL } else { PLAYER_BIG };
+    Hitbox::new(s.x_pos >> 8, s.y_pos >> 8, offsets)
+  }
+  pub fn intersects(&self, other: &Hitbox) -> bool {
+    self.x1 <= other.x2 && other.x1 <= self.x2 && self.y1 <= other.y2 && other.y1 <= self.y2
+  }
+}
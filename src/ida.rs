@@ -1,6 +1,6 @@
 use emu::{Emu,EmuResult,Input};
 use heuristics::SearchGoal;
-use options::{Options, Platform, PlayerSize, Swim};
+use options::{AreaHandler, DamageHandler, Options, Platform, PlayerSize, Swim, VineHandler};
 use state::{Dist,PlayerState, State};
 use std::marker::PhantomData;
 use store::StateStore;
@@ -17,14 +17,25 @@ pub struct SmbInputFetcher<O: Options> {
 
 impl<O: Options> InputFetcher for SmbInputFetcher<O> {
   fn valid_next_inputs(s: &State) -> Vec<Input> {
-    if s.y_pos < 0x10000 || s.y_pos >= 0x1d000 { return vec![Input::empty()]; } // inputs are ignored
+    if ::emu::is_vertically_offscreen(s) { return vec![Input::empty()]; } // inputs are ignored
+    if O::AreaHandler::MULTI_AREA && s.area_change_timer > 0 { return vec![Input::empty()]; } // in area transition
+    if O::DamageHandler::TAKES_DAMAGE && ::emu::is_frozen_after_injury(s) { return vec![Input::empty()]; } // frozen after injury
+    if O::VineHandler::CLIMB_VINES && s.player_state == PlayerState::CLIMBING {
+      let mut inputs = Vec::new();
+      for ud in [Input::empty(), Input::UP, Input::DOWN].iter() {
+        for lr in [Input::empty(), Input::LEFT, Input::RIGHT].iter() {
+          inputs.push(*ud | *lr);
+        }
+      }
+      return inputs;
+    }
 
     let mut inputs = Vec::new();
-    if !O::Swim::IS_SWIMMING && s.is_on_ground() {
+    if !O::Swim::is_swimming(s) && s.is_on_ground() {
       inputs.push(Input::B | Input::from_bits_truncate(s.moving_dir.bits()));
     }
     inputs.push(Input::RIGHT);
-    if s.player_state == PlayerState::STANDING || O::Swim::IS_SWIMMING { // Changes the facing_dir
+    if s.player_state == PlayerState::STANDING || O::Swim::is_swimming(s) { // Changes the facing_dir
       inputs.push(Input::LEFT | Input::RIGHT);
     }
     inputs.push(Input::empty());
@@ -39,10 +50,10 @@ impl<O: Options> InputFetcher for SmbInputFetcher<O> {
     if s.is_on_ground() && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1] && (s.moving_dir.is_empty() || !O::PlayerSize::is_big(s)) {
       inputs.push(Input::DOWN | if s.moving_dir.is_empty() { Input::RIGHT } else { Input::empty() });
     }
-    if s.is_on_ground() || (O::Swim::IS_SWIMMING && (s.jump_swim_timer != 0 || s.y_spd >= 0)) || (s.player_state == PlayerState::JUMPING && s.v_force != s.v_force_down) {
+    if s.is_on_ground() || (O::Swim::is_swimming(s) && (s.jump_swim_timer != 0 || s.y_spd >= 0)) || (s.player_state == PlayerState::JUMPING && s.v_force != s.v_force_down) {
       inputs.push(Input::A);
       inputs.push(Input::A | Input::RIGHT);
-      if O::Swim::IS_SWIMMING { // Changes the facing_dir
+      if O::Swim::is_swimming(s) { // Changes the facing_dir
         inputs.push(Input::A | Input::LEFT | Input::RIGHT);
       }
       inputs.push(Input::A | Input::LEFT);
@@ -50,25 +61,25 @@ impl<O: Options> InputFetcher for SmbInputFetcher<O> {
     if O::PlayerSize::is_big(s) && s.is_on_ground() {
       inputs.push(Input::A | Input::DOWN);
     }
-    if O::Swim::IS_SWIMMING && s.is_on_ground() && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[4] && (!s.moving_dir.is_empty() || !O::PlayerSize::is_big(s)) {
+    if O::Swim::is_swimming(s) && s.is_on_ground() && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[4] && (!s.moving_dir.is_empty() || !O::PlayerSize::is_big(s)) {
       inputs.push(Input::A | Input::DOWN | Input::from_bits_truncate(s.moving_dir.bits()));
     }
-    if O::Swim::IS_SWIMMING && s.is_on_ground() && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1] && (s.moving_dir.is_empty() || !O::PlayerSize::is_big(s)) {
+    if O::Swim::is_swimming(s) && s.is_on_ground() && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1] && (s.moving_dir.is_empty() || !O::PlayerSize::is_big(s)) {
       inputs.push(Input::A | Input::DOWN | if s.moving_dir.is_empty() { Input::RIGHT } else { Input::empty() });
     }
-    if (s.is_on_ground() || O::Swim::IS_SWIMMING) && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[4] && s.moving_dir.is_empty() {
+    if (s.is_on_ground() || O::Swim::is_swimming(s)) && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[4] && s.moving_dir.is_empty() {
       inputs.push(Input::UP);
     }
-    if (s.is_on_ground() || O::Swim::IS_SWIMMING) && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1] && !s.moving_dir.is_empty() {
+    if (s.is_on_ground() || O::Swim::is_swimming(s)) && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1] && !s.moving_dir.is_empty() {
       inputs.push(Input::UP);
     }
-    if O::Swim::IS_SWIMMING
+    if O::Swim::is_swimming(s)
         && (s.is_on_ground() || s.jump_swim_timer != 0 || s.y_spd >= 0 || s.v_force != s.v_force_down)
         && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[4]
         && s.moving_dir.is_empty() {
       inputs.push(Input::A | Input::UP);
     }
-    if O::Swim::IS_SWIMMING
+    if O::Swim::is_swimming(s)
         && (s.is_on_ground() || s.jump_swim_timer != 0 || s.y_spd >= 0 || s.v_force != s.v_force_down)
         && s.x_spd_abs < O::Platform::X_SPD_ABS_CUTOFFS[1]
         && !s.moving_dir.is_empty() {
@@ -109,25 +120,35 @@ lazy_static! {
 pub struct IDA<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> {
   visited_states: S,
   search_goal: G,
-  _emu: PhantomData<E>,
+  emu: E,
   _input_fetcher: PhantomData<I>,
   num_visits: u64,
   last_update_time_ns: u64,
   last_update_seen: usize,
 }
-impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> Search for IDA<S, E, G, I> {
+impl<S: StateStore, E: Emu, G: SearchGoal<Params = ()>, I: InputFetcher> Search for IDA<S, E, G, I> {
   fn find_first_solution(mut start_states: Vec<State>, initial_max_allowed_steps: Dist, search_space_size_hint: usize) -> SearchResult {
-    let emu = Self::new(search_space_size_hint);
+    let emu = Self::new(G::new(()), search_space_size_hint);
     let initial_max_allowed_steps = ::std::cmp::max(initial_max_allowed_steps, start_states.iter_mut().filter_map(|mut s| emu.search_goal.distance_to_goal_heuristic(&mut s, 0)).min().unwrap());
-    emu.find_first_solution(start_states, initial_max_allowed_steps)
+    emu.find_first_solution(start_states, initial_max_allowed_steps, ::std::u16::MAX)
   }
 }
 impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
-  fn new(search_space_size_hint: usize) -> IDA<S, E, G, I> {
+  /// Like Search::find_first_solution for the goal created from goal_params, but gives up when there is no solution
+  /// with at most max_allowed_steps steps.
+  pub fn find_first_solution_within(goal_params: G::Params, mut start_states: Vec<State>, max_allowed_steps: Dist, search_space_size_hint: usize) -> SearchResult {
+    let emu = Self::new(G::new(goal_params), search_space_size_hint);
+    let initial_max_allowed_steps = start_states.iter_mut().filter_map(|mut s| emu.search_goal.distance_to_goal_heuristic(&mut s, 0)).min();
+    match initial_max_allowed_steps {
+      Some(initial_max_allowed_steps) => emu.find_first_solution(start_states, initial_max_allowed_steps, max_allowed_steps),
+      None => SearchResult::NotFound,
+    }
+  }
+  fn new(search_goal: G, search_space_size_hint: usize) -> IDA<S, E, G, I> {
     Self {
       visited_states: S::new(search_space_size_hint),
-      search_goal: G::new(),
-      _emu: PhantomData,
+      search_goal,
+      emu: E::new(),
       _input_fetcher: PhantomData,
       num_visits: 0,
       last_update_time_ns: 0,
@@ -145,8 +166,7 @@ impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
     if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: visit watched state {}, steps_already_taken: {}, heuristic {}", DEBUG_STATE_WATCHLIST.iter().position(|ss| ss == &s).unwrap(), steps_already_taken, heuristic_distance_to_goal); }
     if steps_already_taken >= max_allowed_steps
         || steps_already_taken + heuristic_distance_to_goal > max_allowed_steps // out of steps
-        || s.y_pos >= 0x1d000 // too low 0x1c600
-        || !self.visited_states.check_and_update_dist(&s, steps_already_taken) {
+        || !self.visited_states.check_and_update_dist(&s, steps_already_taken) { // not optimal with step dependent frames_after_goal
       return SearchResult::NotFound;
     }
 
@@ -164,12 +184,14 @@ impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
     if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: valid inputs: {:?}", I::valid_next_inputs(&s)); }
     for input in I::valid_next_inputs(&s) {
       if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: input: {}", input); }
-      let (new_state, emu_result) = E::run_step(s.clone(), input);
+      let (new_state, emu_result) = self.emu.run_step(s.clone(), input);
       if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: input: {} state: {}", input, new_state); }
       if self.search_goal.is_goal_state(&new_state, &emu_result) {
+        let goal_distance = 1 + self.search_goal.frames_after_goal(&new_state, steps_already_taken + 1);
+        if steps_already_taken + goal_distance > max_allowed_steps { continue; } // goal is completed too late
         println!("Found goal state after {} seen states!", self.visited_states.len());
-        if heuristic_distance_to_goal > 1 {
-          println!("WARNING: heuristic ({}) larger than actual steps needed (1) for state {:?}", heuristic_distance_to_goal, s);
+        if heuristic_distance_to_goal > goal_distance {
+          println!("WARNING: heuristic ({}) larger than actual steps needed ({}) for state {:?}", heuristic_distance_to_goal, goal_distance, s);
         }
         return SearchResult::Found(vec![new_state, s], vec![input]);
       }
@@ -178,8 +200,9 @@ impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
         if let SearchResult::Found(mut states, mut inputs) = self.find_first_solution_rec(new_state, steps_already_taken + 1, max_allowed_steps) {
         if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: recursive solution"); }
           inputs.push(input);
-          if heuristic_distance_to_goal as usize > inputs.len() {
-            println!("WARNING: heuristic ({}) larger than actual steps needed ({}) for state {:?}", heuristic_distance_to_goal, inputs.len(), s);
+          let goal_distance = inputs.len() as Dist + self.search_goal.frames_after_goal(&states[0], steps_already_taken + inputs.len() as Dist);
+          if heuristic_distance_to_goal > goal_distance {
+            println!("WARNING: heuristic ({}) larger than actual steps needed ({}) for state {:?}", heuristic_distance_to_goal, goal_distance, s);
           }
           states.push(s);
           return SearchResult::Found(states, inputs);
@@ -189,11 +212,11 @@ impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
     if DEBUG_MODE && DEBUG_STATE_WATCHLIST.contains(&s) { println!("DEBUG: exit state {}", DEBUG_STATE_WATCHLIST.iter().position(|ss| ss == &s).unwrap()); }
     SearchResult::NotFound
   }
-  fn find_first_solution(mut self, start_states: Vec<State>, initial_max_allowed_steps: Dist) -> SearchResult {
+  fn find_first_solution(mut self, start_states: Vec<State>, initial_max_allowed_steps: Dist, step_limit: Dist) -> SearchResult {
     let mut max_allowed_steps = initial_max_allowed_steps;
     self.last_update_time_ns = time::precise_time_ns();
 
-    loop {
+    while max_allowed_steps <= step_limit {
       println!("search max distance  {}", max_allowed_steps);
       for s in &start_states {
         if let SearchResult::Found(mut states, mut inputs) = self.find_first_solution_rec(s.clone(), 0, max_allowed_steps) {
@@ -204,8 +227,35 @@ impl<S: StateStore, E: Emu, G: SearchGoal, I: InputFetcher> IDA<S, E, G, I> {
       }
       self.search_goal.report_metrics();
 
+      if max_allowed_steps == step_limit { break; } // step_limit may be Dist::MAX
       max_allowed_steps += 1;
       self.visited_states.increment_all_dists(); // increase distance by one; only shortest paths to any known state will be considered next round
     }
+    SearchResult::NotFound
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use case::SmbSearchCase;
+  use case::w44::W44Clip;
+
+  /// Goal that is never reached, with every search ending after the first step.
+  struct Unreachable;
+  impl SearchGoal for Unreachable {
+    fn new(_: ()) -> Self { Unreachable }
+    fn distance_to_goal_heuristic(&self, _: &mut State, steps_already_taken: Dist) -> Option<Dist> {
+      if steps_already_taken == 0 { Some(::std::u16::MAX - 1) } else { None }
+    }
+    fn is_goal_state(&self, _: &State, _: &EmuResult) -> bool { false }
+  }
+
+  #[test]
+  fn search_stops_at_max_step_limit() {
+    type T = W44Clip;
+    let result = IDA::<<T as SmbSearchCase>::StateStore, <T as SmbSearchCase>::Emu, Unreachable, <T as SmbSearchCase>::InputFetcher>::find_first_solution_within(
+        (), vec![T::start_states().remove(0)], ::std::u16::MAX, 10);
+    if let SearchResult::Found(..) = result { panic!("found a solution for an unreachable goal"); }
   }
 }
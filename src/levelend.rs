@@ -0,0 +1,282 @@
+use blockbuffer::BlockBuffer;
+use emu::{CLIMB_Y_SPD_DOWN, Emu, SmbEmu};
+use emu::inputs::*;
+use options::Options;
+use state::{Dir, Dist, PlayerState, State};
+
+// FlagpoleObject
+const FLAG_Y_POS: i32 = 0x3000;
+// FlagpoleRoutine, the flag moves down using Enemy_YMF_Dummy (assumed to start at 0)
+const FLAG_Y_SPD: i32 = 0x1ff;
+const FLAG_BOTTOM_Y_POS: i32 = 0xaa;
+const PLAYER_BOTTOM_Y_POS: i32 = 0xa2;
+// FlagpoleSlide
+const SLIDE_BOTTOM_Y_POS: i32 = 0x9e;
+// PlayerEndLevel
+const CLIMB_SIDE_TIMER: u8 = 0x18;
+const END_OF_LEVEL_MUSIC_Y_POS: i32 = 0xae;
+const END_OF_LEVEL_MUSIC_MAX_FRAMES: Dist = 0x1e0; // upper bound for the length of EndOfLevelMusic (8 seconds)
+const MAX_WALK_FRAMES: Dist = 0x200; // upper bound for letting go of the pole and walking into the castle
+// RunStarFlagObj
+const STAR_FLAG_Y_POS: u8 = 0x90;
+const STAR_FLAG_RAISED_Y_POS: u8 = 0x72;
+const FIREWORKS_INTERVAL: u8 = 0x20; // FrenzyEnemyTimer
+const STAR_FLAG_INTERVAL_TIMER: u8 = 6; // EnemyIntervalTimer
+// RunGameTimer
+const GAME_TIMER_CTRL_FRAMES: u8 = 0x18;
+// Furthest the flag contact is worth delaying for fewer fireworks: 10 game timer decrements cost more than any fireworks
+const MAX_FIREWORKS_DELAY_FRAMES: Dist = 10 * GAME_TIMER_CTRL_FRAMES as Dist;
+
+/// Game timer display and GameTimerCtrlTimer, a frame timer after which RunGameTimer decrements the display.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct GameTimer {
+  pub display: u16,
+  pub ctrl_timer: u8,
+}
+impl GameTimer {
+  /// Game timer after running the given number of frames.
+  pub fn after_frames(&self, frames: Dist) -> GameTimer {
+    let mut t = *self;
+    for _ in 0..frames {
+      if t.ctrl_timer > 0 { t.ctrl_timer -= 1; }
+      if t.ctrl_timer == 0 {
+        assert!(t.display > 0, "game timer ran out");
+        t.display -= 1;
+        t.ctrl_timer = GAME_TIMER_CTRL_FRAMES;
+      }
+    }
+    t
+  }
+}
+
+struct FrameCounter {
+  frames: Dist,
+  interval_timer_control: u8,
+}
+impl FrameCounter {
+  /// Advances to the next frame, returns whether the interval timers are decremented in it.
+  fn next_frame(&mut self) -> bool {
+    self.frames += 1;
+    if self.interval_timer_control == 0 {
+      self.interval_timer_control = 0x14;
+      true
+    } else {
+      self.interval_timer_control -= 1;
+      false
+    }
+  }
+}
+
+fn player_y_pos(s: &State) -> i32 {
+  (s.y_pos >> 8) & 0xff
+}
+
+/// Number of frames from the frame of the flag contact (the state returned with EmuResult::StateChangeFlag) until the
+/// frame in which PlayerEndLevel loads the next area.
+/// game_timer is the game timer display and interval_timer_control the IntervalTimerControl value in the frame of the
+/// flag contact; the game timer is halted from there on.
+pub fn frames_until_next_level<O: Options, B: BlockBuffer>(s: &State, game_timer: u16, interval_timer_control: u8) -> Dist {
+  let emu = SmbEmu::<O, B>::new();
+  let mut s = s.clone();
+  let mut f = FrameCounter { frames: 0, interval_timer_control };
+
+  // FlagpoleSlide: the flag already moves in the frame of the contact
+  let mut flag_y_pos = FLAG_Y_POS;
+  while (flag_y_pos >> 8) < FLAG_BOTTOM_Y_POS && player_y_pos(&s) < PLAYER_BOTTOM_Y_POS {
+    flag_y_pos += FLAG_Y_SPD;
+    f.next_frame();
+    if player_y_pos(&s) < SLIDE_BOTTOM_Y_POS { s.y_pos += CLIMB_Y_SPD_DOWN as i32; }
+  }
+
+  // PlayerEndLevel: move to the other side of the pole, let go and walk right until hitting the castle
+  let mut music_start = None;
+  s.climb_side_timer = 0;
+  s.y_spd = 0;
+  let walk_start = f.frames;
+  loop {
+    assert!(f.frames - walk_start < MAX_WALK_FRAMES, "player didn't reach the castle within {} frames after the flag pole slide: {}", MAX_WALK_FRAMES, s);
+    f.next_frame();
+    if s.player_state == PlayerState::CLIMBING { // ClimbingSub with right pressed
+      if s.climb_side_timer > 0 { s.climb_side_timer -= 1; }
+      if s.climb_side_timer == 0 {
+        s.climb_side_timer = CLIMB_SIDE_TIMER;
+        if s.facing_dir == Dir::RIGHT {
+          s.x_pos += 0xe00;
+          s.facing_dir = Dir::LEFT;
+        } else {
+          s.x_pos += 0x400;
+          s.player_state = PlayerState::FALLING;
+        }
+      }
+      s.collision_bits = Dir::LR;
+    } else {
+      s = emu.run_step(s, R).0;
+    }
+    if music_start.is_none() && player_y_pos(&s) >= END_OF_LEVEL_MUSIC_Y_POS { music_start = Some(f.frames); }
+    if !s.collision_bits.contains(Dir::RIGHT) { break; }
+  }
+
+  castle_frames(&mut f, game_timer);
+  // the music is shorter than the tally and the wait for the interval timers, unless the game timer is very low
+  assert!(music_start.map_or(true, |frames| f.frames >= frames + END_OF_LEVEL_MUSIC_MAX_FRAMES), "level end depends on EndOfLevelMusic with game timer {}", game_timer);
+  f.frames
+}
+
+/// Frames after the player reached the castle: the game timer tally, the flag raise with the fireworks and
+/// DelayToAreaEnd, until PlayerEndLevel sees StarFlagTaskControl 5.
+fn castle_frames(f: &mut FrameCounter, game_timer: u16) -> () {
+  // GameTimerFireworks
+  let fireworks = match game_timer % 10 { 1 => 1, 3 => 3, 6 => 6, _ => 0 };
+  // AwardGameTimerPoints: one frame per timer unit, and one to notice it ran out
+  for _ in 0..=game_timer { f.next_frame(); }
+  // RaiseFlagSetoffFWorks
+  let mut star_flag_y_pos = STAR_FLAG_Y_POS;
+  loop {
+    f.next_frame();
+    if star_flag_y_pos < STAR_FLAG_RAISED_Y_POS { break; }
+    star_flag_y_pos -= 1;
+  }
+  // InitFireworks sets off one every FrenzyEnemyTimer interval, the flag waits for the last one to explode
+  for _ in 0..fireworks * FIREWORKS_INTERVAL as Dist { f.next_frame(); }
+  // DelayToAreaEnd: waits for the interval timer set by DrawFlagSetTimer and the end of the music
+  let mut star_flag_timer = STAR_FLAG_INTERVAL_TIMER;
+  while star_flag_timer > 0 {
+    if f.next_frame() { star_flag_timer -= 1; }
+  }
+  // PlayerEndLevel sees StarFlagTaskControl 5 in the next frame
+  f.next_frame();
+}
+
+/// Lower bound for frames_until_next_level, leaving out the flag slide and the walk to the castle. Every frame spent on
+/// them can only delay the frame rule the level ends in.
+fn min_frames_until_next_level(game_timer: u16, interval_timer_control: u8) -> Dist {
+  let mut f = FrameCounter { frames: 0, interval_timer_control };
+  castle_frames(&mut f, game_timer);
+  f.frames
+}
+
+/// Lower bounds for the frame in which the next level loads, depending on the frame of the flag contact, for searches
+/// in which the timers are never halted.
+pub struct LevelEndBounds {
+  min_level_end_frames: Vec<Dist>,
+}
+impl LevelEndBounds {
+  /// game_timer and parity (as tracked by Parity21) are the values in the start states of the search, flag contacts
+  /// before frame max_frames are covered.
+  pub fn new(game_timer: GameTimer, parity: u8, max_frames: Dist) -> LevelEndBounds {
+    // level end frame when touching the flag in the given frame, which RunGameTimer doesn't run in
+    let level_end_frames: Vec<Dist> = (0..max_frames + MAX_FIREWORKS_DELAY_FRAMES).map(|frames| {
+      let contact_game_timer = game_timer.after_frames(frames.saturating_sub(1)).display;
+      let interval_timer_control = 0x14 - ((parity as Dist + frames) % 21) as u8;
+      frames + min_frames_until_next_level(contact_game_timer, interval_timer_control)
+    }).collect();
+    let min_level_end_frames = (0..max_frames as usize).map(|frames| {
+      *level_end_frames[frames..frames + MAX_FIREWORKS_DELAY_FRAMES as usize].iter().min().unwrap()
+    }).collect();
+    LevelEndBounds { min_level_end_frames }
+  }
+  /// Lower bound for the frames from frame `frames` of the search until the next level loads, if the flag is touched
+  /// in frame contact_frames or later. None if contact_frames is not covered.
+  pub fn min_frames_until_next_level(&self, frames: Dist, contact_frames: Dist) -> Option<Dist> {
+    self.min_level_end_frames.get(contact_frames as usize).map(|level_end_frames| level_end_frames - frames)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use blockbuffer::NoCollisions;
+  use blockbuffer::world1::BB11;
+  use emu::EmuResult;
+  use options::*;
+  use state::PlayerState;
+  use super::*;
+
+  enum Flag {}
+  impl Options for Flag {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = Parity21;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+
+  /// State in the frame of touching the 1-1 flag pole, jumping at it from the stairs.
+  fn flag_contact() -> State {
+    let mut s = ::transition::entrance_state::<Flag>(0xc5000, 0x18000, PlayerState::FALLING);
+    for _ in 0..0x40 {
+      let (new_s, emu_result) = SmbEmu::<Flag, BB11>::new().run_step(s, R);
+      s = new_s;
+      if let EmuResult::StateChangeFlag(cx, _) = emu_result {
+        assert_eq!(cx, 0xc6);
+        return s;
+      }
+    }
+    panic!("flag pole not reached: {}", s);
+  }
+
+  #[test]
+  fn game_timer_decrements_every_ctrl_period() {
+    let t = GameTimer { display: 300, ctrl_timer: 3 };
+    assert_eq!(t.after_frames(2), GameTimer { display: 300, ctrl_timer: 1 });
+    assert_eq!(t.after_frames(3), GameTimer { display: 299, ctrl_timer: GAME_TIMER_CTRL_FRAMES });
+    assert_eq!(t.after_frames(3 + GAME_TIMER_CTRL_FRAMES as Dist), GameTimer { display: 298, ctrl_timer: GAME_TIMER_CTRL_FRAMES });
+  }
+
+  #[test]
+  fn level_end_waits_for_interval_timer() {
+    for interval_timer_control in 0..=0x14 {
+      // the interval timers are decremented in frame interval_timer_control + 1 and every 21 frames after it
+      let frames = min_frames_until_next_level(300, interval_timer_control);
+      assert_eq!((frames - 1 - (interval_timer_control as Dist + 1)) % 21, 0);
+    }
+  }
+
+  #[test]
+  fn fireworks_delay_level_end() {
+    for interval_timer_control in 0..=0x14 {
+      assert!(min_frames_until_next_level(301, interval_timer_control) > min_frames_until_next_level(302, interval_timer_control));
+      assert!(min_frames_until_next_level(303, interval_timer_control) > min_frames_until_next_level(304, interval_timer_control));
+      assert!(min_frames_until_next_level(306, interval_timer_control) > min_frames_until_next_level(307, interval_timer_control));
+    }
+  }
+
+  #[test]
+  fn bounds_below_flag_pole_level_end() {
+    let s = flag_contact();
+    for interval_timer_control in 0..=0x14 {
+      for &game_timer in &[300, 301, 306] {
+        let frames = frames_until_next_level::<Flag, BB11>(&s, game_timer, interval_timer_control);
+        assert!(frames > min_frames_until_next_level(game_timer, interval_timer_control));
+      }
+    }
+  }
+
+  #[test]
+  #[should_panic(expected = "didn't reach the castle")]
+  fn walk_to_castle_is_bounded() {
+    frames_until_next_level::<Flag, NoCollisions>(&flag_contact(), 300, 0);
+  }
+
+  #[test]
+  fn level_end_bounds_are_admissible() {
+    let game_timer = GameTimer { display: 301, ctrl_timer: 5 };
+    let parity = 7;
+    let max_frames = 0x40;
+    let bounds = LevelEndBounds::new(game_timer, parity, max_frames);
+    for contact_frames in 0..max_frames {
+      let bound = bounds.min_frames_until_next_level(0, contact_frames).unwrap();
+      for later_frames in contact_frames..max_frames {
+        let contact_game_timer = game_timer.after_frames(later_frames.saturating_sub(1)).display;
+        let interval_timer_control = 0x14 - ((parity as Dist + later_frames) % 21) as u8;
+        assert!(bound <= later_frames + min_frames_until_next_level(contact_game_timer, interval_timer_control));
+      }
+      assert_eq!(bounds.min_frames_until_next_level(contact_frames, contact_frames), Some(bound - contact_frames));
+    }
+    assert_eq!(bounds.min_frames_until_next_level(0, max_frames), None);
+  }
+}
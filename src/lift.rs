@@ -0,0 +1,128 @@
+use hitbox::Hitbox;
+
+// BoundBoxCtrlData entries of platforms
+pub const SMALL_LIFT: (i32, i32, i32, i32) = (0x00, 0x00, 0x18, 0x06);
+pub const LARGE_LIFT: (i32, i32, i32, i32) = (0x00, 0x00, 0x30, 0x0d);
+pub const CASTLE_LARGE_LIFT: (i32, i32, i32, i32) = (0x00, 0x00, 0x20, 0x0d); // in castles and secondary hard mode
+
+// MovePlatformDown and MovePlatformUp
+const PLATFORM_DOWN_FORCE: i16 = 0x05;
+const PLATFORM_UP_FORCE: i16 = 0x0a;
+const PLATFORM_MAX_Y_SPD: i16 = 0x03;
+// PlatLiftUp and PlatLiftDown
+pub const LIFT_UP_Y_SPD: i16 = -0xf0;
+pub const LIFT_DOWN_Y_SPD: i16 = 0xf0;
+// XMoveCntr_Platform
+const X_MOVE_SECONDARY_COUNTER_MAX: u8 = 0x0e;
+
+/// A moving platform in a single frame, positioned like the player (x_pos and y_pos include page and fractional part).
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub struct Lift {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub bounding_box: (i32, i32, i32, i32),
+}
+impl Lift {
+  pub fn hitbox(&self) -> Hitbox {
+    Hitbox::new(self.x_pos >> 8, self.y_pos >> 8, self.bounding_box)
+  }
+}
+
+/// Movement of a lift which does not depend on the player.
+pub trait LiftMotion {
+  fn next_frame(&mut self, frame_counter: u8) -> ();
+  fn lift(&self) -> Lift;
+}
+impl<M: LiftMotion + ?Sized> LiftMotion for Box<M> {
+  fn next_frame(&mut self, frame_counter: u8) -> () { (**self).next_frame(frame_counter) }
+  fn lift(&self) -> Lift { (**self).lift() }
+}
+
+/// Positions of a lift in every frame, starting with its current position. frame_counter is the FrameCounter value in
+/// the first frame it moves in.
+pub fn lift_path<M: LiftMotion>(mut motion: M, frame_counter: u8, frames: usize) -> Vec<Lift> {
+  let mut path = vec![motion.lift()];
+  for i in 0..frames {
+    motion.next_frame(frame_counter.wrapping_add(i as u8));
+    path.push(motion.lift());
+  }
+  path
+}
+
+/// ImposeGravity for platforms: moves by y_spd, then accelerates down by down_force and, if move_up is set, up by
+/// up_force, limited to max_spd in either direction.
+pub fn impose_gravity(y_pos: &mut i32, y_spd: &mut i16, down_force: i16, up_force: i16, max_spd: i16, move_up: bool) -> () {
+  *y_pos += *y_spd as i32;
+  *y_spd += down_force;
+  if (*y_spd >> 8) >= max_spd && (*y_spd & 0xff) >= 0x80 { *y_spd = max_spd << 8; }
+  if move_up {
+    *y_spd -= up_force;
+    if (*y_spd >> 8) < -max_spd && (*y_spd & 0xff) < 0x80 { *y_spd = (-max_spd << 8) | 0xff; }
+  }
+}
+fn move_platform_down(y_pos: &mut i32, y_spd: &mut i16) -> () {
+  impose_gravity(y_pos, y_spd, PLATFORM_DOWN_FORCE, PLATFORM_UP_FORCE, PLATFORM_MAX_Y_SPD, false);
+}
+fn move_platform_up(y_pos: &mut i32, y_spd: &mut i16) -> () {
+  impose_gravity(y_pos, y_spd, PLATFORM_DOWN_FORCE, PLATFORM_UP_FORCE, PLATFORM_MAX_Y_SPD, true);
+}
+
+/// Lift moving up or down at a constant speed (MoveLiftPlatforms), wrapping around vertically.
+pub struct ConstantLift {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub y_spd: i16,
+  pub bounding_box: (i32, i32, i32, i32),
+}
+impl LiftMotion for ConstantLift {
+  fn next_frame(&mut self, _: u8) -> () {
+    self.y_pos = (self.y_pos & !0xffff) | ((self.y_pos + self.y_spd as i32) & 0xffff);
+  }
+  fn lift(&self) -> Lift { Lift { x_pos: self.x_pos, y_pos: self.y_pos, bounding_box: self.bounding_box } }
+}
+
+/// Lift moving left and right (XMovingPlatform), its speed driven by the XMoveCntr_Platform counters.
+pub struct XMovingLift {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub primary_counter: u8,
+  pub secondary_counter: u8,
+  pub bounding_box: (i32, i32, i32, i32),
+}
+impl LiftMotion for XMovingLift {
+  fn next_frame(&mut self, frame_counter: u8) -> () {
+    if frame_counter & 3 == 0 { // XMoveCntr_Platform
+      if self.primary_counter & 1 == 0 && self.secondary_counter != X_MOVE_SECONDARY_COUNTER_MAX { self.secondary_counter += 1; }
+      else if self.primary_counter & 1 != 0 && self.secondary_counter != 0 { self.secondary_counter -= 1; }
+      else { self.primary_counter = self.primary_counter.wrapping_add(1); }
+    }
+    // MoveWithXMCntrs
+    let x_spd = if self.primary_counter & 2 != 0 { self.secondary_counter as i32 } else { -(self.secondary_counter as i32) };
+    self.x_pos += x_spd << 4;
+  }
+  fn lift(&self) -> Lift { Lift { x_pos: self.x_pos, y_pos: self.y_pos, bounding_box: self.bounding_box } }
+}
+
+/// Lift moving up and down (YMovingPlatform), accelerating towards its center position.
+pub struct YMovingLift {
+  pub x_pos: i32,
+  pub y_pos: i32,
+  pub y_spd: i16,
+  pub top_y_pos: i32,
+  pub center_y_pos: i32,
+  pub bounding_box: (i32, i32, i32, i32),
+}
+impl LiftMotion for YMovingLift {
+  fn next_frame(&mut self, frame_counter: u8) -> () {
+    if self.y_spd == 0 {
+      self.y_pos &= !0xff;
+      if ((self.y_pos >> 8) & 0xff) < self.top_y_pos {
+        if frame_counter & 7 == 0 { self.y_pos += 0x100; }
+        return;
+      }
+    }
+    if ((self.y_pos >> 8) & 0xff) < self.center_y_pos { move_platform_down(&mut self.y_pos, &mut self.y_spd); }
+    else { move_platform_up(&mut self.y_pos, &mut self.y_spd); }
+  }
+  fn lift(&self) -> Lift { Lift { x_pos: self.x_pos, y_pos: self.y_pos, bounding_box: self.bounding_box } }
+}
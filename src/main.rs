@@ -13,18 +13,40 @@ extern crate typenum;
 use case::Case;
 
 mod blockbuffer;
+mod bowser;
 mod case;
+mod cli;
 mod emu;
+mod enemy;
+mod enemydata;
+mod hazard;
 mod heuristics;
+mod hitbox;
 mod ida;
+mod levelend;
+mod lift;
+mod movie;
+mod multiarea;
 mod options;
+mod ram;
+mod replay;
+mod rom;
+mod scan;
 mod state;
 mod store;
+mod transition;
 
 fn main() {
-  // calc_prng();
-  case::w84::W84Part4Speedup::run();
-  // case::w21::W21ScreenScrollBlock3PipeClip::run();
+  let args: Vec<String> = ::std::env::args().skip(1).collect();
+  match args.first().map(|a| a.as_str()) {
+    Some("bbconvert") => blockbuffer::import::run(&args[1..]),
+    Some(command) => cli::run(command, &args[1..]),
+    None => {
+      // calc_prng();
+      case::w84::W84Part4Speedup::run();
+      // case::w21::W21ScreenScrollBlock3PipeClip::run();
+    },
+  }
 }
 
 #[allow(dead_code)]
@@ -0,0 +1,194 @@
+use emu::Input;
+use movie::with_leading_frames;
+use std::fs::File;
+use std::io::{self, Write};
+
+// Super Mario Bros. (World), SHA1 of the ROM without iNES header
+const GAME_NAME: &str = "Super Mario Bros.";
+const ROM_SHA1: &str = "EA343F4E445A9050D4B4FBAC2C77D0693B1D0922";
+const LOG_KEY: &str = "#Reset|Power|#P1 Up|P1 Down|P1 Left|P1 Right|P1 Start|P1 Select|P1 B|P1 A|";
+// NES controller mnemonics, in LOG_KEY order
+const BUTTONS: [(Input, char); 8] = [
+    (Input::UP, 'U'), (Input::DOWN, 'D'), (Input::LEFT, 'L'), (Input::RIGHT, 'R'),
+    (Input::START, 'S'), (Input::SELECT, 's'), (Input::B, 'B'), (Input::A, 'A')];
+
+/// Input log line of an input on controller 1, e.g. "|..|...R...A|".
+pub fn input_line(input: Input) -> String {
+  let buttons: String = BUTTONS.iter().map(|&(button, c)| if input.contains(button) { c } else { '.' }).collect();
+  format!("|..|{}|", buttons)
+}
+
+/// Writes just the input log lines of the inputs, preceded by leading_frames empty frames, to be pasted into the
+/// Input Log.txt of an existing movie at a chosen frame.
+pub fn write_input_log_fragment<W: Write>(w: &mut W, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  for &input in with_leading_frames(inputs, leading_frames).iter() {
+    writeln!(w, "{}", input_line(input))?;
+  }
+  Ok(())
+}
+
+/// Contents of the Input Log.txt of a movie.
+fn input_log(inputs: &[Input], leading_frames: usize) -> io::Result<Vec<u8>> {
+  let mut log = vec![];
+  writeln!(log, "[Input]")?;
+  writeln!(log, "LogKey:{}", LOG_KEY)?;
+  write_input_log_fragment(&mut log, inputs, leading_frames)?;
+  writeln!(log, "[/Input]")?;
+  Ok(log)
+}
+
+/// Contents of the Header.txt of a movie starting at power-on.
+fn header() -> io::Result<Vec<u8>> {
+  let mut header = vec![];
+  writeln!(header, "MovieVersion BizHawk v2.0.0")?;
+  writeln!(header, "Author ")?;
+  writeln!(header, "Platform NES")?;
+  writeln!(header, "GameName {}", GAME_NAME)?;
+  writeln!(header, "SHA1 {}", ROM_SHA1)?;
+  writeln!(header, "Core NesHawk")?;
+  writeln!(header, "rerecordCount 0")?;
+  Ok(header)
+}
+
+/// Writes a complete .bk2 movie starting at power-on, with the inputs preceded by leading_frames empty frames.
+pub fn write_movie<W: Write>(w: &mut W, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  write_zip(w, &[("Header.txt", header()?), ("Input Log.txt", input_log(inputs, leading_frames)?)])
+}
+
+/// Writes a complete .bk2 movie to the given file, see write_movie.
+pub fn save_movie(path: &str, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  write_movie(&mut File::create(path)?, inputs, leading_frames)
+}
+
+fn crc32(data: &[u8]) -> u32 {
+  let mut crc = !0u32;
+  for &b in data {
+    crc ^= b as u32;
+    for _ in 0..8 { crc = if crc & 1 != 0 { (crc >> 1) ^ 0xedb88320 } else { crc >> 1 }; }
+  }
+  !crc
+}
+
+/// Writes the files as an uncompressed (stored) zip archive.
+fn write_zip<W: Write>(w: &mut W, files: &[(&str, Vec<u8>)]) -> io::Result<()> {
+  fn u16le(v: usize) -> [u8; 2] { [v as u8, (v >> 8) as u8] }
+  fn u32le(v: u32) -> [u8; 4] { [v as u8, (v >> 8) as u8, (v >> 16) as u8, (v >> 24) as u8] }
+
+  let mut offset = 0u32;
+  let mut central_directory = vec![];
+  for &(name, ref data) in files {
+    let crc = crc32(data);
+    // local file header: version 2.0, no flags, stored, no timestamp
+    let mut local_header = vec![];
+    local_header.extend_from_slice(&u32le(0x04034b50));
+    local_header.extend_from_slice(&[20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+    local_header.extend_from_slice(&u32le(crc));
+    local_header.extend_from_slice(&u32le(data.len() as u32));
+    local_header.extend_from_slice(&u32le(data.len() as u32));
+    local_header.extend_from_slice(&u16le(name.len()));
+    local_header.extend_from_slice(&u16le(0));
+    local_header.extend_from_slice(name.as_bytes());
+    w.write_all(&local_header)?;
+    w.write_all(data)?;
+
+    central_directory.extend_from_slice(&u32le(0x02014b50));
+    central_directory.extend_from_slice(&[20, 0, 20, 0, 0, 0, 0, 0, 0, 0, 0x21, 0]);
+    central_directory.extend_from_slice(&u32le(crc));
+    central_directory.extend_from_slice(&u32le(data.len() as u32));
+    central_directory.extend_from_slice(&u32le(data.len() as u32));
+    central_directory.extend_from_slice(&u16le(name.len()));
+    central_directory.extend_from_slice(&[0; 12]); // extra, comment, disk, attributes
+    central_directory.extend_from_slice(&u32le(offset));
+    central_directory.extend_from_slice(name.as_bytes());
+    offset += (local_header.len() + data.len()) as u32;
+  }
+  w.write_all(&central_directory)?;
+
+  let mut end_of_central_directory = vec![];
+  end_of_central_directory.extend_from_slice(&u32le(0x06054b50));
+  end_of_central_directory.extend_from_slice(&[0, 0, 0, 0]);
+  end_of_central_directory.extend_from_slice(&u16le(files.len()));
+  end_of_central_directory.extend_from_slice(&u16le(files.len()));
+  end_of_central_directory.extend_from_slice(&u32le(central_directory.len() as u32));
+  end_of_central_directory.extend_from_slice(&u32le(offset));
+  end_of_central_directory.extend_from_slice(&u16le(0));
+  w.write_all(&end_of_central_directory)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use emu::inputs::*;
+
+  fn u16_at(data: &[u8], i: usize) -> usize { data[i] as usize | (data[i + 1] as usize) << 8 }
+  fn u32_at(data: &[u8], i: usize) -> u32 { u16_at(data, i) as u32 | (u16_at(data, i + 2) as u32) << 16 }
+
+  /// Files of a stored zip archive, following the central directory and checking the local headers and CRCs.
+  fn read_zip(zip: &[u8]) -> Vec<(String, Vec<u8>)> {
+    let end = zip.len() - 22;
+    assert_eq!(u32_at(zip, end), 0x06054b50);
+    let (count, mut entry) = (u16_at(zip, end + 10), u32_at(zip, end + 16) as usize);
+    assert_eq!(entry + u32_at(zip, end + 12) as usize, end);
+    (0..count).map(|_| {
+      assert_eq!(u32_at(zip, entry), 0x02014b50);
+      let (crc, size, name_len) = (u32_at(zip, entry + 16), u32_at(zip, entry + 20) as usize, u16_at(zip, entry + 28));
+      let offset = u32_at(zip, entry + 42) as usize;
+      let name = String::from_utf8(zip[entry + 46..entry + 46 + name_len].to_vec()).unwrap();
+      entry += 46 + name_len;
+      assert_eq!(u32_at(zip, offset), 0x04034b50);
+      assert_eq!(u16_at(zip, offset + 8), 0); // stored
+      assert_eq!((u32_at(zip, offset + 14), u32_at(zip, offset + 18) as usize), (crc, size));
+      assert_eq!(&zip[offset + 30..offset + 30 + name_len], name.as_bytes());
+      let data = zip[offset + 30 + name_len..offset + 30 + name_len + size].to_vec();
+      assert_eq!(crc32(&data), crc);
+      (name, data)
+    }).collect()
+  }
+
+  /// Input of an input log line, the inverse of input_line.
+  fn parse_input_line(line: &str) -> Input {
+    assert!(line.starts_with("|..|") && line.ends_with('|') && line.len() == 13, "malformed input line {}", line);
+    line[4..12].chars().zip(BUTTONS.iter()).fold(Input::empty(), |input, (c, &(button, mnemonic))| {
+      if c == '.' { input } else { assert_eq!(c, mnemonic); input | button }
+    })
+  }
+
+  #[test]
+  fn crc32_matches_check_value() {
+    assert_eq!(crc32(b"123456789"), 0xcbf43926);
+  }
+
+  #[test]
+  fn input_lines_use_log_key_order() {
+    assert_eq!(input_line(NIL), "|..|........|");
+    assert_eq!(input_line(A|R), "|..|...R...A|");
+    assert_eq!(input_line(Input::all()), "|..|UDLRSsBA|");
+  }
+
+  #[test]
+  fn written_movie_is_a_zip_and_round_trips() {
+    let inputs: Vec<Input> = (0..=0xff).map(|bits: u8| Input::from_bits_truncate(bits)).collect();
+    let mut movie = vec![];
+    write_movie(&mut movie, &inputs, 2).unwrap();
+    let files = read_zip(&movie);
+    assert_eq!(files.iter().map(|&(ref name, _)| name.as_str()).collect::<Vec<_>>(), vec!["Header.txt", "Input Log.txt"]);
+
+    let header = String::from_utf8(files[0].1.clone()).unwrap();
+    for line in &["MovieVersion BizHawk v2.0.0", "Platform NES", "GameName Super Mario Bros.", "Core NesHawk"] {
+      assert!(header.lines().any(|l| l == *line), "missing header line {}", line);
+    }
+
+    let log = String::from_utf8(files[1].1.clone()).unwrap();
+    let lines: Vec<&str> = log.lines().collect();
+    assert_eq!((lines[0], lines[1], lines[lines.len() - 1]), ("[Input]", "LogKey:#Reset|Power|#P1 Up|P1 Down|P1 Left|P1 Right|P1 Start|P1 Select|P1 B|P1 A|", "[/Input]"));
+    let logged: Vec<Input> = lines[2..lines.len() - 1].iter().map(|line| parse_input_line(line)).collect();
+    assert_eq!(logged, with_leading_frames(&inputs, 2));
+  }
+
+  #[test]
+  fn fragment_has_only_input_lines() {
+    let mut fragment = vec![];
+    write_input_log_fragment(&mut fragment, &[R, B|L], 1).unwrap();
+    assert_eq!(String::from_utf8(fragment).unwrap(), "|..|........|\n|..|...R....|\n|..|..L...B.|\n");
+  }
+}
@@ -0,0 +1,202 @@
+use emu::Input;
+use movie::with_leading_frames;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+
+// Super Mario Bros. (World), MD5 811b027eaf99c2def7b933c5208636de
+const ROM_FILENAME: &str = "Super Mario Bros.";
+const ROM_CHECKSUM: &str = "base64:gRsCfq+Zwt73uTPFIIY23g==";
+// gamepad column of the input log, "RLDUTSBA"
+const BUTTONS: [(Input, char); 8] = [
+    (Input::RIGHT, 'R'), (Input::LEFT, 'L'), (Input::DOWN, 'D'), (Input::UP, 'U'),
+    (Input::START, 'T'), (Input::SELECT, 'S'), (Input::B, 'B'), (Input::A, 'A')];
+
+/// Gamepad column of an input, e.g. "R......A".
+pub fn gamepad(input: Input) -> String {
+  BUTTONS.iter().map(|&(button, c)| if input.contains(button) { c } else { '.' }).collect()
+}
+
+/// Input log line with the input on port 0 and no commands.
+pub fn input_line(input: Input) -> String {
+  format!("|0|{}|||", gamepad(input))
+}
+
+/// Writes the input log lines of the inputs, preceded by leading_frames empty frames.
+pub fn write_input_log<W: Write>(w: &mut W, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  for &input in with_leading_frames(inputs, leading_frames).iter() {
+    writeln!(w, "{}", input_line(input))?;
+  }
+  Ok(())
+}
+
+/// Writes a complete movie starting at power-on, with the inputs preceded by leading_frames empty frames.
+pub fn write_movie<W: Write>(w: &mut W, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  writeln!(w, "version 3")?;
+  writeln!(w, "emuVersion 22020")?;
+  writeln!(w, "rerecordCount 0")?;
+  writeln!(w, "palFlag 0")?;
+  writeln!(w, "romFilename {}", ROM_FILENAME)?;
+  writeln!(w, "romChecksum {}", ROM_CHECKSUM)?;
+  writeln!(w, "guid 00000000-0000-0000-0000-000000000000")?;
+  writeln!(w, "fourscore 0")?;
+  writeln!(w, "microphone 0")?;
+  writeln!(w, "port0 1")?;
+  writeln!(w, "port1 0")?;
+  writeln!(w, "port2 0")?;
+  writeln!(w, "FDS 0")?;
+  writeln!(w, "NewPPU 0")?;
+  write_input_log(w, inputs, leading_frames)
+}
+
+/// Writes a complete movie to the given file, see write_movie.
+pub fn save_movie(path: &str, inputs: &[Input], leading_frames: usize) -> io::Result<()> {
+  write_movie(&mut File::create(path)?, inputs, leading_frames)
+}
+
+/// Splices the inputs into an existing movie, overwriting its input log from the given frame on. Movies shorter than
+/// that are padded with empty frames.
+pub fn splice(movie: &str, inputs: &[Input], frame: usize) -> String {
+  let mut header: Vec<&str> = vec![];
+  let mut input_log: Vec<String> = vec![];
+  for line in movie.lines() {
+    if line.starts_with('|') { input_log.push(line.to_string()); } else { header.push(line); }
+  }
+  while input_log.len() < frame { input_log.push(input_line(Input::empty())); }
+  for (i, &input) in inputs.iter().enumerate() {
+    if frame + i < input_log.len() { input_log[frame + i] = input_line(input); } else { input_log.push(input_line(input)); }
+  }
+  let mut result = String::new();
+  for line in header { result.push_str(line); result.push('\n'); }
+  for line in input_log { result.push_str(&line); result.push('\n'); }
+  result
+}
+
+/// Malformed input log line of a movie, with its line number (starting at 1).
+#[derive(Debug)]
+pub struct Fm2ParseError {
+  pub line_number: usize,
+  pub line: String,
+}
+impl fmt::Display for Fm2ParseError {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    write!(f, "malformed input log line {}: \"{}\"", self.line_number, self.line)
+  }
+}
+
+/// Input of port 0 in an input log line. Any character other than '.' and ' ' counts as a pressed button.
+pub fn parse_input_line(line: &str) -> Option<Input> {
+  let columns: Vec<&str> = line.split('|').collect();
+  if columns.len() < 4 || !columns[0].is_empty() || columns[2].chars().count() != BUTTONS.len() { return None; }
+  Some(columns[2].chars().zip(BUTTONS.iter()).fold(Input::empty(), |input, (c, &(button, _))| {
+    if c == '.' || c == ' ' { input } else { input | button }
+  }))
+}
+
+/// Inputs of the given frames of a movie, ignoring its header. The range is cut off at the end of the movie.
+pub fn parse_inputs(movie: &str, frames: Range<usize>) -> Result<Vec<Input>, Fm2ParseError> {
+  let mut inputs = vec![];
+  let input_log = movie.lines().enumerate().filter(|&(_, line)| line.starts_with('|'));
+  for (line_index, line) in input_log.skip(frames.start).take(frames.end - frames.start) {
+    match parse_input_line(line) {
+      Some(input) => inputs.push(input),
+      None => return Err(Fm2ParseError { line_number: line_index + 1, line: line.to_string() }),
+    }
+  }
+  Ok(inputs)
+}
+
+/// Inputs of the given frames of a movie file, see parse_inputs.
+pub fn load_inputs(path: &str, frames: Range<usize>) -> Vec<Input> {
+  let mut movie = String::new();
+  File::open(path).and_then(|mut f| f.read_to_string(&mut movie)).unwrap_or_else(|e| panic!("can't read {}: {}", path, e));
+  parse_inputs(&movie, frames).unwrap_or_else(|e| panic!("can't parse {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use emu::inputs::*;
+
+  fn all_inputs() -> Vec<Input> {
+    (0..=0xff).map(|bits: u8| Input::from_bits_truncate(bits)).collect()
+  }
+
+  #[test]
+  fn input_lines_use_fceux_button_order() {
+    assert_eq!(input_line(NIL), "|0|........|||");
+    assert_eq!(input_line(A|R), "|0|R......A|||");
+    assert_eq!(input_line(Input::all()), "|0|RLDUTSBA|||");
+    assert_eq!(gamepad(Input::START | Input::DOWN), "..D.T...");
+  }
+
+  #[test]
+  fn written_movie_has_header_and_round_trips() {
+    let inputs = all_inputs();
+    let mut movie = vec![];
+    write_movie(&mut movie, &inputs, 3).unwrap();
+    let movie = String::from_utf8(movie).unwrap();
+    let lines: Vec<&str> = movie.lines().collect();
+    assert_eq!(lines[0], "version 3");
+    let header: Vec<&str> = lines.iter().cloned().take_while(|line| !line.starts_with('|')).collect();
+    for line in &["romFilename Super Mario Bros.", "romChecksum base64:gRsCfq+Zwt73uTPFIIY23g==", "port0 1", "port1 0", "palFlag 0"] {
+      assert!(header.contains(line), "missing header line {}", line);
+    }
+    assert_eq!(lines.len(), header.len() + 3 + inputs.len());
+    assert!(lines[header.len()..header.len() + 3].iter().all(|&line| line == "|0|........|||"));
+    assert_eq!(parse_inputs(&movie, 0..usize::max_value()).unwrap(), with_leading_frames(&inputs, 3));
+    assert_eq!(parse_inputs(&movie, 3..3 + inputs.len()).unwrap(), inputs);
+  }
+
+  #[test]
+  fn splice_overwrites_from_frame() {
+    let mut movie = vec![];
+    write_movie(&mut movie, &[R; 5], 0).unwrap();
+    let movie = String::from_utf8(movie).unwrap();
+    let spliced = splice(&movie, &[A, B|L], 2);
+    assert!(spliced.starts_with(&movie[..movie.find('|').unwrap()])); // header unchanged
+    assert_eq!(parse_inputs(&spliced, 0..usize::max_value()).unwrap(), vec![R, R, A, B|L, R]);
+    // past the end, with padding
+    let spliced = splice(&movie, &[A, B|L], 7);
+    assert_eq!(parse_inputs(&spliced, 0..usize::max_value()).unwrap(), vec![R, R, R, R, R, NIL, NIL, A, B|L]);
+  }
+
+  #[test]
+  fn input_lines_are_parsed_leniently() {
+    assert_eq!(parse_input_line("|0|R......A|||"), Some(A|R));
+    assert_eq!(parse_input_line("|0|  D  S  |||"), Some(D|Input::SELECT));
+    assert_eq!(parse_input_line("|0|xxxxxxxx|........|"), Some(Input::all())); // any marker, port 1 ignored
+    for line in &["|0|R......|||", "0|R......A|||", "|0|R......A", "|2"] {
+      assert_eq!(parse_input_line(line), None, "{}", line);
+    }
+  }
+
+  #[test]
+  fn parse_inputs_skips_header_and_cuts_range() {
+    let movie = "version 3\nromFilename Super Mario Bros.\n|0|R.......|||\n|0|.......A|||\ncomment in the log\n|0|......B.|||\n";
+    assert_eq!(parse_inputs(movie, 0..3).unwrap(), vec![R, A, B]);
+    assert_eq!(parse_inputs(movie, 1..2).unwrap(), vec![A]);
+    assert_eq!(parse_inputs(movie, 2..10).unwrap(), vec![B]);
+    assert!(parse_inputs(movie, 5..10).unwrap().is_empty());
+  }
+
+  #[test]
+  fn parse_inputs_reports_malformed_line() {
+    let movie = "version 3\n|0|R.......|||\n|0|R.|||\n";
+    let e = parse_inputs(movie, 0..2).unwrap_err();
+    assert_eq!((e.line_number, e.line.as_str()), (3, "|0|R.|||"));
+    assert_eq!(e.to_string(), "malformed input log line 3: \"|0|R.|||\"");
+    // outside of the range
+    assert_eq!(parse_inputs(movie, 0..1).unwrap(), vec![R]);
+  }
+
+  #[test]
+  fn load_inputs_reads_written_movie() {
+    let path = ::std::env::temp_dir().join(format!("smb-opt-load-{}.fm2", ::std::process::id()));
+    let path = path.to_str().unwrap();
+    save_movie(path, &[R, A|R, L], 1).unwrap();
+    assert_eq!(load_inputs(path, 1..3), vec![R, A|R]);
+    ::std::fs::remove_file(path).unwrap();
+  }
+}
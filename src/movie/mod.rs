@@ -0,0 +1,11 @@
+use emu::Input;
+
+pub mod bk2;
+pub mod fm2;
+
+/// The inputs preceded by leading_frames frames without input, e.g. to offset a segment to its frame in a movie.
+pub fn with_leading_frames(inputs: &[Input], leading_frames: usize) -> Vec<Input> {
+  let mut result = vec![Input::empty(); leading_frames];
+  result.extend_from_slice(inputs);
+  result
+}
@@ -0,0 +1,152 @@
+use blockbuffer::BlockBuffer;
+use emu::{Emu, EmuResult, Input, SmbEmu};
+use options::{AreaHandler, Options, Parity};
+use state::{Dist, State};
+use transition::{Entrance, area_transition};
+
+/// Block buffer of an unused area.
+pub struct NoArea;
+impl BlockBuffer for NoArea {
+  fn new() -> Self { NoArea }
+  fn get_block_at(&self, _: usize, _: usize) -> u8 { 0 }
+}
+
+/// Areas of a multi-area search, identified by State::area, and the transitions connecting them.
+pub trait AreaConnections {
+  type Area0: BlockBuffer;
+  type Area1: BlockBuffer;
+  type Area2: BlockBuffer = NoArea;
+  type Area3: BlockBuffer = NoArea;
+
+  /// Destination area and entrance for a transition EmuResult in the given area, or None if the transition is not
+  /// part of the search.
+  fn connect(area: u8, s: &State, emu_result: &EmuResult) -> Option<(u8, Entrance)>;
+}
+
+/// Emulator switching between the areas of C. When a step results in a connected transition, it continues in the
+/// destination area with its entrance state, and State::area_change_timer counts down the frames of the transition,
+/// in which all inputs are ignored. Use with options::MultiArea.
+pub struct MultiAreaEmu<O: Options, C: AreaConnections> {
+  area0: SmbEmu<O, C::Area0>,
+  area1: SmbEmu<O, C::Area1>,
+  area2: SmbEmu<O, C::Area2>,
+  area3: SmbEmu<O, C::Area3>,
+}
+impl<O: Options, C: AreaConnections> MultiAreaEmu<O, C> {
+  fn run_area_step(&self, area: u8, s: State, input: Input) -> (State, EmuResult) {
+    match area {
+      0 => self.area0.run_step(s, input),
+      1 => self.area1.run_step(s, input),
+      2 => self.area2.run_step(s, input),
+      3 => self.area3.run_step(s, input),
+      _ => panic!("invalid area {}", area),
+    }
+  }
+  fn area_transition(&self, area: u8, s: &State, emu_result: &EmuResult, entrance: Entrance) -> Option<(State, Dist)> {
+    match area {
+      0 => area_transition(&self.area0, s, emu_result, entrance),
+      1 => area_transition(&self.area1, s, emu_result, entrance),
+      2 => area_transition(&self.area2, s, emu_result, entrance),
+      3 => area_transition(&self.area3, s, emu_result, entrance),
+      _ => panic!("invalid area {}", area),
+    }
+  }
+}
+impl<O: Options, C: AreaConnections> Emu for MultiAreaEmu<O, C> {
+  fn new() -> Self {
+    MultiAreaEmu { area0: SmbEmu::new(), area1: SmbEmu::new(), area2: SmbEmu::new(), area3: SmbEmu::new() }
+  }
+  fn run_step(&self, mut s: State, input: Input) -> (State, EmuResult) {
+    if s.area_change_timer > 0 {
+      s.area_change_timer -= 1;
+      s.parity = (s.parity + 1) % O::Parity::PARITY;
+      return (s, EmuResult::Success);
+    }
+    let area = s.area;
+    let (s, emu_result) = self.run_area_step(area, s, input);
+    if let Some((next_area, entrance)) = C::connect(area, &s, &emu_result) {
+      let (next_s, frames) = self.area_transition(next_area, &s, &emu_result, entrance).unwrap();
+      assert!(frames < 1 << O::AreaHandler::AREA_CHANGE_TIMER_BITS, "area transition of {} frames doesn't fit AREA_CHANGE_TIMER_BITS", frames);
+      let next_s = State {
+        area: next_area,
+        area_change_timer: frames,
+        // kept across areas
+        collected_coins: s.collected_coins,
+        powerup_block_hit: s.powerup_block_hit,
+        powerup_collected: s.powerup_collected,
+        parity: s.parity,
+        injured: s.injured,
+        injury_timer: s.injury_timer,
+        ..next_s
+      };
+      return (next_s, EmuResult::Success);
+    }
+    (s, emu_result)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use blockbuffer::world1::BB11;
+  use options::*;
+  use state::PlayerState;
+  use super::*;
+
+  enum VineRoute {}
+  impl Options for VineRoute {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = Parity21;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+    type AreaHandler = MultiArea;
+  }
+  /// Top of a vine in every column.
+  struct VineTops;
+  impl BlockBuffer for VineTops {
+    fn new() -> Self { VineTops }
+    fn get_block_at(&self, _: usize, _: usize) -> u8 { 0x26 }
+  }
+  impl AreaConnections for VineRoute {
+    type Area0 = VineTops;
+    type Area1 = BB11;
+
+    fn connect(area: u8, _: &State, emu_result: &EmuResult) -> Option<(u8, Entrance)> {
+      match (area, emu_result) {
+        (0, &EmuResult::StateChangeVineAutoclimb(_, _)) => Some((1, Entrance::Vine(0, 0))),
+        _ => None,
+      }
+    }
+  }
+  type VineRouteEmu = MultiAreaEmu<VineRoute, VineRoute>;
+
+  #[test]
+  fn vine_transition_keeps_player_fields() {
+    let mut s = ::transition::entrance_state::<VineRoute>(0x1800, 0x11f00, PlayerState::FALLING);
+    s.collected_coins = 3;
+    s.powerup_block_hit = true;
+    s.powerup_collected = true;
+    s.parity = 5;
+    s.injured = true;
+    s.injury_timer = 7;
+    let emu = VineRouteEmu::new();
+    let (next_s, emu_result) = emu.run_step(s.clone(), Input::empty());
+    assert_eq!(emu_result, EmuResult::Success);
+    assert_eq!(next_s.area, 1);
+    assert!(next_s.area_change_timer > 0 && next_s.area_change_timer < 1 << MultiArea::AREA_CHANGE_TIMER_BITS);
+    assert_eq!((next_s.collected_coins, next_s.powerup_block_hit, next_s.powerup_collected), (3, true, true));
+    assert_eq!((next_s.parity, next_s.injured, next_s.injury_timer), (6, true, 7)); // parity of the touching frame
+
+    // inputs are ignored until the transition ends
+    let mut t = next_s.clone();
+    for _ in 0..next_s.area_change_timer { t = emu.run_step_nr(t, Input::all()); }
+    assert_eq!(t.area_change_timer, 0);
+    assert_eq!((t.x_pos, t.y_pos), (next_s.x_pos, next_s.y_pos));
+    assert_eq!(t.parity as Dist, (6 + next_s.area_change_timer) % 21);
+  }
+}
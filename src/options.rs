@@ -1,3 +1,5 @@
+use enemy::Enemy;
+use lift::Lift;
 use state::State;
 use std::marker::PhantomData;
 use typenum::Unsigned;
@@ -15,6 +17,15 @@ pub trait Options {
   type Parity: Parity;
   type VerticalPipeHandler: VerticalPipeHandler;
   type YPosFractionalBehavior: YPosFractionalBehavior;
+  type DamageHandler: DamageHandler = NoDamage;
+  type VineHandler: VineHandler = StopAtVines;
+  type AreaHandler: AreaHandler = SingleArea;
+  type ScrollLock: ScrollLock = NoScrollLock;
+  type LiftHandler: LiftHandler = NoLifts;
+  type JumpspringHandler: JumpspringHandler = NoJumpsprings;
+  type HazardHandler: HazardHandler = NoHazards;
+  type KillPlane: KillPlane = BottomOfScreen;
+  type EnemyHandler: EnemyHandler = NoEnemies;
 }
 #[allow(dead_code)]
 pub struct SmbOptions<Size: PlayerSize, Swi: Swim, RunT: RunningTimer, YPFB: YPosFractionalBehavior, ScrP: ScrollPos, Par: Parity, Plat: Platform, CoiH: CoinHandler, PowH: PowerupHandler, VerP: VerticalPipeHandler> {
@@ -69,6 +80,13 @@ impl PlayerSize for BigAfterPowerup {
   const MAY_BE_BIG: bool = true;
   fn is_big(s: &State) -> bool { s.powerup_collected }
 }
+#[allow(dead_code)]
+pub enum BigUntilInjured {}
+impl PlayerSize for BigUntilInjured {
+  const CROUCH_BITS: usize = 1;
+  const MAY_BE_BIG: bool = true;
+  fn is_big(s: &State) -> bool { !s.injured }
+}
 
 pub trait CoinHandler {
   const COIN_HANDLER_BITS: usize;
@@ -151,21 +169,101 @@ impl<X: Unsigned, Y: Unsigned> VerticalPipeHandler for EnterVerticalPipe<X, Y> {
   }
 }
 
+pub trait VineHandler {
+  const CLIMB_VINES: bool;
+  const CLIMB_BITS: usize;
+  const VINE_GROWTH_BITS: usize;
+  fn is_vine_at(&State, usize, usize) -> bool;
+  fn grow_vine(&mut State) -> ();
+}
+#[allow(dead_code)]
+pub enum StopAtVines {}
+impl VineHandler for StopAtVines {
+  const CLIMB_VINES: bool = false;
+  const CLIMB_BITS: usize = 0;
+  const VINE_GROWTH_BITS: usize = 0;
+  fn is_vine_at(_: &State, _: usize, _: usize) -> bool { false }
+  fn grow_vine(_: &mut State) -> () {}
+}
+#[allow(dead_code)]
+pub enum ClimbVines {}
+impl VineHandler for ClimbVines {
+  const CLIMB_VINES: bool = true;
+  const CLIMB_BITS: usize = 5;
+  const VINE_GROWTH_BITS: usize = 0;
+  fn is_vine_at(_: &State, _: usize, _: usize) -> bool { false }
+  fn grow_vine(_: &mut State) -> () {}
+}
+/// Vine growing out of the powerup block at (X, Y) once it is hit, one pixel on two of every four frames.
+#[allow(dead_code)]
+pub struct ClimbGrowingVine<X: Unsigned, Y: Unsigned> {
+  _x: PhantomData<X>,
+  _y: PhantomData<Y>,
+  _void: Void,
+}
+impl<X: Unsigned, Y: Unsigned> VineHandler for ClimbGrowingVine<X, Y> {
+  const CLIMB_VINES: bool = true;
+  const CLIMB_BITS: usize = 5;
+  const VINE_GROWTH_BITS: usize = 7;
+  fn is_vine_at(s: &State, cx: usize, cy: usize) -> bool {
+    cx == X::to_usize() && cy < Y::to_usize() && (s.vine_timer as usize >> 1) >= (Y::to_usize() - cy) << 4
+  }
+  fn grow_vine(s: &mut State) -> () {
+    if s.powerup_block_hit && s.vine_timer < 0x7f { s.vine_timer += 1; }
+  }
+}
+
+pub trait DamageHandler {
+  const DAMAGE_BITS: usize;
+  const TAKES_DAMAGE: bool; // big players shrink when injured instead of dying, requires Parity21 for the InjuryTimer
+}
+#[allow(dead_code)]
+pub enum NoDamage {}
+impl DamageHandler for NoDamage {
+  const DAMAGE_BITS: usize = 0;
+  const TAKES_DAMAGE: bool = false;
+}
+/// Injuries by the enemies of the EnemyHandler or by hazards shrink big players and make them invincible for a while.
+#[allow(dead_code)]
+pub enum TakeDamage {}
+impl DamageHandler for TakeDamage {
+  const DAMAGE_BITS: usize = 7;
+  const TAKES_DAMAGE: bool = true;
+}
+
 pub trait Swim {
-  const IS_SWIMMING: bool;
+  const MAY_SWIM: bool;
+  const ALWAYS_SWIMMING: bool;
   const SWIMMING_BITS: usize;
+  fn is_swimming(&State) -> bool;
 }
 #[allow(dead_code)]
 pub enum NotSwimming {}
 impl Swim for NotSwimming {
-  const IS_SWIMMING: bool = false;
+  const MAY_SWIM: bool = false;
+  const ALWAYS_SWIMMING: bool = false;
   const SWIMMING_BITS: usize = 0;
+  fn is_swimming(_: &State) -> bool { false }
 }
 #[allow(dead_code)]
 pub enum Swimming {}
 impl Swim for Swimming {
-  const IS_SWIMMING: bool = true;
+  const MAY_SWIM: bool = true;
+  const ALWAYS_SWIMMING: bool = true;
+  const SWIMMING_BITS: usize = 5;
+  fn is_swimming(_: &State) -> bool { true }
+}
+/// Swimming only in water area A of a multi-area search (e.g. the 8-4 water section), on land in all others.
+#[allow(dead_code)]
+pub struct SwimmingInArea<A: Unsigned> {
+  _a: PhantomData<A>,
+  _void: Void,
+}
+impl<A: Unsigned> Swim for SwimmingInArea<A> {
+  const MAY_SWIM: bool = true;
+  const ALWAYS_SWIMMING: bool = false;
   const SWIMMING_BITS: usize = 5;
+  fn is_swimming(s: &State) -> bool { s.area == A::to_u8() }
 }
 
 pub trait RunningTimer {
@@ -216,39 +314,99 @@ impl ScrollPos for WithScrollPos {
   const SCROLL_POS_BITS: usize = 12;
 }
 
+/// Areas in which the screen stops scrolling (ScrollLock), e.g. at warp zones or in front of Bowser.
+/// Only relevant when the scroll position is tracked.
+pub trait ScrollLock {
+  fn is_scroll_locked(screen_left_x_pos: i32) -> bool;
+}
+#[allow(dead_code)]
+pub enum NoScrollLock {}
+impl ScrollLock for NoScrollLock {
+  fn is_scroll_locked(_: i32) -> bool { false }
+}
+/// Scrolling stops once the left edge of the screen reached page P.
+#[allow(dead_code)]
+pub struct ScrollLockAtPage<P: Unsigned> {
+  _p: PhantomData<P>,
+  _void: Void,
+}
+impl<P: Unsigned> ScrollLock for ScrollLockAtPage<P> {
+  fn is_scroll_locked(screen_left_x_pos: i32) -> bool { screen_left_x_pos >= P::to_i32() << 8 }
+}
+
+/// Vertical position below which the player dies (EmuResult::Death).
+pub trait KillPlane {
+  fn kill_y_pos() -> i32;
+}
+/// Dies below the bottom of the screen, from where inputs are ignored and the player can't get back up.
+#[allow(dead_code)]
+pub enum BottomOfScreen {}
+impl KillPlane for BottomOfScreen {
+  fn kill_y_pos() -> i32 { ::emu::BOTTOM_OF_SCREEN_Y_POS }
+}
+/// Dies at pixel Y of the screen, e.g. U198 to stop earlier. Y is at most U208, the bottom of the screen, as
+/// CompressedState can't store the positions below it.
+#[allow(dead_code)]
+pub struct KillPlaneAt<Y: Unsigned> {
+  _y: PhantomData<Y>,
+  _void: Void,
+}
+impl<Y: Unsigned> KillPlane for KillPlaneAt<Y> {
+  fn kill_y_pos() -> i32 {
+    let kill_y_pos = 0x10000 + (Y::to_i32() << 8);
+    assert!(kill_y_pos <= ::emu::BOTTOM_OF_SCREEN_Y_POS, "kill plane at {:#x} is below the bottom of the screen", Y::to_i32());
+    kill_y_pos
+  }
+}
+
 pub trait Parity {
   const PARITY: u8;
   const PARITY_BITS: usize;
+  const HALTED_BY_TIMER_CONTROL: bool; // doesn't advance while an injury halts the timers
 }
 #[allow(dead_code)]
 pub enum NoParity {}
 impl Parity for NoParity {
   const PARITY: u8 = 1;
   const PARITY_BITS: usize = 0;
+  const HALTED_BY_TIMER_CONTROL: bool = false;
 }
 #[allow(dead_code)]
 pub enum Parity2 {}
 impl Parity for Parity2 {
   const PARITY: u8 = 2;
   const PARITY_BITS: usize = 1;
+  const HALTED_BY_TIMER_CONTROL: bool = false;
 }
 #[allow(dead_code)]
 pub enum Parity3 {}
 impl Parity for Parity3 {
   const PARITY: u8 = 3;
   const PARITY_BITS: usize = 2;
+  const HALTED_BY_TIMER_CONTROL: bool = false;
 }
 #[allow(dead_code)]
 pub enum Parity4 {}
 impl Parity for Parity4 {
   const PARITY: u8 = 4;
   const PARITY_BITS: usize = 2;
+  const HALTED_BY_TIMER_CONTROL: bool = false;
 }
 #[allow(dead_code)]
 pub enum Parity8 {}
 impl Parity for Parity8 {
   const PARITY: u8 = 8;
   const PARITY_BITS: usize = 3;
+  const HALTED_BY_TIMER_CONTROL: bool = false;
+}
+/// The interval timer period, i.e. the frame rule: parity is 0x14 - IntervalTimerControl, and wraps to 0 in the frames
+/// in which the interval timers are decremented.
+#[allow(dead_code)]
+pub enum Parity21 {}
+impl Parity for Parity21 {
+  const PARITY: u8 = 21;
+  const PARITY_BITS: usize = 5;
+  const HALTED_BY_TIMER_CONTROL: bool = true;
 }
 
 pub trait Platform {
@@ -366,3 +524,96 @@ impl Platform for PAL {
 
   const X_SPD_ABS_CUTOFFS: [u8; 6] = [0x00, 0x0d, 0x12, 0x1d, 0x20, 0x27];
 }
+
+pub trait AreaHandler {
+  const MULTI_AREA: bool;
+  const AREA_BITS: usize;
+  const AREA_CHANGE_TIMER_BITS: usize;
+}
+#[allow(dead_code)]
+pub enum SingleArea {}
+impl AreaHandler for SingleArea {
+  const MULTI_AREA: bool = false;
+  const AREA_BITS: usize = 0;
+  const AREA_CHANGE_TIMER_BITS: usize = 0;
+}
+/// Up to four areas connected by transitions, see ::multiarea::MultiAreaEmu.
+#[allow(dead_code)]
+pub enum MultiArea {}
+impl AreaHandler for MultiArea {
+  const MULTI_AREA: bool = true;
+  const AREA_BITS: usize = 2;
+  const AREA_CHANGE_TIMER_BITS: usize = 9;
+}
+
+/// Moving platforms of a case, see ::lift. Lifts are identified by their index, and positioned by
+/// State::object_frame, the number of frames objects moved since the start states.
+pub trait LiftHandler {
+  const LIFT_COUNT: usize;
+  const LIFT_FRAME_BITS: usize;
+  fn get_lift(&State, usize) -> Lift;
+}
+#[allow(dead_code)]
+pub enum NoLifts {}
+impl LiftHandler for NoLifts {
+  const LIFT_COUNT: usize = 0;
+  const LIFT_FRAME_BITS: usize = 0;
+  fn get_lift(_: &State, _: usize) -> Lift { panic!("no lifts"); }
+}
+
+pub trait JumpspringHandler {
+  const JUMPSPRINGS: bool;
+  const JUMPSPRING_BITS: usize;
+}
+/// Jumpspring metatiles are solid blocks.
+#[allow(dead_code)]
+pub enum NoJumpsprings {}
+impl JumpspringHandler for NoJumpsprings {
+  const JUMPSPRINGS: bool = false;
+  const JUMPSPRING_BITS: usize = 0;
+}
+/// Landing on a jumpspring metatile compresses it and bounces the player, higher when pressing A while it is pressed.
+/// The parity must follow FrameCounter (Parity4 or Parity8), which times the jumpspring animation.
+#[allow(dead_code)]
+pub enum Jumpsprings {}
+impl JumpspringHandler for Jumpsprings {
+  const JUMPSPRINGS: bool = true;
+  const JUMPSPRING_BITS: usize = 6; // includes an additional y_spd bit for the higher bounce speed
+}
+
+/// Hazards of a case (firebars, Podoboos and piranha plants, see ::hazard), positioned by State::object_frame.
+/// Touching them injures the player like an enemy.
+pub trait HazardHandler {
+  const HAZARDS: bool;
+  const HAZARD_FRAME_BITS: usize;
+  const PIRANHA_PLANT_BITS: usize;
+  const BOWSER_BITS: usize; // State::bowser_passed and State::bowser_pass_frame
+  fn is_touching_hazard(&State) -> bool;
+  /// Moves hazards depending on the player (piranha plants, Bowser), after the player moved in this frame.
+  fn move_hazards(&mut State) -> ();
+}
+#[allow(dead_code)]
+pub enum NoHazards {}
+impl HazardHandler for NoHazards {
+  const HAZARDS: bool = false;
+  const HAZARD_FRAME_BITS: usize = 0;
+  const PIRANHA_PLANT_BITS: usize = 0;
+  const BOWSER_BITS: usize = 0;
+  fn is_touching_hazard(_: &State) -> bool { false }
+  fn move_hazards(_: &mut State) -> () {}
+}
+
+/// Stompable enemies of a case (see ::enemy), positioned by State::object_frame. Stomped enemies are recorded in
+/// State::stomped_enemies and are harmless from then on; touching them otherwise injures the player.
+pub trait EnemyHandler {
+  const ENEMY_COUNT: usize; // at most 8, the bits of State::stomped_enemies
+  const ENEMY_FRAME_BITS: usize;
+  fn get_enemy(&State, usize) -> Enemy;
+}
+#[allow(dead_code)]
+pub enum NoEnemies {}
+impl EnemyHandler for NoEnemies {
+  const ENEMY_COUNT: usize = 0;
+  const ENEMY_FRAME_BITS: usize = 0;
+  fn get_enemy(_: &State, _: usize) -> Enemy { panic!("no enemies"); }
+}
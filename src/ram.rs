@@ -0,0 +1,483 @@
+use bowser::{Bowser, BowserPath, PseudoRandom};
+use hazard::{FIREBAR_FAST_SPIN_SPEED, FIREBAR_SLOW_SPIN_SPEED, Firebar, HazardPaths, PiranhaPlant, podoboo_jumps, podoboo_path};
+use levelend::GameTimer;
+use lift::{CASTLE_LARGE_LIFT, ConstantLift, LARGE_LIFT, LIFT_DOWN_Y_SPD, LIFT_UP_Y_SPD, LiftMotion, SMALL_LIFT, XMovingLift, YMovingLift};
+use options::{DamageHandler, Options, Parity, Platform, PlayerSize};
+use state::{Dir, PlayerState, State};
+use std::env;
+use std::fs::File;
+use std::io::{ErrorKind, Read};
+use std::path::PathBuf;
+
+const RAM_SIZE: usize = 0x800;
+// Environment variable with the directory of the RAM dump files, the working directory if not set
+const RAM_DIR_VAR: &str = "SMB_RAM_DIR";
+
+// RAM addresses of the player's variables
+const PLAYER_STATE: usize = 0x1d;
+const PLAYER_FACING_DIR: usize = 0x33;
+const PLAYER_MOVING_DIR: usize = 0x45;
+const PLAYER_X_SPEED: usize = 0x57;
+const PLAYER_PAGE_LOC: usize = 0x6d;
+const PLAYER_X_POSITION: usize = 0x86;
+const PLAYER_Y_SPEED: usize = 0x9f;
+const PLAYER_Y_HIGH_POS: usize = 0xb5;
+const PLAYER_Y_POSITION: usize = 0xce;
+const PLAYER_X_SUBPIXEL: usize = 0x400; // SprObject_X_MoveForce
+const PLAYER_Y_SUBPIXEL: usize = 0x416; // SprObject_YMF_Dummy
+const PLAYER_Y_MOVE_FORCE: usize = 0x433;
+const PLAYER_COLLISION_BITS: usize = 0x490;
+const PLAYER_X_SPEED_ABSOLUTE: usize = 0x700;
+const RUNNING_SPEED: usize = 0x703;
+const PLAYER_X_MOVE_FORCE: usize = 0x705;
+const VERTICAL_FORCE: usize = 0x709;
+const VERTICAL_FORCE_DOWN: usize = 0x70a;
+const CROUCHING_FLAG: usize = 0x714;
+const SCREEN_LEFT_X_POS: usize = 0x71c;
+const PLAYER_SIZE: usize = 0x754;
+const JUMP_SWIM_TIMER: usize = 0x782;
+const RUNNING_TIMER: usize = 0x783;
+const SIDE_COLLISION_TIMER: usize = 0x785;
+const INJURY_TIMER: usize = 0x79e;
+
+// RAM addresses of the enemy objects, indexed by enemy slot
+const ENEMY_SLOTS: usize = 5;
+const ENEMY_FLAG: usize = 0x0f;
+const ENEMY_ID: usize = 0x16;
+const ENEMY_X_SPEED: usize = 0x58; // XMoveSecondaryCounter and YPlatformCenterYPos for lifts
+const ENEMY_PAGE_LOC: usize = 0x6e;
+const ENEMY_X_POSITION: usize = 0x87;
+const ENEMY_Y_SPEED: usize = 0xa0; // XMovePrimaryCounter for horizontal lifts
+const ENEMY_Y_HIGH_POS: usize = 0xb6;
+const ENEMY_Y_POSITION: usize = 0xcf;
+const ENEMY_X_SUBPIXEL: usize = 0x401; // Enemy_X_MoveForce, YPlatformTopYPos for vertical lifts
+const ENEMY_Y_SUBPIXEL: usize = 0x417; // Enemy_YMF_Dummy
+const ENEMY_Y_MOVE_FORCE: usize = 0x434;
+const ENEMY_INTERVAL_TIMER: usize = 0x796;
+const FIREBAR_SPIN_DIRECTION: usize = 0x34;
+const FIREBAR_SPIN_STATE_LOW: usize = ENEMY_X_SPEED;
+const FIREBAR_SPIN_STATE_HIGH: usize = ENEMY_Y_SPEED;
+const FIREBAR_SPIN_SPEED: usize = 0x388;
+const PSEUDO_RANDOM_BIT_REG: usize = 0x7a7;
+const PIRANHA_PLANT_Y_SPEED: usize = ENEMY_X_SPEED;
+const PIRANHA_PLANT_MOVE_FLAG: usize = ENEMY_Y_SPEED;
+const PIRANHA_PLANT_DOWN_Y_POS: usize = ENEMY_Y_MOVE_FORCE;
+const ENEMY_FRAME_TIMER: usize = 0x78a;
+const ENEMY_MOVING_DIR: usize = 0x46;
+const ENEMY_FRENZY_BUFFER: usize = 0x6cb;
+const BOWSER_BODY_CONTROLS: usize = 0x363; // bit 7 is Bowser's open mouth
+const BOWSER_MOVEMENT_SPEED: usize = 0x365;
+const BOWSER_ORIG_X_POS: usize = 0x366;
+const BOWSER_FLAME_TIMER_CTRL: usize = 0x367;
+const BOWSER_FIRE_BREATH_TIMER: usize = 0x790;
+const MAX_RANGE_FROM_ORIGIN: usize = 0x6dc;
+const WORLD_NUMBER: usize = 0x75f;
+const FRAME_COUNTER: usize = 0x09;
+const AREA_TYPE: usize = 0x74e;
+const SECONDARY_HARD_MODE: usize = 0x6cc;
+
+// RAM addresses of the timers
+const INTERVAL_TIMER_CONTROL: usize = 0x77f;
+const GAME_TIMER_CTRL_TIMER: usize = 0x787;
+const GAME_TIMER_DISPLAY: usize = 0x7f8; // three decimal digits
+
+/// Whether v_force is one of the values Platform::get_v_force_index accepts.
+fn is_known_v_force<P: Platform>(v_force: u8) -> bool {
+  [P::V_FORCE_SWIM_TOO_HIGH, P::V_FORCE_AREA_INIT,
+      P::V_FORCE_JUMP_STANDING, P::V_FORCE_JUMP_WALKING, P::V_FORCE_JUMP_RUNNING, P::V_FORCE_JUMP_SWIMMING,
+      P::V_FORCE_FALL_STANDING, P::V_FORCE_FALL_WALKING, P::V_FORCE_FALL_RUNNING, P::V_FORCE_FALL_SWIMMING].contains(&v_force)
+}
+
+/// Player State from a 2KB NES RAM dump, e.g. to use as a start state, and warnings about the values in it the search
+/// can't handle. Fields without a RAM equivalent (areas, lifts and hazards) start out as 0, and the parity only follows
+/// IntervalTimerControl with Parity21. PlayerSize sets both injured and powerup_collected, so that O::PlayerSize sees
+/// the size of the dump.
+pub fn state_from_ram<O: Options>(ram: &[u8]) -> (State, Vec<String>) {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  let player_state = match ram[PLAYER_STATE] {
+    0 => PlayerState::STANDING,
+    1 => PlayerState::JUMPING,
+    2 => PlayerState::FALLING,
+    3 => PlayerState::CLIMBING,
+    v => panic!("unexpected Player_State value {}", v),
+  };
+  let big = ram[PLAYER_SIZE] == 0;
+  let s = State {
+    x_pos: (ram[PLAYER_PAGE_LOC] as i32) << 16 | (ram[PLAYER_X_POSITION] as i32) << 8 | ram[PLAYER_X_SUBPIXEL] as i32,
+    y_pos: (ram[PLAYER_Y_HIGH_POS] as i32) << 16 | (ram[PLAYER_Y_POSITION] as i32) << 8 | ram[PLAYER_Y_SUBPIXEL] as i32,
+    x_spd: ((ram[PLAYER_X_SPEED] as u16) << 8 | ram[PLAYER_X_MOVE_FORCE] as u16) as i16,
+    y_spd: ((ram[PLAYER_Y_SPEED] as u16) << 8 | ram[PLAYER_Y_MOVE_FORCE] as u16) as i16,
+    player_state,
+    moving_dir: Dir::from_bits_truncate(ram[PLAYER_MOVING_DIR]),
+    facing_dir: Dir::from_bits_truncate(ram[PLAYER_FACING_DIR]),
+    v_force: ram[VERTICAL_FORCE],
+    v_force_down: ram[VERTICAL_FORCE_DOWN],
+    x_spd_abs: ram[PLAYER_X_SPEED_ABSOLUTE],
+    running_speed: ram[RUNNING_SPEED] != 0,
+    collision_bits: Dir::from_bits_truncate(ram[PLAYER_COLLISION_BITS]),
+    is_crouching: ram[CROUCHING_FLAG] != 0,
+    jump_swim_timer: ram[JUMP_SWIM_TIMER],
+    running_timer: ram[RUNNING_TIMER],
+    left_screen_edge_pos: ram[SCREEN_LEFT_X_POS],
+    side_collision_timer: ram[SIDE_COLLISION_TIMER],
+    collected_coins: 0,
+    powerup_block_hit: false,
+    powerup_collected: big,
+    parity: if O::Parity::PARITY == 21 { 0x14 - ram[INTERVAL_TIMER_CONTROL] } else { ram[FRAME_COUNTER].wrapping_add(1) % O::Parity::PARITY }, // FrameCounter of the next frame
+    injured: !big,
+    injury_timer: ram[INJURY_TIMER],
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
+  };
+  let mut warnings = vec![];
+  if !is_known_v_force::<O::Platform>(s.v_force) { warnings.push(format!("VerticalForce {:#x} is not supported by the platform", s.v_force)); }
+  if !is_known_v_force::<O::Platform>(s.v_force_down) { warnings.push(format!("VerticalForceDown {:#x} is not supported by the platform", s.v_force_down)); }
+  if s.moving_dir.is_empty() || s.facing_dir.is_empty() { warnings.push(format!("unexpected Player_MovingDir {} or PlayerFacingDir {}", ram[PLAYER_MOVING_DIR], ram[PLAYER_FACING_DIR])); }
+  if O::PlayerSize::is_big(&s) != big { warnings.push(format!("player is {}, but the options make it {}", if big { "big" } else { "small" }, if big { "small" } else { "big" })); }
+  if s.injury_timer > 0 && !O::DamageHandler::TAKES_DAMAGE { warnings.push(format!("InjuryTimer is {}, injuries are only modeled with a DamageHandler", s.injury_timer)); }
+  (s, warnings)
+}
+
+/// Game timer from a 2KB NES RAM dump.
+pub fn game_timer_from_ram(ram: &[u8]) -> GameTimer {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  let digits = &ram[GAME_TIMER_DISPLAY..GAME_TIMER_DISPLAY + 3];
+  assert!(digits.iter().all(|&d| d < 10), "unexpected GameTimerDisplay {:?}", digits);
+  GameTimer {
+    display: digits.iter().fold(0, |display, &d| display * 10 + d as u16),
+    ctrl_timer: ram[GAME_TIMER_CTRL_TIMER],
+  }
+}
+
+/// Lifts in the enemy slots of a 2KB NES RAM dump, in slot order, and the FrameCounter value of the frame after the
+/// dump, in which they move next. Lifts moving depending on the player (balance, falling and right moving lifts) have
+/// no path and are rejected.
+pub fn lifts_from_ram(ram: &[u8]) -> (Vec<Box<dyn LiftMotion>>, u8) {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  let large_lift = if ram[AREA_TYPE] == 3 || ram[SECONDARY_HARD_MODE] != 0 { CASTLE_LARGE_LIFT } else { LARGE_LIFT };
+  let lifts = (0..ENEMY_SLOTS).filter(|&i| ram[ENEMY_FLAG + i] != 0).filter_map(|i| {
+    let x_pos = (ram[ENEMY_PAGE_LOC + i] as i32) << 16 | (ram[ENEMY_X_POSITION + i] as i32) << 8 | ram[ENEMY_X_SUBPIXEL + i] as i32;
+    let y_pos = (ram[ENEMY_Y_HIGH_POS + i] as i32) << 16 | (ram[ENEMY_Y_POSITION + i] as i32) << 8 | ram[ENEMY_Y_SUBPIXEL + i] as i32;
+    let y_spd = ((ram[ENEMY_Y_SPEED + i] as u16) << 8 | ram[ENEMY_Y_MOVE_FORCE + i] as u16) as i16;
+    let motion: Box<dyn LiftMotion> = match ram[ENEMY_ID + i] {
+      0x25 => Box::new(YMovingLift { x_pos: x_pos & !0xff, y_pos, y_spd, top_y_pos: ram[ENEMY_X_SUBPIXEL + i] as i32,
+          center_y_pos: ram[ENEMY_X_SPEED + i] as i32, bounding_box: large_lift }),
+      0x26 | 0x27 | 0x2b | 0x2c => {
+        let id = ram[ENEMY_ID + i];
+        let y_spd = if id == 0x26 || id == 0x2b { LIFT_UP_Y_SPD } else { LIFT_DOWN_Y_SPD };
+        Box::new(ConstantLift { x_pos, y_pos, y_spd, bounding_box: if id >= 0x2b { SMALL_LIFT } else { large_lift } })
+      },
+      0x28 => Box::new(XMovingLift { x_pos, y_pos, primary_counter: ram[ENEMY_Y_SPEED + i],
+          secondary_counter: ram[ENEMY_X_SPEED + i], bounding_box: large_lift }),
+      0x24 | 0x29 | 0x2a => panic!("lift {:#04x} in enemy slot {} moves depending on the player", ram[ENEMY_ID + i], i),
+      _ => return None,
+    };
+    Some(motion)
+  }).collect();
+  (lifts, ram[FRAME_COUNTER].wrapping_add(1))
+}
+
+/// Firebars and Podoboos in the enemy slots of a 2KB NES RAM dump, with their paths over the given number of frames
+/// after the dump. Hazards spawning later are not included.
+pub fn hazard_paths_from_ram(ram: &[u8], frames: usize) -> HazardPaths {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  let mut prng = [0; 7];
+  prng.copy_from_slice(&ram[PSEUDO_RANDOM_BIT_REG..PSEUDO_RANDOM_BIT_REG + 7]);
+  let mut hazards = HazardPaths { frames, firebars: vec![], podoboos: vec![] };
+  for i in (0..ENEMY_SLOTS).filter(|&i| ram[ENEMY_FLAG + i] != 0) {
+    let x_pos = (ram[ENEMY_PAGE_LOC + i] as i32) << 8 | ram[ENEMY_X_POSITION + i] as i32;
+    match ram[ENEMY_ID + i] {
+      0x0c => { // Podoboo
+        let y_pos = (ram[ENEMY_Y_HIGH_POS + i] as i32) << 16 | (ram[ENEMY_Y_POSITION + i] as i32) << 8 | ram[ENEMY_Y_SUBPIXEL + i] as i32;
+        let y_spd = ((ram[ENEMY_Y_SPEED + i] as u16) << 8 | ram[ENEMY_Y_MOVE_FORCE + i] as u16) as i16;
+        let jumps = podoboo_jumps(PseudoRandom(prng), ram[INTERVAL_TIMER_CONTROL], ram[ENEMY_INTERVAL_TIMER + i], i, frames);
+        hazards.podoboos.push((x_pos << 8, podoboo_path(y_pos, y_spd, &jumps, frames)));
+      },
+      0x1b ..= 0x1f => { // firebars, 0x1f being the long one
+        let spin_speed = ram[FIREBAR_SPIN_SPEED + i];
+        assert!(spin_speed == FIREBAR_SLOW_SPIN_SPEED || spin_speed == FIREBAR_FAST_SPIN_SPEED, "unexpected FirebarSpinSpeed {:#x} in enemy slot {}", spin_speed, i);
+        let firebar = Firebar {
+          x_pos,
+          y_pos: ram[ENEMY_Y_POSITION + i] as i32,
+          long: ram[ENEMY_ID + i] == 0x1f,
+          spin_speed,
+          counter_clockwise: ram[FIREBAR_SPIN_DIRECTION + i] != 0,
+          spin_state: (ram[FIREBAR_SPIN_STATE_HIGH + i] as u16) << 8 | ram[FIREBAR_SPIN_STATE_LOW + i] as u16,
+        };
+        let spin_states = firebar.spin_states(frames);
+        hazards.firebars.push((firebar, spin_states));
+      },
+      _ => {},
+    }
+  }
+  hazards
+}
+
+/// Enemy slot of the piranha plant in a 2KB NES RAM dump.
+fn piranha_plant_slot(ram: &[u8]) -> usize {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  (0..ENEMY_SLOTS).find(|&i| ram[ENEMY_FLAG + i] != 0 && ram[ENEMY_ID + i] == 0x0d).unwrap_or_else(|| panic!("no piranha plant in RAM dump"))
+}
+
+/// Piranha plant in the enemy slots of a 2KB NES RAM dump.
+pub fn piranha_plant_from_ram(ram: &[u8]) -> PiranhaPlant {
+  let i = piranha_plant_slot(ram);
+  PiranhaPlant { x_pos: (ram[ENEMY_PAGE_LOC + i] as i32) << 8 | ram[ENEMY_X_POSITION + i] as i32, y_pos: ram[PIRANHA_PLANT_DOWN_Y_POS + i] as i32 }
+}
+
+/// s with the movement of the piranha plant in a 2KB NES RAM dump taken in the frame of s.
+pub fn with_piranha_plant_from_ram(ram: &[u8], s: State) -> State {
+  let i = piranha_plant_slot(ram);
+  assert!(ram[ENEMY_Y_HIGH_POS + i] == 1 && ram[ENEMY_Y_POSITION + i] <= ram[PIRANHA_PLANT_DOWN_Y_POS + i], "piranha plant below its pipe");
+  State {
+    piranha_plant_y_pos: ram[PIRANHA_PLANT_DOWN_Y_POS + i] - ram[ENEMY_Y_POSITION + i],
+    piranha_plant_timer: ram[ENEMY_FRAME_TIMER + i],
+    piranha_plant_rising: ram[PIRANHA_PLANT_Y_SPEED + i] >= 0x80,
+    piranha_plant_moving: ram[PIRANHA_PLANT_MOVE_FLAG + i] != 0,
+    ..s
+  }
+}
+
+/// Bowser in the enemy slots of a 2KB NES RAM dump, with his path over the given number of frames after the dump. His
+/// flames spawn in the first free enemy slot. Flames already breathed are not modeled and are rejected.
+pub fn bowser_path_from_ram(ram: &[u8], frames: usize) -> BowserPath {
+  assert!(ram.len() == RAM_SIZE, "RAM dump has {} bytes instead of {}", ram.len(), RAM_SIZE);
+  let i = (0..ENEMY_SLOTS).find(|&i| ram[ENEMY_FLAG + i] != 0 && ram[ENEMY_ID + i] == 0x2d).unwrap_or_else(|| panic!("no Bowser in RAM dump"));
+  if let Some(j) = (0..ENEMY_SLOTS).find(|&j| ram[ENEMY_FLAG + j] != 0 && ram[ENEMY_ID + j] == 0x15) { panic!("Bowser flame in enemy slot {} is not modeled", j); }
+  assert!(ram[ENEMY_FRENZY_BUFFER] != 0x15, "Bowser flame about to spawn is not modeled");
+  assert!(ram[ENEMY_Y_HIGH_POS + i] == 1, "Bowser is off screen");
+  let flame_slot = (0..ENEMY_SLOTS).find(|&j| ram[ENEMY_FLAG + j] == 0).unwrap_or_else(|| panic!("no free enemy slot for Bowser's flames"));
+  let mut prng = [0; 7];
+  prng.copy_from_slice(&ram[PSEUDO_RANDOM_BIT_REG..PSEUDO_RANDOM_BIT_REG + 7]);
+  let bowser = Bowser {
+    x_pos: (ram[ENEMY_PAGE_LOC + i] as i32) << 8 | ram[ENEMY_X_POSITION + i] as i32,
+    y_pos: (ram[ENEMY_Y_POSITION + i] as i32) << 8 | ram[ENEMY_Y_SUBPIXEL + i] as i32,
+    y_spd: ((ram[ENEMY_Y_SPEED + i] as u16) << 8 | ram[ENEMY_Y_MOVE_FORCE + i] as u16) as i16,
+    orig_x_pos: ram[BOWSER_ORIG_X_POS],
+    moving_dir: ram[ENEMY_MOVING_DIR + i],
+    movement_speed: ram[BOWSER_MOVEMENT_SPEED],
+    max_range: ram[MAX_RANGE_FROM_ORIGIN],
+    frame_timer: ram[ENEMY_FRAME_TIMER + i],
+    fire_breath_timer: ram[BOWSER_FIRE_BREATH_TIMER],
+    mouth_open: ram[BOWSER_BODY_CONTROLS] & 0x80 != 0,
+    flame_timer_ctrl: ram[BOWSER_FLAME_TIMER_CTRL],
+    breathes_fire: ram[WORLD_NUMBER] < 5 || ram[WORLD_NUMBER] == 7,
+    secondary_hard_mode: ram[SECONDARY_HARD_MODE] != 0,
+    slot: i,
+    flame_slot,
+  };
+  bowser.path(PseudoRandom(prng), ram[FRAME_COUNTER], frames)
+}
+
+fn ram_path(name: &str) -> PathBuf {
+  env::var_os(RAM_DIR_VAR).map_or_else(PathBuf::new, PathBuf::from).join(name)
+}
+
+/// Contents of the RAM dump file with the given name in the RAM dump directory, None if there is no such file.
+pub fn try_load_ram(name: &str) -> Option<Vec<u8>> {
+  let path = ram_path(name);
+  let mut ram = vec![];
+  match File::open(&path).and_then(|mut f| f.read_to_end(&mut ram)) {
+    Ok(_) => Some(ram),
+    Err(ref e) if e.kind() == ErrorKind::NotFound => None,
+    Err(e) => panic!("can't read {}: {}", path.display(), e),
+  }
+}
+
+/// Contents of the RAM dump file with the given name in the RAM dump directory.
+pub fn load_ram(name: &str) -> Vec<u8> {
+  try_load_ram(name).unwrap_or_else(|| panic!("RAM dump {} not found, set {} to the directory containing it", ram_path(name).display(), RAM_DIR_VAR))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use case::w11::W11Flag;
+  use case::w84::W84Part2VertPipeEntry;
+  use options::{BigAfterPowerup, BigUntilInjured, NTSC};
+
+  /// RAM with an object of the given id in enemy slot 1, at x 0x234 and y 0x190.
+  fn ram_with_object(id: u8) -> Vec<u8> {
+    let mut ram = vec![0; RAM_SIZE];
+    ram[FRAME_COUNTER] = 0xff;
+    ram[ENEMY_FLAG + 1] = 1;
+    ram[ENEMY_ID + 1] = id;
+    ram[ENEMY_PAGE_LOC + 1] = 2;
+    ram[ENEMY_X_POSITION + 1] = 0x34;
+    ram[ENEMY_Y_HIGH_POS + 1] = 1;
+    ram[ENEMY_Y_POSITION + 1] = 0x90;
+    // an inactive lift in slot 3
+    ram[ENEMY_ID + 3] = 0x26;
+    ram
+  }
+
+  /// RAM with a small player jumping right at x 0x123456 and y 0x19abc, with IntervalTimerControl 0x11.
+  fn ram_with_player() -> Vec<u8> {
+    let mut ram = vec![0; RAM_SIZE];
+    ram[PLAYER_STATE] = 1;
+    ram[PLAYER_PAGE_LOC] = 0x12;
+    ram[PLAYER_X_POSITION] = 0x34;
+    ram[PLAYER_X_SUBPIXEL] = 0x56;
+    ram[PLAYER_Y_HIGH_POS] = 1;
+    ram[PLAYER_Y_POSITION] = 0x9a;
+    ram[PLAYER_Y_SUBPIXEL] = 0xbc;
+    ram[PLAYER_X_SPEED] = 0x18;
+    ram[PLAYER_X_MOVE_FORCE] = 0x40;
+    ram[PLAYER_Y_SPEED] = 0xfb;
+    ram[PLAYER_Y_MOVE_FORCE] = 0x20;
+    ram[PLAYER_MOVING_DIR] = 1;
+    ram[PLAYER_FACING_DIR] = 2;
+    ram[VERTICAL_FORCE] = <NTSC as Platform>::V_FORCE_JUMP_RUNNING;
+    ram[VERTICAL_FORCE_DOWN] = <NTSC as Platform>::V_FORCE_FALL_RUNNING;
+    ram[PLAYER_SIZE] = 1;
+    ram[INTERVAL_TIMER_CONTROL] = 0x11;
+    ram[FRAME_COUNTER] = 0x2a;
+    ram
+  }
+
+  #[test]
+  fn state_from_ram_reads_player() {
+    let (s, warnings) = state_from_ram::<W11Flag>(&ram_with_player());
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!((s.x_pos, s.y_pos, s.x_spd, s.y_spd), (0x123456, 0x19abc, 0x1840, -0x4e0));
+    assert_eq!((s.player_state, s.moving_dir, s.facing_dir), (PlayerState::JUMPING, Dir::RIGHT, Dir::LEFT));
+    assert_eq!((s.v_force, s.v_force_down), (<NTSC as Platform>::V_FORCE_JUMP_RUNNING, <NTSC as Platform>::V_FORCE_FALL_RUNNING));
+    assert_eq!((s.injured, s.powerup_collected), (true, false));
+    // Parity21 follows IntervalTimerControl
+    assert_eq!(s.parity, 3);
+  }
+
+  #[test]
+  fn state_from_ram_maps_player_size() {
+    // Parity2 follows the FrameCounter of the next frame
+    let (s, warnings) = state_from_ram::<W84Part2VertPipeEntry>(&ram_with_player());
+    assert_eq!(s.parity, 1);
+    assert_eq!(warnings, vec!["player is small, but the options make it big".to_string()]);
+
+    let mut ram = ram_with_player();
+    ram[PLAYER_SIZE] = 0;
+    let (s, warnings) = state_from_ram::<W84Part2VertPipeEntry>(&ram);
+    assert!(warnings.is_empty(), "{:?}", warnings);
+    assert_eq!((s.injured, s.powerup_collected), (false, true));
+    assert!(BigAfterPowerup::is_big(&s) && BigUntilInjured::is_big(&s));
+    let (_, warnings) = state_from_ram::<W11Flag>(&ram);
+    assert_eq!(warnings, vec!["player is big, but the options make it small".to_string()]);
+  }
+
+  #[test]
+  fn lifts_from_ram_reads_active_slots() {
+    let (lifts, frame_counter) = lifts_from_ram(&ram_with_object(0x27));
+    assert_eq!(frame_counter, 0);
+    assert_eq!(lifts.len(), 1);
+    let lift = lifts[0].lift();
+    assert_eq!((lift.x_pos, lift.y_pos, lift.bounding_box), (0x23400, 0x19000, LARGE_LIFT));
+    let mut motion = lifts.into_iter().next().unwrap();
+    motion.next_frame(frame_counter);
+    assert_eq!(motion.lift().y_pos, 0x19000 + LIFT_DOWN_Y_SPD as i32);
+
+    let (lifts, _) = lifts_from_ram(&ram_with_object(0x2b));
+    assert_eq!(lifts[0].lift().bounding_box, SMALL_LIFT);
+
+    let mut ram = ram_with_object(0x26);
+    ram[AREA_TYPE] = 3;
+    let (lifts, _) = lifts_from_ram(&ram);
+    assert_eq!(lifts[0].lift().bounding_box, CASTLE_LARGE_LIFT);
+
+    // enemies are no lifts
+    let (lifts, _) = lifts_from_ram(&ram_with_object(0x06));
+    assert!(lifts.is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "moves depending on the player")]
+  fn lifts_from_ram_rejects_balance_lifts() {
+    lifts_from_ram(&ram_with_object(0x24));
+  }
+
+  #[test]
+  fn hazard_paths_from_ram_reads_firebars_and_podoboos() {
+    let mut ram = ram_with_object(0x1f);
+    ram[FIREBAR_SPIN_DIRECTION + 1] = 1;
+    ram[FIREBAR_SPIN_SPEED + 1] = 0x28;
+    ram[FIREBAR_SPIN_STATE_HIGH + 1] = 0x03;
+    ram[FIREBAR_SPIN_STATE_LOW + 1] = 0x80;
+    let hazards = hazard_paths_from_ram(&ram, 0x10);
+    assert_eq!(hazards.frames, 0x10);
+    assert!(hazards.podoboos.is_empty());
+    let (ref firebar, ref spin_states) = hazards.firebars[0];
+    assert_eq!((firebar.x_pos, firebar.y_pos, firebar.long, firebar.counter_clockwise), (0x234, 0x90, true, true));
+    assert_eq!((firebar.spin_speed, firebar.spin_state), (0x28, 0x380));
+    assert_eq!(spin_states.len(), 0x11);
+
+    let mut ram = ram_with_object(0x0c);
+    ram[ENEMY_INTERVAL_TIMER + 1] = 5;
+    let hazards = hazard_paths_from_ram(&ram, 0x10);
+    assert!(hazards.firebars.is_empty());
+    let (x_pos, ref path) = hazards.podoboos[0];
+    assert_eq!(x_pos, 0x23400);
+    assert_eq!(path.len(), 0x11);
+    assert_eq!(path[0], 0x19000);
+    // falls until its timer runs out
+    assert!(path.windows(2).all(|w| w[1] >= w[0]));
+
+    // other enemies are no hazards
+    let hazards = hazard_paths_from_ram(&ram_with_object(0x06), 0x10);
+    assert!(hazards.firebars.is_empty() && hazards.podoboos.is_empty());
+  }
+
+  #[test]
+  fn piranha_plant_from_ram_reads_rising_plant() {
+    let mut ram = ram_with_object(0x0d);
+    ram[PIRANHA_PLANT_DOWN_Y_POS + 1] = 0x98;
+    ram[PIRANHA_PLANT_Y_SPEED + 1] = 0xff;
+    ram[PIRANHA_PLANT_MOVE_FLAG + 1] = 1;
+    let plant = piranha_plant_from_ram(&ram);
+    assert_eq!((plant.x_pos, plant.y_pos), (0x234, 0x98));
+    let s = with_piranha_plant_from_ram(&ram, ::transition::entrance_state::<::case::w13::W13Lift>(0x4000, 0x1b000, PlayerState::STANDING));
+    assert_eq!((s.piranha_plant_y_pos, s.piranha_plant_timer, s.piranha_plant_rising, s.piranha_plant_moving), (8, 0, true, true));
+  }
+
+  #[test]
+  #[should_panic(expected = "no piranha plant")]
+  fn piranha_plant_from_ram_needs_a_plant() {
+    piranha_plant_from_ram(&ram_with_object(0x0c));
+  }
+
+  #[test]
+  fn bowser_path_from_ram_matches_initialized_bowser() {
+    let mut ram = ram_with_object(0x2d);
+    ram[ENEMY_MOVING_DIR + 1] = 0xdf;
+    ram[ENEMY_FRAME_TIMER + 1] = 0x20;
+    ram[BOWSER_MOVEMENT_SPEED] = 0x02;
+    ram[BOWSER_ORIG_X_POS] = 0x34;
+    ram[BOWSER_FIRE_BREATH_TIMER] = 0xdf;
+    ram[PSEUDO_RANDOM_BIT_REG] = 0xa5;
+    let path = bowser_path_from_ram(&ram, 0x100);
+    let expected = Bowser::new(0x234, 0x90, 1, 0).path(PseudoRandom([0xa5, 0, 0, 0, 0, 0, 0]), 0xff, 0x100);
+    assert_eq!(path.len(), 0x100);
+    assert_eq!(path.frame_counter, 0xff);
+    let positions = |path: &BowserPath| path.frames.iter().map(|f| (f.x_pos, f.y_pos, f.flames.len())).collect::<Vec<_>>();
+    assert_eq!(positions(&path), positions(&expected));
+  }
+
+  #[test]
+  #[should_panic(expected = "Bowser flame in enemy slot 3")]
+  fn bowser_path_from_ram_rejects_flames() {
+    let mut ram = ram_with_object(0x2d);
+    ram[ENEMY_FLAG + 3] = 1;
+    ram[ENEMY_ID + 3] = 0x15;
+    bowser_path_from_ram(&ram, 0x10);
+  }
+}
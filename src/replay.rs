@@ -0,0 +1,33 @@
+use emu::{Emu, EmuResult, Input};
+use state::State;
+
+/// State and EmuResult after every frame of replaying the inputs from s with the emulator, e.g. inputs of an existing
+/// movie. The trace ends with the first step that is not successful, since the emulator can't continue from there.
+pub fn trace<E: Emu>(emu: &E, mut s: State, inputs: &[Input]) -> Vec<(State, EmuResult)> {
+  let mut trace = vec![];
+  for &input in inputs {
+    let (new_state, emu_result) = emu.run_step(s, input);
+    s = new_state.clone();
+    let success = emu_result == EmuResult::Success;
+    trace.push((new_state, emu_result));
+    if !success { break; }
+  }
+  trace
+}
+
+/// Prints a trace, with the frame numbers offset by first_frame (e.g. the movie frame of the first input).
+pub fn print_trace(trace: &[(State, EmuResult)], inputs: &[Input], first_frame: usize) -> () {
+  for (i, &(ref s, ref emu_result)) in trace.iter().enumerate() {
+    println!("frame {} input {}: {:?} {}", first_frame + i, inputs[i], emu_result, s);
+  }
+  if trace.len() < inputs.len() { println!("replay stopped after {} of {} inputs", trace.len(), inputs.len()); }
+}
+
+/// Replays the given frames of an .fm2 movie from s and prints the trace, to cross-check the emulator against runs
+/// known to sync.
+pub fn replay_fm2<E: Emu>(emu: &E, s: State, path: &str, first_frame: usize, last_frame: usize) -> Vec<(State, EmuResult)> {
+  let inputs = ::movie::fm2::load_inputs(path, first_frame..last_frame + 1);
+  let trace = trace(emu, s, &inputs);
+  print_trace(&trace, &inputs, first_frame);
+  trace
+}
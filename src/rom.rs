@@ -0,0 +1,109 @@
+use std::env;
+use std::fs::File;
+use std::io::Read;
+
+// Environment variable with the path of the user's ROM
+const ROM_PATH_VAR: &str = "SMB_ROM";
+
+const INES_HEADER_SIZE: usize = 0x10;
+const PRG_ROM_SIZE: usize = 0x8000;
+const PRG_ROM_START: u16 = 0x8000;
+
+// WorldAddrOffsets and the first entries of AreaAddrOffsets following it, used to find the area pointer tables
+const WORLD_ADDR_OFFSETS: [u8; 8] = [0x00, 0x05, 0x0a, 0x0e, 0x13, 0x17, 0x1b, 0x20];
+const WORLD1_AREAS: [u8; 5] = [0x25, 0x29, 0xc0, 0x26, 0x60];
+const NUM_AREAS: usize = 0x24;
+const NUM_AREA_DATA_ADDRS: usize = 0x22;
+// Offsets of the tables following WorldAddrOffsets
+const AREA_ADDR_OFFSETS: usize = 0x08;
+const ENEMY_ADDR_H_OFFSETS: usize = AREA_ADDR_OFFSETS + NUM_AREAS;
+const ENEMY_DATA_ADDR_LOW: usize = ENEMY_ADDR_H_OFFSETS + 4;
+const ENEMY_DATA_ADDR_HIGH: usize = ENEMY_DATA_ADDR_LOW + NUM_AREA_DATA_ADDRS;
+const AREA_DATA_H_OFFSETS: usize = ENEMY_DATA_ADDR_HIGH + NUM_AREA_DATA_ADDRS;
+const AREA_DATA_ADDR_LOW: usize = AREA_DATA_H_OFFSETS + 4;
+const AREA_DATA_ADDR_HIGH: usize = AREA_DATA_ADDR_LOW + NUM_AREA_DATA_ADDRS;
+
+/// Area type, from bits 5-6 of the area pointer.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum AreaType {
+  Water,
+  Ground,
+  Underground,
+  Castle,
+}
+impl AreaType {
+  pub fn from_area_pointer(area_pointer: u8) -> AreaType {
+    AreaType::from_index((area_pointer >> 5) & 0x3)
+  }
+  /// Area type of an AreaType value.
+  pub fn from_index(index: u8) -> AreaType {
+    match index & 0x3 {
+      0 => AreaType::Water,
+      1 => AreaType::Ground,
+      2 => AreaType::Underground,
+      _ => AreaType::Castle,
+    }
+  }
+  pub fn index(self) -> usize {
+    self as usize
+  }
+}
+
+/// Path of the user's ROM, from the SMB_ROM environment variable.
+pub fn rom_path() -> String {
+  env::var(ROM_PATH_VAR).unwrap_or_else(|_| panic!("{} is not set, set it to the path of a Super Mario Bros. ROM", ROM_PATH_VAR))
+}
+
+/// Super Mario Bros. ROM in iNES format, supplied by the user.
+pub struct Rom {
+  prg: Vec<u8>,
+  tables: usize, // PRG offset of WorldAddrOffsets
+}
+impl Rom {
+  pub fn load(path: &str) -> Rom {
+    let mut data = vec![];
+    File::open(path).and_then(|mut f| f.read_to_end(&mut data)).unwrap_or_else(|e| panic!("can't read {}: {}", path, e));
+    Rom::from_ines(&data)
+  }
+  pub fn from_ines(data: &[u8]) -> Rom {
+    assert!(data.len() >= INES_HEADER_SIZE + PRG_ROM_SIZE && &data[0..4] == b"NES\x1a", "not an iNES ROM with 32KB of PRG ROM");
+    let prg = data[INES_HEADER_SIZE..INES_HEADER_SIZE + PRG_ROM_SIZE].to_vec();
+    let tables = (0..PRG_ROM_SIZE - AREA_DATA_ADDR_HIGH - NUM_AREA_DATA_ADDRS).find(|&i| {
+      prg[i..i + 8] == WORLD_ADDR_OFFSETS && prg[i + AREA_ADDR_OFFSETS..i + AREA_ADDR_OFFSETS + 5] == WORLD1_AREAS
+    }).expect("area pointer tables not found, not a Super Mario Bros. ROM");
+    assert!(prg[tables + AREA_DATA_H_OFFSETS..tables + AREA_DATA_H_OFFSETS + 4] == [0x00, 0x03, 0x19, 0x1c], "unexpected AreaDataHOffsets");
+    assert!(prg[tables + ENEMY_ADDR_H_OFFSETS..tables + ENEMY_ADDR_H_OFFSETS + 4] == [0x1f, 0x06, 0x1c, 0x00], "unexpected EnemyAddrHOffsets");
+    Rom { prg, tables }
+  }
+
+  /// PRG ROM from the given CPU address to its end.
+  pub fn bytes_from(&self, addr: u16) -> &[u8] {
+    assert!(addr >= PRG_ROM_START, "address {:#06x} is not in PRG ROM", addr);
+    &self.prg[(addr - PRG_ROM_START) as usize..]
+  }
+  fn table(&self, offset: usize, index: usize) -> u8 {
+    self.prg[self.tables + offset + index]
+  }
+
+  /// AreaPointer of the given area in a world, both 0-based as in WorldNumber and AreaNumber. Areas include the
+  /// automatic walking scenes, e.g. area 1 of world 0 is the pipe intro of 1-2.
+  pub fn area_pointer(&self, world_number: usize, area_number: usize) -> u8 {
+    assert!(world_number < WORLD_ADDR_OFFSETS.len(), "invalid world number {}", world_number);
+    let first = self.table(0, world_number) as usize;
+    let end = if world_number + 1 < WORLD_ADDR_OFFSETS.len() { self.table(0, world_number + 1) as usize } else { NUM_AREAS };
+    assert!(first + area_number < end, "world {} has no area {}", world_number, area_number);
+    self.table(AREA_ADDR_OFFSETS, first + area_number)
+  }
+  /// GetAreaDataAddrs: address of the enemy object data of the area.
+  pub fn enemy_data_addr(&self, area_pointer: u8) -> u16 {
+    let index = self.table(ENEMY_ADDR_H_OFFSETS, AreaType::from_area_pointer(area_pointer).index()) as usize + (area_pointer & 0x1f) as usize;
+    assert!(index < NUM_AREA_DATA_ADDRS, "invalid area pointer {:#04x}", area_pointer);
+    (self.table(ENEMY_DATA_ADDR_HIGH, index) as u16) << 8 | self.table(ENEMY_DATA_ADDR_LOW, index) as u16
+  }
+  /// GetAreaDataAddrs: address of the area object data of the area, starting with its 2 byte header.
+  pub fn area_data_addr(&self, area_pointer: u8) -> u16 {
+    let index = self.table(AREA_DATA_H_OFFSETS, AreaType::from_area_pointer(area_pointer).index()) as usize + (area_pointer & 0x1f) as usize;
+    assert!(index < NUM_AREA_DATA_ADDRS, "invalid area pointer {:#04x}", area_pointer);
+    (self.table(AREA_DATA_ADDR_HIGH, index) as u16) << 8 | self.table(AREA_DATA_ADDR_LOW, index) as u16
+  }
+}
@@ -0,0 +1,106 @@
+use case::SmbSearchCase;
+use emu::{Emu, EmuResult, Input};
+use heuristics::{SearchGoal, min_x_pos_heuristic};
+use ida::{IDA, SearchResult};
+use options::Options;
+use replay::trace;
+use state::{Dist, State};
+use std::marker::PhantomData;
+
+/// Reaches at least the x position the original run reached at the end of the window.
+pub struct ReachWindowXPos<O: Options> {
+  target_x_pos: i32,
+  _options: PhantomData<O>,
+}
+impl<O: Options> SearchGoal for ReachWindowXPos<O> {
+  type Params = i32; // target x_pos
+  fn new(target_x_pos: i32) -> Self { ReachWindowXPos { target_x_pos, _options: PhantomData } }
+  fn distance_to_goal_heuristic(&self, s: &mut State, _: Dist) -> Option<Dist> {
+    Some(min_x_pos_heuristic::<O::Platform>(s, self.target_x_pos))
+  }
+  fn is_goal_state(&self, s: &State, emu_result: &EmuResult) -> bool {
+    *emu_result == EmuResult::Success && s.x_pos >= self.target_x_pos
+  }
+}
+
+/// Window of an existing run in which the search found a faster way to the x position reached at its end.
+pub struct Improvement {
+  pub start_frame: usize,
+  pub original_frames: usize,
+  pub frames: usize,
+  pub inputs: Vec<Input>,
+}
+
+/// Improved windows found by a scan, and the number of inputs the replay of the run got through, which is less than
+/// the length of the run if the replay stopped early.
+pub struct Scan {
+  pub replayed_frames: usize,
+  pub improvements: Vec<Improvement>,
+}
+
+/// Replays the inputs of an existing run (e.g. an imported movie) from s, and searches every window of the given
+/// length (every step frames) for a way to reach the x position of its end in fewer frames. Uses the emulator, state
+/// store and input fetcher of case T, with a state store sized for a single window. The improvements only match the x
+/// position, and need to be checked for whether the rest of the run still syncs.
+pub fn scan<T: SmbSearchCase>(s: State, inputs: &[Input], window: usize, step: usize, search_space_size_hint: usize) -> Scan {
+  assert!(window > 0, "scan window must not be empty");
+  assert!(step > 0, "scan step must be positive");
+  let mut states = vec![s];
+  states.extend(trace(&T::Emu::new(), states[0].clone(), inputs).into_iter().map(|(s, _)| s));
+
+  let mut improvements = vec![];
+  let mut start_frame = 0;
+  while start_frame + window < states.len() {
+    let target_x_pos = states[start_frame + window].x_pos;
+    let result = IDA::<T::StateStore, T::Emu, ReachWindowXPos<T>, T::InputFetcher>::find_first_solution_within(target_x_pos, vec![states[start_frame].clone()], window as Dist - 1, search_space_size_hint);
+    if let SearchResult::Found(_, found_inputs) = result {
+      improvements.push(Improvement { start_frame, original_frames: window, frames: found_inputs.len(), inputs: found_inputs });
+    }
+    start_frame += step;
+  }
+  Scan { replayed_frames: states.len() - 1, improvements }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use case::w44::W44Clip;
+  use emu::parse_rle;
+
+  #[test]
+  fn window_goal_reaches_target_x_pos() {
+    let goal = ReachWindowXPos::<W44Clip>::new(0x1000);
+    let s = ::transition::entrance_state::<W44Clip>(0x1000, 0x1b000, ::state::PlayerState::STANDING);
+    assert!(goal.is_goal_state(&s, &EmuResult::Success));
+    assert!(!goal.is_goal_state(&State { x_pos: 0xff0, ..s }, &EmuResult::Success));
+  }
+
+  #[test]
+  fn scan_finds_no_faster_window_in_clip() {
+    let inputs = parse_rle("[1x L, 1x L|R, 18x NIL, 1x A | DOWN, 1x A, 4x NIL, 2x R, 1x NIL, 13x R, 1x B|R, 1x L, 1x A|R, 3x R, 1x A|R, 3x R] (len: 52)").unwrap();
+    let scan = scan::<W44Clip>(W44Clip::start_states().remove(0), &inputs, 12, 12, 100000);
+    assert_eq!(scan.replayed_frames, inputs.len());
+    assert!(scan.improvements.is_empty());
+  }
+
+  #[test]
+  fn scan_reports_where_replay_stopped() {
+    // falls into the pit behind the start state
+    let inputs = parse_rle("[100x L] (len: 100)").unwrap();
+    let scan = scan::<W44Clip>(W44Clip::start_states().remove(0), &inputs, 80, 1, 10);
+    assert_eq!(scan.replayed_frames, 74);
+    assert!(scan.improvements.is_empty());
+  }
+
+  #[test]
+  #[should_panic(expected = "scan window must not be empty")]
+  fn scan_rejects_empty_window() {
+    scan::<W44Clip>(W44Clip::start_states().remove(0), &[], 0, 1, 10);
+  }
+
+  #[test]
+  #[should_panic(expected = "scan step must be positive")]
+  fn scan_rejects_zero_step() {
+    scan::<W44Clip>(W44Clip::start_states().remove(0), &[], 1, 0, 10);
+  }
+}
@@ -1,6 +1,7 @@
 use bitpack::BitPack;
 use core::array::FixedSizeArray;
-use options::{CoinHandler, Options, Parity, Platform, PlayerSize, Swim, PowerupHandler, RunningTimer, ScrollPos};
+use options::{AreaHandler, CoinHandler, DamageHandler, EnemyHandler, HazardHandler, JumpspringHandler, LiftHandler, Options, Parity, Platform, PlayerSize, Swim, PowerupHandler, RunningTimer, ScrollPos, VineHandler};
+use std::cmp::max;
 use std::hash::{Hash, Hasher};
 use std::marker::PhantomData;
 
@@ -55,6 +56,22 @@ pub struct State {
   pub powerup_block_hit: bool, // only for powerup collection
   pub powerup_collected: bool, // only for powerup collection
   pub parity: u8,
+  pub injured: bool, // only when taking damage
+  pub injury_timer: u8, // only when taking damage
+  pub climb_side_timer: u8, // only when climbing vines
+  pub vine_timer: u8, // only for growing vines
+  pub area: u8, // only for multi-area searches
+  pub area_change_timer: u16, // only for multi-area searches
+  pub object_frame: u16, // only with lifts or hazards
+  pub jumpspring_anim_ctrl: u8, // only with jumpsprings
+  pub jumpspring_high_bounce: bool, // only with jumpsprings
+  pub piranha_plant_y_pos: u8, // only with piranha plants
+  pub piranha_plant_timer: u8, // only with piranha plants
+  pub piranha_plant_rising: bool, // only with piranha plants
+  pub piranha_plant_moving: bool, // only with piranha plants
+  pub bowser_pass_frame: u16, // only with Bowser
+  pub bowser_passed: bool, // only with Bowser
+  pub stomped_enemies: u8, // only with enemies
 }
 impl State {
   pub fn is_on_ground(&self) -> bool { self.player_state == PlayerState::STANDING }
@@ -86,6 +103,22 @@ impl ::std::fmt::Display for State {
     writeln!(f, "  powerup_block_hit: {:?}", self.powerup_block_hit)?;
     writeln!(f, "  powerup_collected: {:?}", self.powerup_collected)?;
     writeln!(f, "  parity: {}", self.parity)?;
+    writeln!(f, "  injured: {:?}", self.injured)?;
+    writeln!(f, "  injury_timer: {}", self.injury_timer)?;
+    writeln!(f, "  climb_side_timer: {}", self.climb_side_timer)?;
+    writeln!(f, "  vine_timer: {}", self.vine_timer)?;
+    writeln!(f, "  area: {}", self.area)?;
+    writeln!(f, "  area_change_timer: {}", self.area_change_timer)?;
+    writeln!(f, "  object_frame: {}", self.object_frame)?;
+    writeln!(f, "  jumpspring_anim_ctrl: {}", self.jumpspring_anim_ctrl)?;
+    writeln!(f, "  jumpspring_high_bounce: {:?}", self.jumpspring_high_bounce)?;
+    writeln!(f, "  piranha_plant_y_pos: {:#x}", self.piranha_plant_y_pos)?;
+    writeln!(f, "  piranha_plant_timer: {}", self.piranha_plant_timer)?;
+    writeln!(f, "  piranha_plant_rising: {:?}", self.piranha_plant_rising)?;
+    writeln!(f, "  piranha_plant_moving: {:?}", self.piranha_plant_moving)?;
+    writeln!(f, "  bowser_pass_frame: {}", self.bowser_pass_frame)?;
+    writeln!(f, "  bowser_passed: {:?}", self.bowser_passed)?;
+    writeln!(f, "  stomped_enemies: {:#b}", self.stomped_enemies)?;
     write!(f, "}}")
   }
 }
@@ -99,12 +132,17 @@ impl StateCompressor for State {
   }
 }
 
+fn object_frame_bits<O: Options>() -> usize {
+  max(max(O::LiftHandler::LIFT_FRAME_BITS, O::HazardHandler::HAZARD_FRAME_BITS), O::EnemyHandler::ENEMY_FRAME_BITS)
+}
+
 pub trait StateArray: FixedSizeArray<u8> + Hash + Clone + PartialEq + Eq {
   fn new() -> Self;
 }
 impl StateArray for [u8; 10] { fn new() -> Self { [0; 10] } }
 impl StateArray for [u8; 11] { fn new() -> Self { [0; 11] } }
 impl StateArray for [u8; 12] { fn new() -> Self { [0; 12] } }
+impl StateArray for [u8; 14] { fn new() -> Self { [0; 14] } }
 pub struct CompressedState<O: Options, A: StateArray> {
   buf: A,
   _options: PhantomData<O>,
@@ -142,12 +180,23 @@ impl<O: Options, A: StateArray> StateCompressor for CompressedState<O, A> {
         + O::ScrollPos::SCROLL_POS_BITS
         + O::Parity::PARITY_BITS
         + O::CoinHandler::COIN_HANDLER_BITS
-        + O::PowerupHandler::POWERUP_HANDLER_BITS) >> 3;
+        + O::PowerupHandler::POWERUP_HANDLER_BITS
+        + O::DamageHandler::DAMAGE_BITS
+        + O::VineHandler::CLIMB_BITS
+        + O::VineHandler::VINE_GROWTH_BITS
+        + O::AreaHandler::AREA_BITS
+        + O::AreaHandler::AREA_CHANGE_TIMER_BITS
+        + object_frame_bits::<O>()
+        + O::JumpspringHandler::JUMPSPRING_BITS
+        + O::HazardHandler::PIRANHA_PLANT_BITS
+        + O::HazardHandler::BOWSER_BITS
+        + O::EnemyHandler::ENEMY_COUNT) >> 3;
     assert!(bytes_needed == bytes_available, "bytes_needed {} != bytes_available {}", bytes_needed, bytes_available);
 
     {
       let mut bitpack = BitPack::<&mut [u8]>::new(buf.as_mut_slice());
       bitpack.write(s.x_pos as u32 >> 4, 16).unwrap();
+      assert!(s.y_pos >= Y_POS_OFFSET as i32 && s.y_pos < (Y_POS_OFFSET + (1 << 16)) as i32, "y_pos {:#x} doesn't fit in 16 bits", s.y_pos);
       bitpack.write(s.y_pos as u32 - Y_POS_OFFSET, 16).unwrap();
       bitpack.write(s.x_spd as u32 >> 2, 13).unwrap();
       bitpack.write(s.y_spd as u32, 12).unwrap();
@@ -183,6 +232,41 @@ impl<O: Options, A: StateArray> StateCompressor for CompressedState<O, A> {
         bitpack.write(if s.powerup_block_hit { 1 } else { 0 }, 1).unwrap();
         bitpack.write(if s.powerup_collected { 1 } else { 0 }, 1).unwrap();
       }
+      if O::DamageHandler::DAMAGE_BITS > 0 {
+        bitpack.write(if s.injured { 1 } else { 0 }, 1).unwrap();
+        bitpack.write(s.injury_timer as u32, 6).unwrap();
+      }
+      if O::VineHandler::CLIMB_BITS > 0 {
+        bitpack.write(s.climb_side_timer as u32, 5).unwrap();
+      }
+      if O::VineHandler::VINE_GROWTH_BITS > 0 {
+        bitpack.write(s.vine_timer as u32, 7).unwrap();
+      }
+      if O::AreaHandler::AREA_BITS > 0 {
+        bitpack.write(s.area as u32, O::AreaHandler::AREA_BITS).unwrap();
+        bitpack.write(s.area_change_timer as u32, O::AreaHandler::AREA_CHANGE_TIMER_BITS).unwrap();
+      }
+      if object_frame_bits::<O>() > 0 {
+        bitpack.write(s.object_frame as u32, object_frame_bits::<O>()).unwrap();
+      }
+      if O::JumpspringHandler::JUMPSPRING_BITS > 0 {
+        bitpack.write(s.jumpspring_anim_ctrl as u32, 3).unwrap();
+        bitpack.write(if s.jumpspring_high_bounce { 1 } else { 0 }, 1).unwrap();
+        bitpack.write((s.y_spd as u32 >> 12) & 3, 2).unwrap();
+      }
+      if O::HazardHandler::PIRANHA_PLANT_BITS > 0 {
+        bitpack.write(s.piranha_plant_y_pos as u32, 5).unwrap();
+        bitpack.write(s.piranha_plant_timer as u32, 7).unwrap();
+        bitpack.write(if s.piranha_plant_rising { 1 } else { 0 }, 1).unwrap();
+        bitpack.write(if s.piranha_plant_moving { 1 } else { 0 }, 1).unwrap();
+      }
+      if O::HazardHandler::BOWSER_BITS > 0 {
+        bitpack.write(if s.bowser_passed { 1 } else { 0 }, 1).unwrap();
+        bitpack.write(s.bowser_pass_frame as u32, O::HazardHandler::BOWSER_BITS - 1).unwrap();
+      }
+      if O::EnemyHandler::ENEMY_COUNT > 0 {
+        bitpack.write(s.stomped_enemies as u32, O::EnemyHandler::ENEMY_COUNT).unwrap();
+      }
     }
     CompressedState { buf, _options: PhantomData }
   }
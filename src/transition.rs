@@ -0,0 +1,253 @@
+use blockbuffer::BlockBuffer;
+use emu::{CLIMB_Y_SPD_UP, Emu, EmuResult, SmbEmu, screen_left_x_pos};
+use emu::inputs::*;
+use options::{Options, Platform};
+use state::{Dir, Dist, PlayerState, State};
+
+// HandlePipeEntry
+const VERTICAL_PIPE_CHANGE_AREA_TIMER: Dist = 0x30;
+// AreaChangeTimerData, depending on whether the left side of the screen is on page 0
+const SIDE_PIPE_CHANGE_AREA_TIMER: [Dist; 2] = [0xa0, 0x34];
+// EnterSidePipe, the player walks right with Player_X_Speed 8 until aligned to a block
+const SIDE_PIPE_X_SPD: i32 = 0x80;
+// InitializeArea sets ColumnSets to 0x0b, AreaParserTaskControl renders one column set per frame until it wraps
+const AREA_PARSER_FRAMES: Dist = 0x0b + 1;
+/// Frames from the area change (ChgAreaMode) until the first frame of the new area is run, one per GameModeTasks and
+/// ScreenRoutines task: InitializeArea, InitScreen, SetupIntermediate, WriteTopStatusLine, WriteBottomStatusLine,
+/// DisplayTimeUp (skipping ResetSpritesAndScreenTimer), DisplayIntermediate (skipping to AreaParserTaskControl
+/// with AltEntranceControl set), AreaParserTaskControl, GetAreaPalette, GetBackgroundColor, GetAlternatePalette1,
+/// DrawTitleScreen (ending ScreenRoutines outside the title screen) and SecondaryGameSetup.
+pub const AREA_LOAD_FRAMES: Dist = 1 + 6 + AREA_PARSER_FRAMES + 4 + 1;
+// PlayerEntrance (EntrMode2) without JoypadOverride: rising out of a pipe
+const PIPE_EXIT_Y_POS: i32 = 0x1f000;
+const PIPE_EXIT_TOP_Y_POS: i32 = 0x19100;
+// PlayerEntrance: no inputs until the player falls to 0x30
+const FALL_ENTRANCE_Y_POS: i32 = 0x3000;
+// Entrance_GameTimerSetup with AltEntranceControl 2 (PlayerStarting_X_Pos, PlayerStarting_Y_Pos)
+const VINE_ENTRANCE_X_POS: i32 = 0x3800;
+const VINE_ENTRANCE_Y_POS: i32 = 0x1f000;
+// PlayerEntrance with JoypadOverride set
+const VINE_ENTRANCE_VINE_HEIGHT: u8 = 0x60;
+const VINE_ENTRANCE_CLIMB_Y_POS: i32 = 0x19900;
+const VINE_ENTRANCE_EXIT_X_POS: i32 = 0x48;
+const VINE_ENTRANCE_MAX_EXIT_FRAMES: Dist = 0x100; // walking 0x10 pixels right takes far fewer frames
+
+/// Player state set up by Entrance_GameTimerSetup at the start of an area.
+pub fn entrance_state<O: Options>(x_pos: i32, y_pos: i32, player_state: PlayerState) -> State {
+  State {
+    x_pos,
+    y_pos,
+    x_spd: 0,
+    y_spd: 0,
+    player_state,
+    moving_dir: Dir::empty(),
+    facing_dir: Dir::RIGHT,
+    v_force: O::Platform::V_FORCE_AREA_INIT,
+    v_force_down: O::Platform::V_FORCE_AREA_INIT,
+    x_spd_abs: 0,
+    running_speed: false,
+    collision_bits: Dir::LR,
+    is_crouching: false,
+    jump_swim_timer: 0,
+    running_timer: 0,
+    left_screen_edge_pos: 0,
+    side_collision_timer: 0,
+    collected_coins: 0,
+    powerup_block_hit: false,
+    powerup_collected: false,
+    parity: 0,
+    injured: false,
+    injury_timer: 0,
+    climb_side_timer: 0,
+    vine_timer: 0,
+    area: 0,
+    area_change_timer: 0,
+    object_frame: 0,
+    jumpspring_anim_ctrl: 0,
+    jumpspring_high_bounce: false,
+    piranha_plant_y_pos: 0,
+    piranha_plant_timer: 0,
+    piranha_plant_rising: false,
+    piranha_plant_moving: false,
+    bowser_pass_frame: 0,
+    bowser_passed: false,
+    stomped_enemies: 0,
+  }
+}
+
+/// Vine_AutoClimb: after touching the top of a vine (StateChangeVineAutoclimb), the player climbs up with collision
+/// detection disabled until reaching 0xe4 above the screen, where the area changes.
+/// Returns the state at the area change and the number of frames until then.
+pub fn vine_autoclimb(mut s: State) -> (State, Dist) {
+  let mut frames = 1; // frame of the area change
+  s.player_state = PlayerState::CLIMBING;
+  s.y_spd = CLIMB_Y_SPD_UP;
+  while s.y_pos >= 0xe400 {
+    s.y_pos += s.y_spd as i32;
+    frames += 1;
+  }
+  (s, frames)
+}
+
+/// PlayerEntrance into an area reached by vine (e.g. a coin heaven): the vine grows from the bottom of the screen, the
+/// player climbs it up with collision detection disabled, and is then moved right until reaching 0x48.
+/// frame_counter is the FrameCounter value in the first frame of the area, which decides when the vine grows.
+/// Returns the first state under player control and the number of frames until then.
+pub fn vine_entrance<O: Options, B: BlockBuffer>(emu: &SmbEmu<O, B>, screen_left_x_pos: i32, mut frame_counter: u8) -> (State, Dist) {
+  let mut s = entrance_state::<O>(screen_left_x_pos + VINE_ENTRANCE_X_POS, VINE_ENTRANCE_Y_POS, PlayerState::CLIMBING);
+  let mut frames = 0;
+  let mut vine_height = 0;
+  while vine_height < VINE_ENTRANCE_VINE_HEIGHT { // VineObjectHandler
+    if frame_counter & 0x2 != 0 { vine_height += 1; }
+    frame_counter = frame_counter.wrapping_add(1);
+    frames += 1;
+  }
+  while s.y_pos >= VINE_ENTRANCE_CLIMB_Y_POS {
+    s.y_pos += CLIMB_Y_SPD_UP as i32;
+    frames += 1;
+  }
+  let exit_start_frames = frames;
+  while (s.x_pos >> 8) & 0xff < VINE_ENTRANCE_EXIT_X_POS {
+    assert!(frames - exit_start_frames < VINE_ENTRANCE_MAX_EXIT_FRAMES, "player is stuck before reaching {:#x} in the vine entrance: {}", VINE_ENTRANCE_EXIT_X_POS, s);
+    s = emu.run_step_nr(s, R);
+    frames += 1;
+  }
+  (s, frames)
+}
+
+/// How the player enters an area, depending on AltEntranceControl and PlayerEntranceCtrl.
+#[derive(Clone,Copy,Debug,Eq,PartialEq)]
+pub enum Entrance {
+  /// Placed at (x_pos, y_pos) and falling without control until reaching 0x30, e.g. into a sub area.
+  Fall(i32, i32),
+  /// Rising out of a vertical pipe at x_pos (AltEntranceControl 2), e.g. after a side pipe.
+  PipeExit(i32),
+  /// Climbing up a vine from the bottom of the screen (AltEntranceControl 2 with JoypadOverride), given the screen
+  /// left x_pos and the FrameCounter in the first frame of the area.
+  Vine(i32, u8),
+}
+
+/// Frames from the frame of a transition EmuResult until the area changes, or None if the result is no transition.
+pub fn exit_area_frames<O: Options>(s: &State, emu_result: &EmuResult) -> Option<Dist> {
+  match emu_result {
+    &EmuResult::StateChangeVerticalPipe(_, _) => Some(VERTICAL_PIPE_CHANGE_AREA_TIMER), // VerticalPipeEntry
+    &EmuResult::StateChangeSidePipe(_, _) => Some(side_pipe_entry_frames::<O>(s)),
+    &EmuResult::StateChangeVineAutoclimb(_, _) => Some(vine_autoclimb(s.clone()).1),
+    _ => None,
+  }
+}
+
+/// EnterSidePipe: the player walks right until aligned to a block. ChangeAreaTimer is set again in every frame the
+/// player is not aligned, and runs out afterwards. If the player is already aligned in the frame of the pipe collision,
+/// the timer is assumed to be set in that frame.
+fn side_pipe_entry_frames<O: Options>(s: &State) -> Dist {
+  let left_screen_page_loc = screen_left_x_pos::<O>(s) >> 8;
+  let change_area_timer = SIDE_PIPE_CHANGE_AREA_TIMER[if left_screen_page_loc == 0 { 0 } else { 1 }];
+
+  let mut x_pos = s.x_pos;
+  let mut frames: Dist = 0;
+  while x_pos & 0xf00 != 0 {
+    x_pos += SIDE_PIPE_X_SPD;
+    frames += 1;
+  }
+  frames.saturating_sub(1) + change_area_timer
+}
+
+/// PlayerEntrance into the area of emu.
+/// Returns the first state under player control and the number of frames from the first frame of the area until then.
+pub fn enter_area<O: Options, B: BlockBuffer>(emu: &SmbEmu<O, B>, entrance: Entrance) -> (State, Dist) {
+  match entrance {
+    Entrance::Fall(x_pos, y_pos) => {
+      let mut s = entrance_state::<O>(x_pos, y_pos, PlayerState::FALLING);
+      let mut frames = 0;
+      while (s.y_pos & 0xff00) < FALL_ENTRANCE_Y_POS {
+        s = emu.run_step_nr(s, NIL);
+        frames += 1;
+      }
+      (s, frames)
+    }
+    Entrance::PipeExit(x_pos) => {
+      let mut s = entrance_state::<O>(x_pos, PIPE_EXIT_Y_POS, PlayerState::STANDING);
+      let mut frames = 0;
+      while s.y_pos >= PIPE_EXIT_TOP_Y_POS {
+        s.y_pos -= 0x100;
+        frames += 1;
+      }
+      (s, frames)
+    }
+    Entrance::Vine(screen_left_x_pos, frame_counter) => vine_entrance(emu, screen_left_x_pos, frame_counter),
+  }
+}
+
+/// Full area transition after a transition EmuResult, into the area of emu.
+/// Returns the first state under player control in the new area and the number of frames after the frame of s.
+pub fn area_transition<O: Options, B: BlockBuffer>(emu: &SmbEmu<O, B>, s: &State, emu_result: &EmuResult, entrance: Entrance) -> Option<(State, Dist)> {
+  exit_area_frames::<O>(s, emu_result).map(|exit_frames| {
+    let (new_s, entrance_frames) = enter_area(emu, entrance);
+    (new_s, exit_frames + AREA_LOAD_FRAMES + entrance_frames)
+  })
+}
+
+#[cfg(test)]
+mod tests {
+  use blockbuffer::BlockBuffer;
+  use blockbuffer::world1::{BB11, BB11Sub};
+  use options::*;
+  use state::PlayerState;
+  use super::*;
+
+  enum VineEntrance {}
+  impl Options for VineEntrance {
+    type CoinHandler = IgnoreCoins;
+    type Platform = NTSC;
+    type PlayerSize = Small;
+    type Swim = NotSwimming;
+    type PowerupHandler = NoPowerups;
+    type RunningTimer = NoRunningTimer;
+    type ScrollPos = NoScrollPos;
+    type Parity = NoParity;
+    type VerticalPipeHandler = IgnoreVerticalPipes;
+    type YPosFractionalBehavior = KeepYPosFractionals;
+  }
+  /// Ground with a wall right of the vine entrance.
+  struct Walled;
+  impl BlockBuffer for Walled {
+    fn new() -> Self { Walled }
+    fn get_block_at(&self, block_x: usize, block_y: usize) -> u8 { if block_y >= 11 || block_x == 5 { 0x54 } else { 0 } }
+  }
+  /// Flag pole in every column.
+  struct FlagPoles;
+  impl BlockBuffer for FlagPoles {
+    fn new() -> Self { FlagPoles }
+    fn get_block_at(&self, _: usize, _: usize) -> u8 { 0x25 }
+  }
+
+  #[test]
+  fn vine_entrance_walks_off_vine() {
+    let (s, frames) = vine_entrance(&SmbEmu::<VineEntrance, BB11>::new(), 0, 0);
+    assert!((s.x_pos >> 8) >= VINE_ENTRANCE_EXIT_X_POS && (s.x_pos >> 8) < VINE_ENTRANCE_EXIT_X_POS + 8);
+    assert!(s.y_pos < VINE_ENTRANCE_CLIMB_Y_POS + 0x2000 && s.player_state != PlayerState::CLIMBING);
+    // the vine grows by a pixel every other frame, aligned to FrameCounter bit 1
+    let (_, later_frames) = vine_entrance(&SmbEmu::<VineEntrance, BB11>::new(), 0, 1);
+    assert_eq!(later_frames + 1, frames);
+  }
+
+  #[test]
+  #[should_panic(expected = "stuck")]
+  fn vine_entrance_stuck_at_wall() {
+    vine_entrance(&SmbEmu::<VineEntrance, Walled>::new(), 0, 0);
+  }
+
+  #[test]
+  fn fall_entrance_into_sub_area() {
+    let (s, frames) = enter_area(&SmbEmu::<VineEntrance, BB11Sub>::new(), Entrance::Fall(0x1800, 0x12000));
+    assert!((s.y_pos & 0xff00) >= FALL_ENTRANCE_Y_POS && (s.y_pos & 0xff00) < FALL_ENTRANCE_Y_POS + 0x1000);
+    assert!(frames > 0 && s.player_state == PlayerState::FALLING);
+  }
+
+  #[test]
+  #[should_panic(expected = "emu_result == EmuResult::Success")]
+  fn fall_entrance_into_flag_pole() {
+    enter_area(&SmbEmu::<VineEntrance, FlagPoles>::new(), Entrance::Fall(0x1800, 0x12000));
+  }
+}